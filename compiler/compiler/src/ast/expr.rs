@@ -1,83 +1,425 @@
-use super::token::*;
+use super::{
+    pattern::Pattern,
+    stmt::{FnDecl, Stmt},
+    token::*,
+    types::TypeExpr,
+};
+use crate::position::Position;
+use ascii::AsciiString;
+use std::rc::Rc;
 
 pub enum Expr {
+    Array {
+        elements: Vec<Self>,
+        pos: Position,
+    },
     Assign {
-        ident: Token,
+        target: Box<Self>,
         value: Box<Self>,
+        pos: Position,
     },
     Binary {
         left: Box<Self>,
         operator: Operator,
         right: Box<Self>,
+        pos: Position,
+    },
+    Block {
+        stmts: Vec<Stmt>,
+        tail: Option<Box<Self>>,
+        pos: Position,
+    },
+    Break {
+        label: Option<AsciiString>,
+        value: Option<Box<Self>>,
+        pos: Position,
     },
-    Function {
+    Call {
         callee: Box<Self>,
-        param: Operator,
         arguments: Vec<Self>,
+        pos: Position,
+    },
+    // `x as u8`, `pos` is the `as`
+    Cast {
+        expresion: Box<Self>,
+        ty: TypeExpr,
+        pos: Position,
+    },
+    // `|x, y| x + y`, a `move` closure captures copies of the variables it can see
+    Closure {
+        decl: Rc<FnDecl>,
+        moves: bool,
+    },
+    Continue {
+        label: Option<AsciiString>,
+        pos: Position,
+    },
+    // `safe` is set for `object?.name`, which is `?` when the object is
+    Field {
+        object: Box<Self>,
+        name: AsciiString,
+        safe: bool,
+        pos: Position,
     },
     Group {
         expresion: Box<Self>,
     },
+    Index {
+        object: Box<Self>,
+        index: Box<Self>,
+        pos: Position,
+    },
+    If {
+        condition: Box<Self>,
+        then_branch: Box<Self>,
+        else_branch: Option<Box<Self>>,
+        pos: Position,
+    },
     Literal {
         value: Literal,
+        pos: Position,
     },
     Logical {
         left: Box<Self>,
         operator: Operator,
         right: Box<Self>,
+        pos: Position,
+    },
+    Map {
+        entries: Vec<(Self, Self)>,
+        pos: Position,
+    },
+    Match {
+        scrutinee: Box<Self>,
+        arms: Vec<Arm>,
+        pos: Position,
+    },
+    Method {
+        receiver: Box<Self>,
+        name: AsciiString,
+        arguments: Vec<Self>,
+        safe: bool,
+        pos: Position,
+    },
+    Loop {
+        label: Option<AsciiString>,
+        body: Box<Self>,
+        pos: Position,
+    },
+    LoopIn {
+        label: Option<AsciiString>,
+        pattern: Pattern,
+        iterable: Box<Self>,
+        body: Box<Self>,
+        pos: Position,
+    },
+    Path {
+        segments: Vec<AsciiString>,
+        pos: Position,
+    },
+    Range {
+        start: Option<Box<Self>>,
+        end: Option<Box<Self>>,
+        inclusive: bool,
+        pos: Position,
+    },
+    Return {
+        value: Option<Box<Self>>,
+        pos: Position,
+    },
+    // `Point { .. }` or `Shape::Rect { .. }`
+    Struct {
+        path: Vec<AsciiString>,
+        fields: Vec<(AsciiString, Self)>,
+        pos: Position,
+    },
+    Tuple {
+        elements: Vec<Self>,
+        pos: Position,
+    },
+    // `pair.0`
+    TupleIndex {
+        object: Box<Self>,
+        index: usize,
+        safe: bool,
+        pos: Position,
+    },
+    // `max::<i32>`, the type arguments of a generic function, struct or variant
+    TypeArgs {
+        expresion: Box<Self>,
+        args: Vec<TypeExpr>,
+        pos: Position,
     },
     Unary {
         operator: Operator,
         expresion: Side<Box<Self>>,
+        pos: Position,
     },
     Variable {
-        name: Token,
+        name: AsciiString,
+        pos: Position,
     },
 }
 
-#[allow(dead_code)]
+// `pattern if guard => body`
+pub struct Arm {
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: Expr,
+}
+
 impl Expr {
-    pub fn assign(ident: Token, value: Box<Self>) -> Self {
-        Self::Assign { ident, value }
+    pub fn array(elements: Vec<Self>, pos: Position) -> Self {
+        Self::Array { elements, pos }
     }
-    pub fn binary(left: Box<Self>, operator: Operator, right: Box<Self>) -> Self {
+    pub fn assign(target: Box<Self>, value: Box<Self>, pos: Position) -> Self {
+        Self::Assign { target, value, pos }
+    }
+    pub fn binary(left: Box<Self>, operator: Operator, right: Box<Self>, pos: Position) -> Self {
         Self::Binary {
             left,
             operator,
             right,
+            pos,
         }
     }
-    pub fn function(callee: Box<Self>, param: Operator, arguments: Vec<Self>) -> Self {
-        Self::Function {
+    pub fn block(stmts: Vec<Stmt>, tail: Option<Box<Self>>, pos: Position) -> Self {
+        Self::Block { stmts, tail, pos }
+    }
+    pub fn brk(label: Option<AsciiString>, value: Option<Box<Self>>, pos: Position) -> Self {
+        Self::Break { label, value, pos }
+    }
+    pub fn call(callee: Box<Self>, arguments: Vec<Self>, pos: Position) -> Self {
+        Self::Call {
             callee,
-            param,
             arguments,
+            pos,
+        }
+    }
+    pub fn cast(expresion: Box<Self>, ty: TypeExpr, pos: Position) -> Self {
+        Self::Cast { expresion, ty, pos }
+    }
+    pub fn closure(decl: FnDecl, moves: bool) -> Self {
+        Self::Closure {
+            decl: Rc::new(decl),
+            moves,
+        }
+    }
+    pub fn cont(label: Option<AsciiString>, pos: Position) -> Self {
+        Self::Continue { label, pos }
+    }
+    pub fn field(object: Box<Self>, name: AsciiString, safe: bool, pos: Position) -> Self {
+        Self::Field {
+            object,
+            name,
+            safe,
+            pos,
         }
     }
     pub fn group(expresion: Box<Self>) -> Self {
         Self::Group { expresion }
     }
-    pub fn literal(value: Literal) -> Self {
-        Self::Literal { value }
+    pub fn index(object: Box<Self>, index: Box<Self>, pos: Position) -> Self {
+        Self::Index { object, index, pos }
+    }
+    pub fn if_(
+        condition: Box<Self>,
+        then_branch: Box<Self>,
+        else_branch: Option<Box<Self>>,
+        pos: Position,
+    ) -> Self {
+        Self::If {
+            condition,
+            then_branch,
+            else_branch,
+            pos,
+        }
     }
-    pub fn logical(left: Box<Self>, operator: Operator, right: Box<Self>) -> Self {
+    pub fn literal(value: Literal, pos: Position) -> Self {
+        Self::Literal { value, pos }
+    }
+    pub fn logical(left: Box<Self>, operator: Operator, right: Box<Self>, pos: Position) -> Self {
         Self::Logical {
             left,
             operator,
             right,
+            pos,
+        }
+    }
+    pub fn map(entries: Vec<(Self, Self)>, pos: Position) -> Self {
+        Self::Map { entries, pos }
+    }
+    pub fn match_(scrutinee: Box<Self>, arms: Vec<Arm>, pos: Position) -> Self {
+        Self::Match {
+            scrutinee,
+            arms,
+            pos,
+        }
+    }
+    pub fn method(
+        receiver: Box<Self>,
+        name: AsciiString,
+        arguments: Vec<Self>,
+        safe: bool,
+        pos: Position,
+    ) -> Self {
+        Self::Method {
+            receiver,
+            name,
+            arguments,
+            safe,
+            pos,
+        }
+    }
+    pub fn loop_(label: Option<AsciiString>, body: Box<Self>, pos: Position) -> Self {
+        Self::Loop { label, body, pos }
+    }
+    pub fn loop_in(
+        label: Option<AsciiString>,
+        pattern: Pattern,
+        iterable: Box<Self>,
+        body: Box<Self>,
+        pos: Position,
+    ) -> Self {
+        Self::LoopIn {
+            label,
+            pattern,
+            iterable,
+            body,
+            pos,
+        }
+    }
+    pub fn path(segments: Vec<AsciiString>, pos: Position) -> Self {
+        Self::Path { segments, pos }
+    }
+    pub fn range(
+        start: Option<Box<Self>>,
+        end: Option<Box<Self>>,
+        inclusive: bool,
+        pos: Position,
+    ) -> Self {
+        Self::Range {
+            start,
+            end,
+            inclusive,
+            pos,
         }
     }
-    pub fn unary(operator: Operator, expresion: Side<Box<Self>>) -> Self {
+    pub fn ret(value: Option<Box<Self>>, pos: Position) -> Self {
+        Self::Return { value, pos }
+    }
+    pub fn struct_(
+        path: Vec<AsciiString>,
+        fields: Vec<(AsciiString, Self)>,
+        pos: Position,
+    ) -> Self {
+        Self::Struct { path, fields, pos }
+    }
+    pub fn tuple(elements: Vec<Self>, pos: Position) -> Self {
+        Self::Tuple { elements, pos }
+    }
+    pub fn tuple_index(object: Box<Self>, index: usize, safe: bool, pos: Position) -> Self {
+        Self::TupleIndex {
+            object,
+            index,
+            safe,
+            pos,
+        }
+    }
+    pub fn type_args(expresion: Box<Self>, args: Vec<TypeExpr>, pos: Position) -> Self {
+        Self::TypeArgs {
+            expresion,
+            args,
+            pos,
+        }
+    }
+    pub fn unary(operator: Operator, expresion: Side<Box<Self>>, pos: Position) -> Self {
         Self::Unary {
             operator,
             expresion,
+            pos,
         }
     }
-    pub fn variable(name: Token) -> Self {
-        Self::Variable { name }
+    pub fn variable(name: AsciiString, pos: Position) -> Self {
+        Self::Variable { name, pos }
+    }
+
+    pub fn pos(&self) -> &Position {
+        match self {
+            Self::Group { expresion } | Self::TypeArgs { expresion, .. } => expresion.pos(),
+            Self::Closure { decl, .. } => &decl.pos,
+            Self::Array { pos, .. }
+            | Self::Assign { pos, .. }
+            | Self::Binary { pos, .. }
+            | Self::Block { pos, .. }
+            | Self::Break { pos, .. }
+            | Self::Call { pos, .. }
+            | Self::Cast { pos, .. }
+            | Self::Continue { pos, .. }
+            | Self::Field { pos, .. }
+            | Self::If { pos, .. }
+            | Self::Index { pos, .. }
+            | Self::Literal { pos, .. }
+            | Self::Logical { pos, .. }
+            | Self::Loop { pos, .. }
+            | Self::LoopIn { pos, .. }
+            | Self::Map { pos, .. }
+            | Self::Match { pos, .. }
+            | Self::Method { pos, .. }
+            | Self::Path { pos, .. }
+            | Self::Range { pos, .. }
+            | Self::Struct { pos, .. }
+            | Self::Tuple { pos, .. }
+            | Self::TupleIndex { pos, .. }
+            | Self::Return { pos, .. }
+            | Self::Unary { pos, .. }
+            | Self::Variable { pos, .. } => pos,
+        }
+    }
+
+    // Where the source of the expression begins, `pos` of calls and binary
+    // expressions is their operator
+    pub fn start(&self) -> &Position {
+        match self {
+            Self::Assign { target: left, .. }
+            | Self::Binary { left, .. }
+            | Self::Logical { left, .. } => left.start(),
+            Self::Call { callee: object, .. }
+            | Self::Cast {
+                expresion: object, ..
+            }
+            | Self::Field { object, .. }
+            | Self::Index { object, .. }
+            | Self::Method {
+                receiver: object, ..
+            }
+            | Self::TupleIndex { object, .. }
+            | Self::Unary {
+                expresion: Side::Left(object),
+                ..
+            } => object.start(),
+            Self::Range {
+                start: Some(start), ..
+            } => start.start(),
+            expr => expr.pos(),
+        }
+    }
+
+    // Block-like expressions end statements without a semicolon
+    pub fn is_block_like(&self) -> bool {
+        matches!(
+            self,
+            Self::Block { .. }
+                | Self::If { .. }
+                | Self::Loop { .. }
+                | Self::LoopIn { .. }
+                | Self::Match { .. }
+        )
     }
 }
 
+/// Which side of the operator the operand sits on, `Right` for prefix
+/// operators like `-x` and `Left` for postfix ones like `x++`.
 pub enum Side<T> {
     Left(T),
     Right(T),
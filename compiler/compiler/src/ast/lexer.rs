@@ -10,38 +10,64 @@ use std::str::Chars;
 pub struct Lexer<'a> {
     pos: Position,
     chars: Peekable<Chars<'a>>,
+    // Right after a `.` numbers are tuple indices, so `t.0.1` is not `t.` followed by `0.1`
+    after_dot: bool,
+    // Right after a `-` that negates, a number out of range is read with the
+    // sign, the least value of a signed type like `-128i8` only fits negated
+    after_minus: bool,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(file: &'a str, source: &'a str) -> Self {
+        Self::at_line(file, source, 1)
+    }
+
+    // The source is numbered from `line`, which keeps the positions of the
+    // lines of the REPL apart
+    pub fn at_line(file: &'a str, source: &'a str, line: u16) -> Self {
         Self {
-            pos: Position::new(file),
+            pos: Position::at_line(file, line),
             chars: source.chars().peekable(),
+            after_dot: false,
+            after_minus: false,
         }
     }
 
-    pub fn tokenize(&mut self) -> Result<Vec<Token>> {
+    pub fn tokenize(&mut self) -> Result<Vec<(Token, Position)>> {
         let mut tokens = Vec::new();
         loop {
+            // Skip comments and whitespace
+            self.skip_comments()?;
+            let pos = self.pos.clone();
             let token = self.token()?;
+            self.after_dot = token == Token::Operator(Operator::Dot);
+            self.after_minus = token == Token::Operator(Operator::Minus)
+                && !tokens.last().is_some_and(|(token, _)| ends_operand(token));
+            if let Token::Literal(Literal::Number(n)) = &token {
+                // The number took the sign, it starts at the `-`
+                if n.is_negative() {
+                    let (_, pos) = tokens.pop().unwrap();
+                    tokens.push((token, pos));
+                    continue;
+                }
+            }
             if let Token::Eof = token {
-                tokens.push(Token::Eof);
+                tokens.push((Token::Eof, pos));
                 break;
             } else {
-                tokens.push(token);
+                tokens.push((token, pos));
             }
         }
         Ok(tokens)
     }
 
     fn next(&mut self) -> Option<char> {
-        self.chars.next().map(|c| {
+        self.chars.next().inspect(|&c| {
             if c == '\n' {
                 self.pos.next(Next::Line)
             } else {
                 self.pos.next(Next::Column)
             }
-            c
         })
     }
 
@@ -49,17 +75,26 @@ impl<'a> Lexer<'a> {
         self.chars.peek().copied()
     }
 
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.chars.clone().nth(n)
+    }
+
     fn number_str(&mut self) -> String {
         let mut number = String::new();
         let mut isint = true;
         while let Some(ch) = self.peek() {
             if ch == '.' || ch == '_' || ch.is_ascii_digit() {
                 if ch == '.' {
-                    if !isint {
+                    // `0..n` is a range, not the float `0.`
+                    if !isint
+                        || self.after_dot
+                        || !self.peek_nth(1).is_some_and(|c| c.is_ascii_digit())
+                    {
                         break;
                     }
                     isint = false;
                 } else if ch == '_' {
+                    self.next();
                     continue;
                 }
                 number.push(ch);
@@ -73,55 +108,21 @@ impl<'a> Lexer<'a> {
         number
     }
 
-    fn numeric(&mut self) -> Result<Number> {
+    // Numbers without a suffix are typed by the checker
+    fn numeric(&mut self) -> Result<Literal> {
         let value = self.number_str();
-        let mut suffix = String::new();
-        if let Some(i) = value.find(|c: char| c.is_ascii_alphabetic()) {
-            suffix = value.clone().drain(i..).collect::<String>();
-            if !suffix.starts_with(|c| matches!(c, 'i' | 'u' | 'f')) {
-                return Error::UnknownIdent(suffix).throw(&self.pos);
-            }
-        }
-        let number = value.trim_end_matches(&suffix).trim();
-
-        let float_overflow = |_| {
-            Error::NumOverFlow(
-                String::from(number),
-                String::from(if suffix.is_empty() { "f64" } else { &suffix }),
-            )
+        let Some(i) = value.find(|c: char| c.is_ascii_alphabetic()) else {
+            return Ok(Literal::Unsuffixed(Unsuffixed::new(value)));
         };
-
-        if number.contains('.') {
-            return Ok(match &*suffix {
-                "f32" => Number::F32(number.parse().map_err(float_overflow)?),
-                "" | "f" | "f64" => Number::F64(number.parse().map_err(float_overflow)?),
-                _ => return Error::InvalidSuffix(suffix).throw(&self.pos),
-            });
+        let (number, suffix) = value.split_at(i);
+        let parsed = Number::parse(number, suffix).or_else(|error| match self.after_minus {
+            true => Number::parse(&format!("-{number}"), suffix).map_err(|_| error),
+            false => Err(error),
+        });
+        match parsed {
+            Ok(number) => Ok(Literal::Number(number)),
+            Err(error) => error.throw(&self.pos),
         }
-        let int_overflow = |_| {
-            Error::NumOverFlow(
-                String::from(number),
-                String::from(if suffix.is_empty() { "i32" } else { &suffix }),
-            )
-        };
-
-        Ok(match &*suffix {
-            "i8" => Number::I8(number.parse().map_err(int_overflow)?),
-            "i16" => Number::I16(number.parse().unwrap()),
-            "" | "i32" => Number::I32(number.parse().map_err(int_overflow)?),
-            "i64" => Number::I64(number.parse().map_err(int_overflow)?),
-            "i128" => Number::I128(number.parse().map_err(int_overflow)?),
-            "isize" => Number::ISize(number.parse().map_err(int_overflow)?),
-            "u8" => Number::U8(number.parse().map_err(int_overflow)?),
-            "u16" => Number::U16(number.parse().map_err(int_overflow)?),
-            "u" | "u32" => Number::U32(number.parse().map_err(int_overflow)?),
-            "u64" => Number::U64(number.parse().map_err(int_overflow)?),
-            "u128" => Number::U128(number.parse().map_err(int_overflow)?),
-            "usize" => Number::USize(number.parse().map_err(int_overflow)?),
-            "f32" => Number::F32(number.parse().map_err(float_overflow)?),
-            "f" | "f64" => Number::F64(number.parse().map_err(float_overflow)?),
-            _ => return Error::InvalidSuffix(suffix).throw(&self.pos),
-        })
     }
 
     fn ident_str(&mut self) -> AsciiString {
@@ -149,6 +150,13 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    // `'outer` is a label while `'o'` is a character
+    fn is_label(&self) -> bool {
+        self.peek_nth(1)
+            .is_some_and(|c| c == '_' || c.is_ascii_alphabetic())
+            && self.peek_nth(2) != Some('\'')
+    }
+
     fn chars(&mut self) -> Result<Token> {
         self.next();
         let c;
@@ -212,10 +220,7 @@ impl<'a> Lexer<'a> {
 
         // Consume the opening double quote
         self.next();
-        let mut value = String::from(match self.peek() {
-            Some(c) => c,
-            None => return Ok(Token::Eof),
-        });
+        let mut value = String::new();
 
         while let Some(ch) = self.next() {
             match ch {
@@ -266,12 +271,15 @@ impl<'a> Lexer<'a> {
         let mut comment: Option<Position> = None;
 
         loop {
-            while self.peek().is_some_and(|c| matches!(c, ' ' | '\t' | '\n')) {
+            while self
+                .peek()
+                .is_some_and(|c| matches!(c, ' ' | '\t' | '\r' | '\n'))
+            {
                 self.next();
             }
 
             if let Some(c) = self.peek() {
-                if c == '/' {
+                if c == '/' && matches!(self.peek_nth(1), Some('/' | '*')) {
                     self.next();
                     match self.peek() {
                         Some('/') => {
@@ -333,52 +341,62 @@ impl<'a> Lexer<'a> {
         Ok(())
     }
 
-    fn token(&mut self) -> Result<Token> {
-        // Skip comments and whitespace
-        self.skip_comments()?;
+    fn operator(&mut self, c: char) -> Operator {
+        let compound = match (c, self.peek()) {
+            (':', Some(':')) => Some(Operator::Acceses),
+            ('+', Some('+')) => Some(Operator::Increment),
+            ('-', Some('-')) => Some(Operator::Decrement),
+            ('-', Some('>')) => Some(Operator::Arrow),
+            ('!', Some('=')) => Some(Operator::NotEqualTo),
+            ('=', Some('=')) => Some(Operator::EqualTo),
+            ('=', Some('>')) => Some(Operator::FatArrow),
+            ('<', Some('=')) => Some(Operator::LessThanOrEqualTo),
+            ('>', Some('=')) => Some(Operator::GreaterThanOrEqualTo),
+            ('.', Some('.')) => {
+                self.next();
+                if self.peek() == Some('=') {
+                    Some(Operator::RangeInclusive)
+                } else {
+                    return Operator::Range;
+                }
+            }
+            ('&', Some('&')) => Some(Operator::And),
+            ('|', Some('|')) => Some(Operator::Or),
+            ('?', Some('.')) => Some(Operator::SafeAccess),
+            ('?', Some('?')) => Some(Operator::Coalesce),
+            _ => None,
+        };
 
+        match compound {
+            Some(operator) => {
+                self.next();
+                operator
+            }
+            None => Operator::from(c),
+        }
+    }
+
+    fn token(&mut self) -> Result<Token> {
         let c = match self.peek() {
             Some(c) => c,
             None => return Ok(Token::Eof),
         };
 
         Ok(match c {
+            // Handle labels, character and string literals
+            '\'' if self.is_label() => {
+                self.next();
+                Token::Label(self.ident_str())
+            }
+            '\'' => self.chars()?,
+            '"' => self.string()?,
             // Handle operators
-            '`' | '!'..='/' | ':'..='@' | '['..='^' | '{'..='~' => match self.peek() {
-                Some(ch) => {
-                    if c == '?' {
-                        Token::Literal(Literal::Null)
-                    } else if c == '\'' {
-                        self.chars().unwrap()
-                    } else if c == '"' {
-                        self.string().unwrap()
-                    } else if c == ':' && ch == ':' {
-                        self.next();
-                        Token::Operator(Operator::Acceses)
-                    } else if matches!(c, '+' | '-') && matches!(ch, '+' | '-') {
-                        self.next();
-                        Token::Operator(if c == '+' {
-                            Operator::Increment
-                        } else {
-                            Operator::Decrement
-                        })
-                    } else if matches!(c, '!' | '=' | '<' | '>') && ch == '=' {
-                        self.next();
-                        Token::Operator(match c {
-                            '!' => Operator::NotEqualTo,
-                            '=' => Operator::EqualTo,
-                            '<' => Operator::LessThanOrEqualTo,
-                            '>' => Operator::GreaterThanOrEqualTo,
-                            _ => unimplemented!(),
-                        })
-                    } else {
-                        Token::Operator(Operator::from(c))
-                    }
-                }
-                None => return Ok(Token::Eof),
-            },
+            '`' | '!'..='/' | ':'..='@' | '['..='^' | '{'..='~' => {
+                self.next();
+                Token::Operator(self.operator(c))
+            }
             // Handle numbers
-            '0'..='9' => Token::Literal(Literal::Number(self.numeric()?)),
+            '0'..='9' => Token::Literal(self.numeric()?),
             // Handle identifiers and keywords
             '_' | 'a'..='z' | 'A'..='Z' => self.identifier(),
             _ => {
@@ -387,3 +405,54 @@ impl<'a> Lexer<'a> {
         })
     }
 }
+
+// Whether a token can end an operand, so a `-` after it subtracts
+fn ends_operand(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Literal(_)
+            | Token::Identifier(_)
+            | Token::Operator(
+                Operator::ParenClose
+                    | Operator::BracketClose
+                    | Operator::CurlyBraceClose
+                    | Operator::Question
+            )
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Lexer, Literal, Number, Operator, Token};
+
+    fn tokens(source: &str) -> Vec<Token> {
+        let tokens = Lexer::new("test", source).tokenize().unwrap();
+        tokens.into_iter().map(|(token, _)| token).collect()
+    }
+
+    #[test]
+    fn least_signed_numbers_take_their_sign() {
+        let number = |n| Token::Literal(Literal::Number(n));
+        assert_eq!(tokens("-128i8"), [number(Number::I8(-128)), Token::Eof]);
+        assert_eq!(tokens("(-128i8)")[1], number(Number::I8(-128)));
+        // Numbers in range keep the `-` as an operator, the parser folds it
+        assert_eq!(tokens("-127i8")[0], Token::Operator(Operator::Minus));
+        assert!(Lexer::new("test", "1 -128i8").tokenize().is_err());
+        assert!(Lexer::new("test", "-129i8").tokenize().is_err());
+    }
+
+    #[test]
+    fn numbers_after_a_dot_index_tuples() {
+        let dots = |source| {
+            let tokens = tokens(source);
+            let dots = tokens
+                .iter()
+                .filter(|token| **token == Token::Operator(Operator::Dot));
+            (dots.count(), tokens.len())
+        };
+        // `t`, `.`, `0`, `.`, `1` and the end
+        assert_eq!(dots("t.0.1"), (2, 6));
+        assert_eq!(dots("(1, 2).1"), (1, 8));
+        assert_eq!(dots("1.5"), (0, 2));
+    }
+}
@@ -1,3 +1,7 @@
 pub mod expr;
 pub mod lexer;
+pub mod parser;
+pub mod pattern;
+pub mod stmt;
 pub mod token;
+pub mod types;
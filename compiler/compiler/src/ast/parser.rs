@@ -0,0 +1,1371 @@
+use super::{
+    expr::{Arm, Expr, Side},
+    pattern::Pattern,
+    stmt::{
+        Allow, ConstDecl, EnumDecl, Field, FnDecl, Generic, Param, Payload, Stmt, StructDecl,
+        TraitDecl, TraitMethod, Variant,
+    },
+    token::*,
+    types::TypeExpr,
+};
+use crate::{error::*, position::Position};
+use ascii::AsciiString;
+use std::{cell::OnceCell, mem, rc::Rc};
+
+pub struct Parser {
+    tokens: Vec<(Token, Position)>,
+    current: usize,
+    functions: usize,
+    // The labels of the enclosing loops, innermost last
+    loops: Vec<Option<AsciiString>>,
+    // Struct literals are not allowed right before the block of an `if` or `loop`
+    no_struct: bool,
+    // The `#[allow(..)]` attributes parsed so far
+    allows: Vec<Allow>,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<(Token, Position)>) -> Self {
+        Self {
+            tokens,
+            current: 0,
+            functions: 0,
+            loops: Vec::new(),
+            no_struct: false,
+            allows: Vec::new(),
+        }
+    }
+
+    pub fn parse(&mut self) -> Result<Vec<Stmt>> {
+        let mut stmts = Vec::new();
+        while !self.is_at_end() {
+            stmts.push(self.item()?);
+        }
+        Ok(stmts)
+    }
+
+    // The attributes of the statements parsed, which are not part of them
+    pub fn allows(&mut self) -> Vec<Allow> {
+        mem::take(&mut self.allows)
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.current].0
+    }
+
+    fn pos(&self) -> Position {
+        self.tokens[self.current].1.clone()
+    }
+
+    fn is_at_end(&self) -> bool {
+        matches!(self.peek(), Token::Eof)
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        if !self.is_at_end() {
+            self.current += 1;
+        }
+        token
+    }
+
+    fn check(&self, operator: Operator) -> bool {
+        matches!(self.peek(), Token::Operator(op) if *op == operator)
+    }
+
+    fn check_keyword(&self, keyword: Keyword) -> bool {
+        matches!(self.peek(), Token::Keyword(kw) if *kw == keyword)
+    }
+
+    fn matches(&mut self, operator: Operator) -> bool {
+        if self.check(operator) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn matches_keyword(&mut self, keyword: Keyword) -> bool {
+        if self.check_keyword(keyword) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn unexpected<T>(&self, expected: &str) -> Result<T> {
+        let expected = String::from(expected);
+        match self.peek() {
+            Token::Eof => Error::UnexpectedEndOfFile(expected).throw(&self.pos()),
+            token => Error::UnexpectedToken(token.to_string(), expected).throw(&self.pos()),
+        }
+    }
+
+    fn consume(&mut self, operator: Operator) -> Result<Position> {
+        let pos = self.pos();
+        if self.matches(operator) {
+            Ok(pos)
+        } else {
+            self.unexpected(&format!("`{operator}`"))
+        }
+    }
+
+    fn identifier(&mut self, expected: &str) -> Result<(AsciiString, Position)> {
+        let pos = self.pos();
+        match self.peek() {
+            Token::Identifier(ident) => {
+                let ident = ident.clone();
+                self.advance();
+                Ok((ident, pos))
+            }
+            _ => self.unexpected(expected),
+        }
+    }
+
+    fn is_declaration(&self) -> bool {
+        self.is_attribute()
+            || [
+                Keyword::Fn,
+                Keyword::Let,
+                Keyword::Const,
+                Keyword::Struct,
+                Keyword::Enum,
+                Keyword::Impl,
+                Keyword::Trait,
+                Keyword::Pub,
+                Keyword::Use,
+            ]
+            .iter()
+            .any(|&keyword| self.check_keyword(keyword))
+    }
+
+    // `#[`, where `#{` starts a map
+    fn is_attribute(&self) -> bool {
+        self.check(Operator::Hash)
+            && matches!(
+                self.tokens.get(self.current + 1),
+                Some((Token::Operator(Operator::BracketOpen), _))
+            )
+    }
+
+    // `#[allow(a, b)]` before the statement `parse` parses, which can have
+    // attributes of its own
+    fn attribute(&mut self, parse: fn(&mut Self) -> Result<Stmt>) -> Result<Stmt> {
+        let start = self.pos();
+        self.advance();
+        self.advance();
+        if !matches!(self.peek(), Token::Identifier(name) if name == "allow") {
+            return self.unexpected("`allow`");
+        }
+        self.advance();
+        self.consume(Operator::ParenOpen)?;
+        let mut lints = Vec::new();
+        while !self.check(Operator::ParenClose) {
+            lints.push(self.identifier("a lint name")?);
+            if !self.matches(Operator::Comma) {
+                break;
+            }
+        }
+        self.consume(Operator::ParenClose)?;
+        self.consume(Operator::BracketClose)?;
+        let stmt = parse(self)?;
+        let end = self.tokens[self.current - 1].1.clone();
+        self.allows.push(Allow { lints, start, end });
+        Ok(stmt)
+    }
+
+    // Imports and exports are only allowed at the top level of a module
+    fn item(&mut self) -> Result<Stmt> {
+        if self.is_attribute() {
+            self.attribute(Self::item)
+        } else if self.matches_keyword(Keyword::Use) {
+            self.use_declaration()
+        } else if self.matches_keyword(Keyword::Pub) {
+            if self.matches_keyword(Keyword::Fn) {
+                Ok(Stmt::function(self.function(true)?))
+            } else if self.matches_keyword(Keyword::Const) {
+                self.const_declaration(true)
+            } else if self.matches_keyword(Keyword::Struct) {
+                self.struct_declaration(true)
+            } else if self.matches_keyword(Keyword::Enum) {
+                self.enum_declaration(true)
+            } else if self.matches_keyword(Keyword::Trait) {
+                self.trait_declaration(true)
+            } else {
+                self.unexpected("`fn`, `const`, `struct`, `enum` or `trait`")
+            }
+        } else {
+            self.declaration()
+        }
+    }
+
+    fn declaration(&mut self) -> Result<Stmt> {
+        if self.is_attribute() {
+            return self.attribute(Self::declaration);
+        }
+        if let Token::Keyword(keyword @ (Keyword::Pub | Keyword::Use)) = *self.peek() {
+            let place = String::from("the top level of a module");
+            return Error::Misplaced(keyword.to_string(), place).throw(&self.pos());
+        }
+        if self.matches_keyword(Keyword::Fn) {
+            Ok(Stmt::function(self.function(false)?))
+        } else if self.matches_keyword(Keyword::Const) {
+            self.const_declaration(false)
+        } else if self.matches_keyword(Keyword::Struct) {
+            self.struct_declaration(false)
+        } else if self.matches_keyword(Keyword::Enum) {
+            self.enum_declaration(false)
+        } else if self.matches_keyword(Keyword::Impl) {
+            self.impl_block()
+        } else if self.matches_keyword(Keyword::Trait) {
+            self.trait_declaration(false)
+        } else if self.matches_keyword(Keyword::Let) {
+            self.let_declaration()
+        } else {
+            self.statement()
+        }
+    }
+
+    // `use a::b::c;` or `use a::b::{c, d};`, the last segment before the
+    // items names the module
+    fn use_declaration(&mut self) -> Result<Stmt> {
+        let (module, pos) = self.identifier("a module name")?;
+        let mut path = vec![module];
+        let items = loop {
+            self.consume(Operator::Acceses)?;
+            if self.matches(Operator::CurlyBraceOpen) {
+                let mut items = Vec::new();
+                while !self.check(Operator::CurlyBraceClose) {
+                    items.push(self.identifier("an item name")?);
+                    if !self.matches(Operator::Comma) {
+                        break;
+                    }
+                }
+                self.consume(Operator::CurlyBraceClose)?;
+                break items;
+            }
+            let (name, pos) = self.identifier("a module or item name")?;
+            if !self.check(Operator::Acceses) {
+                break vec![(name, pos)];
+            }
+            path.push(name);
+        };
+        self.consume(Operator::Semicolon)?;
+        Ok(Stmt::use_(path, items, pos))
+    }
+
+    fn function(&mut self, public: bool) -> Result<FnDecl> {
+        let mut decl = self.signature(public)?;
+        decl.body = self.body(Self::block)?;
+        Ok(decl)
+    }
+
+    // A function up to its body, which is left empty
+    fn signature(&mut self, public: bool) -> Result<FnDecl> {
+        let (name, pos) = self.identifier("a function name")?;
+        let generics = self.generics()?;
+        self.consume(Operator::ParenOpen)?;
+        let params = self.params(Operator::ParenClose)?;
+        let ret = if self.matches(Operator::Arrow) {
+            Some(self.type_expr()?)
+        } else {
+            None
+        };
+        Ok(FnDecl {
+            name,
+            public,
+            constant: false,
+            generics,
+            params,
+            ret,
+            body: Expr::block(Vec::new(), None, pos.clone()),
+            pos,
+        })
+    }
+
+    // `<T, U>` after the name of a generic declaration, declarations are never
+    // compared so `<` is not an operator here
+    fn generics(&mut self) -> Result<Vec<Generic>> {
+        let mut generics = Vec::new();
+        if !self.matches(Operator::LessThan) {
+            return Ok(generics);
+        }
+        while !self.check(Operator::GreaterThan) {
+            let (name, pos) = self.identifier("a type parameter")?;
+            let mut bounds = Vec::new();
+            if self.matches(Operator::Colon) {
+                bounds.push(self.identifier("a trait name")?);
+                while self.matches(Operator::Plus) {
+                    bounds.push(self.identifier("a trait name")?);
+                }
+            }
+            generics.push(Generic { name, bounds, pos });
+            if !self.matches(Operator::Comma) {
+                break;
+            }
+        }
+        self.consume(Operator::GreaterThan)?;
+        Ok(generics)
+    }
+
+    // `mut name: Type, ...` up to and including `close`
+    fn params(&mut self, close: Operator) -> Result<Vec<Param>> {
+        let mut params: Vec<Param> = Vec::new();
+        while !self.check(close) {
+            let mutable = self.matches_keyword(Keyword::Mut);
+            let (name, pos) = self.identifier("a parameter name")?;
+            let ty = if self.matches(Operator::Colon) {
+                Some(self.type_expr()?)
+            } else {
+                None
+            };
+            params.push(Param {
+                name,
+                mutable,
+                ty,
+                pos,
+            });
+            if !self.matches(Operator::Comma) {
+                break;
+            }
+        }
+        self.consume(close)?;
+        Ok(params)
+    }
+
+    // The body of a function or closure, which `return` leaves
+    fn body(&mut self, parse: fn(&mut Self) -> Result<Expr>) -> Result<Expr> {
+        // Loops do not reach into the functions declared inside them
+        let loops = mem::take(&mut self.loops);
+        self.functions += 1;
+        let body = parse(self);
+        self.functions -= 1;
+        self.loops = loops;
+        body
+    }
+
+    // `|x, y| x + y`, `|| 0` or `move |x| -> i32 { x + n }`
+    fn closure(&mut self) -> Result<Expr> {
+        let pos = self.pos();
+        let moves = self.matches_keyword(Keyword::Move);
+        // `||` is lexed as a single operator
+        let params = if self.matches(Operator::Or) {
+            Vec::new()
+        } else {
+            self.consume(Operator::VerticalBar)?;
+            self.params(Operator::VerticalBar)?
+        };
+        // Like in functions, a declared return type is followed by a block
+        let (ret, body) = if self.matches(Operator::Arrow) {
+            (Some(self.type_expr()?), self.body(Self::block)?)
+        } else {
+            (None, self.body(Self::expression)?)
+        };
+        let decl = FnDecl {
+            name: AsciiString::from_ascii("closure").unwrap(),
+            public: false,
+            constant: false,
+            generics: Vec::new(),
+            params,
+            ret,
+            body,
+            pos,
+        };
+        Ok(Expr::closure(decl, moves))
+    }
+
+    fn struct_declaration(&mut self, public: bool) -> Result<Stmt> {
+        let (name, pos) = self.identifier("a struct name")?;
+        let generics = self.generics()?;
+        let fields = self.fields()?;
+        Ok(Stmt::struct_(StructDecl {
+            name,
+            public,
+            generics,
+            fields,
+            pos,
+        }))
+    }
+
+    // `{ name: Type, ... }`
+    fn fields(&mut self) -> Result<Vec<Field>> {
+        self.consume(Operator::CurlyBraceOpen)?;
+        let mut fields: Vec<Field> = Vec::new();
+        while !self.check(Operator::CurlyBraceClose) {
+            let (name, pos) = self.identifier("a field name")?;
+            self.consume(Operator::Colon)?;
+            let ty = self.type_expr()?;
+            fields.push(Field { name, ty, pos });
+            if !self.matches(Operator::Comma) {
+                break;
+            }
+        }
+        self.consume(Operator::CurlyBraceClose)?;
+        Ok(fields)
+    }
+
+    fn enum_declaration(&mut self, public: bool) -> Result<Stmt> {
+        let (name, pos) = self.identifier("an enum name")?;
+        let generics = self.generics()?;
+        self.consume(Operator::CurlyBraceOpen)?;
+        let mut variants: Vec<Variant> = Vec::new();
+        while !self.check(Operator::CurlyBraceClose) {
+            let (name, pos) = self.identifier("a variant name")?;
+            let payload = if self.matches(Operator::ParenOpen) {
+                Payload::Tuple(self.type_list(Operator::ParenClose)?)
+            } else if self.check(Operator::CurlyBraceOpen) {
+                Payload::Struct(self.fields()?)
+            } else {
+                Payload::Unit
+            };
+            variants.push(Variant { name, payload, pos });
+            if !self.matches(Operator::Comma) {
+                break;
+            }
+        }
+        self.consume(Operator::CurlyBraceClose)?;
+        Ok(Stmt::enum_(EnumDecl {
+            name,
+            public,
+            generics,
+            variants,
+            pos,
+        }))
+    }
+
+    // `impl Name { ... }`, `impl<T> Name<T> { ... }` or `impl Trait for Name { ... }`
+    fn impl_block(&mut self) -> Result<Stmt> {
+        let generics = self.generics()?;
+        let (mut ty, mut pos) = self.identifier("a type name")?;
+        let mut trait_ = None;
+        if self.matches_keyword(Keyword::For) {
+            trait_ = Some((ty, pos));
+            (ty, pos) = self.identifier("a type name")?;
+        }
+        let args = if self.matches(Operator::LessThan) {
+            self.type_list(Operator::GreaterThan)?
+        } else {
+            Vec::new()
+        };
+        self.consume(Operator::CurlyBraceOpen)?;
+        let mut methods = Vec::new();
+        while !self.check(Operator::CurlyBraceClose) {
+            if !self.matches_keyword(Keyword::Fn) {
+                return self.unexpected("`fn`");
+            }
+            // Methods are as visible as their type
+            methods.push(self.function(false)?);
+        }
+        self.consume(Operator::CurlyBraceClose)?;
+        Ok(Stmt::impl_(generics, trait_, ty, args, methods, pos))
+    }
+
+    // `trait Name { fn required(self); fn provided(self) { ... } }`
+    fn trait_declaration(&mut self, public: bool) -> Result<Stmt> {
+        let (name, pos) = self.identifier("a trait name")?;
+        self.consume(Operator::CurlyBraceOpen)?;
+        let mut methods = Vec::new();
+        while !self.check(Operator::CurlyBraceClose) {
+            if !self.matches_keyword(Keyword::Fn) {
+                return self.unexpected("`fn`");
+            }
+            let mut decl = self.signature(false)?;
+            let provided = !self.matches(Operator::Semicolon);
+            if provided {
+                decl.body = self.body(Self::block)?;
+            }
+            methods.push(TraitMethod {
+                decl: Rc::new(decl),
+                provided,
+            });
+        }
+        self.consume(Operator::CurlyBraceClose)?;
+        Ok(Stmt::trait_(TraitDecl {
+            name,
+            public,
+            methods,
+            pos,
+        }))
+    }
+
+    fn block(&mut self) -> Result<Expr> {
+        let pos = self.consume(Operator::CurlyBraceOpen)?;
+        let mut stmts = Vec::new();
+        let mut tail = None;
+        while !self.check(Operator::CurlyBraceClose) && !self.is_at_end() {
+            if self.is_declaration() {
+                stmts.push(self.declaration()?);
+                continue;
+            }
+            let expresion = self.expression_statement()?;
+            let semicolon = self.matches(Operator::Semicolon);
+            if !semicolon && self.check(Operator::CurlyBraceClose) {
+                // The trailing expression is the value of the block
+                tail = Some(Box::new(expresion));
+            } else if semicolon || expresion.is_block_like() {
+                stmts.push(Stmt::expr(expresion));
+            } else {
+                return self.unexpected("`;`");
+            }
+        }
+        self.consume(Operator::CurlyBraceClose)?;
+        Ok(Expr::block(stmts, tail, pos))
+    }
+
+    fn type_expr(&mut self) -> Result<TypeExpr> {
+        let pos = self.pos();
+        let mut ty = if self.matches(Operator::ParenOpen) {
+            TypeExpr::tuple(self.type_list(Operator::ParenClose)?, pos)
+        } else if self.matches(Operator::BracketOpen) {
+            let element = self.type_expr()?;
+            let length = if self.matches(Operator::Semicolon) {
+                Some(self.expression()?)
+            } else {
+                None
+            };
+            self.consume(Operator::BracketClose)?;
+            TypeExpr::array(element, length, pos)
+        } else if self.matches_keyword(Keyword::Fn) {
+            self.consume(Operator::ParenOpen)?;
+            let params = self.type_list(Operator::ParenClose)?;
+            let ret = if self.matches(Operator::Arrow) {
+                Some(self.type_expr()?)
+            } else {
+                None
+            };
+            TypeExpr::function(params, ret, pos)
+        } else if self.matches_keyword(Keyword::Dyn) {
+            let (name, _) = self.identifier("a trait name")?;
+            TypeExpr::dyn_(name, pos)
+        } else {
+            let (name, pos) = self.identifier("a type")?;
+            let args = if self.matches(Operator::LessThan) {
+                self.type_list(Operator::GreaterThan)?
+            } else {
+                Vec::new()
+            };
+            TypeExpr::named(name, args, pos)
+        };
+        if self.check(Operator::Question) {
+            let pos = self.pos();
+            self.advance();
+            ty = TypeExpr::optional(ty, pos);
+        }
+        Ok(ty)
+    }
+
+    // Comma separated types up to and including `close`
+    fn type_list(&mut self, close: Operator) -> Result<Vec<TypeExpr>> {
+        let mut types = Vec::new();
+        while !self.check(close) {
+            types.push(self.type_expr()?);
+            if !self.matches(Operator::Comma) {
+                break;
+            }
+        }
+        self.consume(close)?;
+        Ok(types)
+    }
+
+    fn let_declaration(&mut self) -> Result<Stmt> {
+        let pos = self.pos();
+        let pattern = self.pattern()?;
+        let ty = if self.matches(Operator::Colon) {
+            Some(self.type_expr()?)
+        } else {
+            None
+        };
+        // Only a plain variable can be declared without a value to destructure
+        let value = if self.matches(Operator::Assign) {
+            Some(self.expression()?)
+        } else if let Pattern::Binding { .. } = pattern {
+            None
+        } else {
+            return self.unexpected("`=`");
+        };
+        self.consume(Operator::Semicolon)?;
+        Ok(Stmt::let_(pattern, ty, value, pos))
+    }
+
+    // `const NAME: Type = value;`, or `const fn` for the functions constants
+    // can call
+    fn const_declaration(&mut self, public: bool) -> Result<Stmt> {
+        if self.matches_keyword(Keyword::Fn) {
+            let mut decl = self.function(public)?;
+            decl.constant = true;
+            return Ok(Stmt::function(decl));
+        }
+        let (name, pos) = self.identifier("a constant name")?;
+        self.consume(Operator::Colon)?;
+        let ty = self.type_expr()?;
+        self.consume(Operator::Assign)?;
+        let value = self.expression()?;
+        self.consume(Operator::Semicolon)?;
+        Ok(Stmt::const_(ConstDecl {
+            name,
+            public,
+            ty,
+            value,
+            pos,
+            folded: OnceCell::new(),
+        }))
+    }
+
+    fn statement(&mut self) -> Result<Stmt> {
+        let expresion = self.expression_statement()?;
+        // The last expression of a script may leave out its semicolon
+        if !self.matches(Operator::Semicolon) && !self.is_at_end() && !expresion.is_block_like() {
+            return self.unexpected("`;`");
+        }
+        Ok(Stmt::expr(expresion))
+    }
+
+    // Block-like expressions in statement position end the statement, like in Rust
+    fn expression_statement(&mut self) -> Result<Expr> {
+        if self.check(Operator::CurlyBraceOpen)
+            || self.check_keyword(Keyword::If)
+            || self.check_keyword(Keyword::Loop)
+            || matches!(self.peek(), Token::Label(_))
+        {
+            self.primary()
+        } else {
+            self.expression()
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expr> {
+        if self.check_keyword(Keyword::Return) {
+            return self.return_expr();
+        } else if self.check_keyword(Keyword::Break) {
+            return self.break_expr();
+        } else if self.check_keyword(Keyword::Continue) {
+            let pos = self.pos();
+            self.advance();
+            let label = self.jump_label(Keyword::Continue, &pos)?;
+            return Ok(Expr::cont(label, pos));
+        }
+        self.assignment()
+    }
+
+    // The optional label of a `break` or `continue`, which has to name an enclosing loop
+    fn jump_label(&mut self, keyword: Keyword, pos: &Position) -> Result<Option<AsciiString>> {
+        if self.loops.is_empty() {
+            return Error::Misplaced(keyword.to_string(), String::from("a loop")).throw(pos);
+        }
+        let Token::Label(label) = self.peek().clone() else {
+            return Ok(None);
+        };
+        if !self.loops.iter().any(|l| l.as_ref() == Some(&label)) {
+            return Error::UndeclaredLabel(label.to_string()).throw(&self.pos());
+        }
+        self.advance();
+        Ok(Some(label))
+    }
+
+    fn ends_expression(&self) -> bool {
+        self.is_at_end()
+            || self.check(Operator::Semicolon)
+            || self.check(Operator::CurlyBraceClose)
+            || self.check(Operator::ParenClose)
+            || self.check(Operator::Comma)
+            || self.check(Operator::BracketClose)
+    }
+
+    fn return_expr(&mut self) -> Result<Expr> {
+        let pos = self.pos();
+        self.advance();
+        if self.functions == 0 {
+            return Error::Misplaced(Keyword::Return.to_string(), String::from("a function"))
+                .throw(&pos);
+        }
+        let value = if self.ends_expression() {
+            None
+        } else {
+            Some(Box::new(self.expression()?))
+        };
+        Ok(Expr::ret(value, pos))
+    }
+
+    fn break_expr(&mut self) -> Result<Expr> {
+        let pos = self.pos();
+        self.advance();
+        let label = self.jump_label(Keyword::Break, &pos)?;
+        let value = if self.ends_expression() {
+            None
+        } else {
+            Some(Box::new(self.expression()?))
+        };
+        Ok(Expr::brk(label, value, pos))
+    }
+
+    fn match_expr(&mut self) -> Result<Expr> {
+        let pos = self.pos();
+        self.advance();
+        let scrutinee = self.without_structs(Self::expression)?;
+        self.consume(Operator::CurlyBraceOpen)?;
+        let mut arms = Vec::new();
+        while !self.check(Operator::CurlyBraceClose) {
+            let pattern = self.pattern()?;
+            let guard = if self.matches_keyword(Keyword::If) {
+                Some(self.expression()?)
+            } else {
+                None
+            };
+            self.consume(Operator::FatArrow)?;
+            let body = self.with_structs(true, Self::expression)?;
+            // Arms with a block-like body need no comma before the next arm
+            let block_like = body.is_block_like();
+            arms.push(Arm {
+                pattern,
+                guard,
+                body,
+            });
+            if !self.matches(Operator::Comma)
+                && !block_like
+                && !self.check(Operator::CurlyBraceClose)
+            {
+                return self.unexpected("`,`");
+            }
+        }
+        self.consume(Operator::CurlyBraceClose)?;
+        Ok(Expr::match_(Box::new(scrutinee), arms, pos))
+    }
+
+    fn pattern(&mut self) -> Result<Pattern> {
+        let pos = self.pos();
+        match self.peek().clone() {
+            Token::Identifier(name) if name == "_" => {
+                self.advance();
+                Ok(Pattern::Wildcard { pos })
+            }
+            Token::Keyword(Keyword::Mut) => {
+                self.advance();
+                let (name, pos) = self.identifier("a binding name")?;
+                Ok(Pattern::binding(name, true, pos))
+            }
+            Token::Identifier(name) => {
+                self.advance();
+                let mut path = vec![name];
+                while self.matches(Operator::Acceses) {
+                    path.push(self.identifier("a name")?.0);
+                }
+                if self.matches(Operator::ParenOpen) {
+                    let elements = self.patterns(Operator::ParenClose)?;
+                    Ok(Pattern::Variant {
+                        path,
+                        elements,
+                        pos,
+                    })
+                } else if self.matches(Operator::CurlyBraceOpen) {
+                    let fields = self.field_patterns()?;
+                    Ok(Pattern::Struct { path, fields, pos })
+                } else if path.len() > 1 {
+                    Ok(Pattern::Path { path, pos })
+                } else {
+                    Ok(Pattern::binding(path.pop().unwrap(), false, pos))
+                }
+            }
+            Token::Operator(Operator::ParenOpen) => {
+                self.advance();
+                if self.matches(Operator::ParenClose) {
+                    return Ok(Pattern::Tuple {
+                        elements: Vec::new(),
+                        pos,
+                    });
+                }
+                let pattern = self.pattern()?;
+                if !self.matches(Operator::Comma) {
+                    self.consume(Operator::ParenClose)?;
+                    return Ok(pattern);
+                }
+                let mut elements = vec![pattern];
+                elements.append(&mut self.patterns(Operator::ParenClose)?);
+                Ok(Pattern::Tuple { elements, pos })
+            }
+            Token::Operator(Operator::Range | Operator::RangeInclusive) => {
+                self.range_pattern(None, pos)
+            }
+            _ => {
+                let value = self.pattern_literal()?;
+                if self.check(Operator::Range) || self.check(Operator::RangeInclusive) {
+                    self.range_pattern(Some(value), pos)
+                } else {
+                    Ok(Pattern::Literal { value, pos })
+                }
+            }
+        }
+    }
+
+    // Comma separated patterns up to and including `close`
+    fn patterns(&mut self, close: Operator) -> Result<Vec<Pattern>> {
+        let mut patterns = Vec::new();
+        while !self.check(close) {
+            patterns.push(self.pattern()?);
+            if !self.matches(Operator::Comma) {
+                break;
+            }
+        }
+        self.consume(close)?;
+        Ok(patterns)
+    }
+
+    // `{ x, y: pattern, .. }` where `x` is short for `x: x`
+    fn field_patterns(&mut self) -> Result<Vec<(AsciiString, Pattern)>> {
+        let mut fields = Vec::new();
+        while !self.check(Operator::CurlyBraceClose) {
+            // Unlisted fields match anything, `..` only says so explicitly
+            if self.matches(Operator::Range) {
+                break;
+            }
+            let mutable = self.matches_keyword(Keyword::Mut);
+            let (name, pos) = self.identifier("a field name")?;
+            let pattern = if !mutable && self.matches(Operator::Colon) {
+                self.pattern()?
+            } else {
+                Pattern::binding(name.clone(), mutable, pos)
+            };
+            fields.push((name, pattern));
+            if !self.matches(Operator::Comma) {
+                break;
+            }
+        }
+        self.consume(Operator::CurlyBraceClose)?;
+        Ok(fields)
+    }
+
+    fn range_pattern(&mut self, start: Option<Literal>, pos: Position) -> Result<Pattern> {
+        let inclusive = self.check(Operator::RangeInclusive);
+        self.advance();
+        let end = match self.peek() {
+            Token::Literal(_) | Token::Operator(Operator::Minus) => Some(self.pattern_literal()?),
+            _ => None,
+        };
+        Ok(Pattern::Range {
+            start,
+            end,
+            inclusive,
+            pos,
+        })
+    }
+
+    // A literal, numbers may be negated
+    fn pattern_literal(&mut self) -> Result<Literal> {
+        let pos = self.pos();
+        let negative = self.matches(Operator::Minus);
+        match self.peek().clone() {
+            Token::Literal(Literal::Number(n)) if negative => {
+                self.advance();
+                match n.negate() {
+                    Ok(n) => Ok(Literal::Number(n)),
+                    Err(error) => error.throw(&pos),
+                }
+            }
+            Token::Literal(Literal::Unsuffixed(n)) if negative => {
+                self.advance();
+                Ok(Literal::Unsuffixed(n.negate()))
+            }
+            Token::Literal(value) if !negative => {
+                self.advance();
+                Ok(value)
+            }
+            Token::Operator(Operator::Question) if !negative => {
+                self.advance();
+                Ok(Literal::Null)
+            }
+            _ => self.unexpected("a pattern"),
+        }
+    }
+
+    fn if_expr(&mut self) -> Result<Expr> {
+        let pos = self.pos();
+        self.advance();
+        let condition = self.without_structs(Self::expression)?;
+        let then_branch = self.block()?;
+        let else_branch = if self.matches_keyword(Keyword::Else) {
+            if self.check_keyword(Keyword::If) {
+                Some(self.if_expr()?)
+            } else {
+                Some(self.block()?)
+            }
+        } else {
+            None
+        };
+
+        Ok(Expr::if_(
+            Box::new(condition),
+            Box::new(then_branch),
+            else_branch.map(Box::new),
+            pos,
+        ))
+    }
+
+    fn loop_expr(&mut self, label: Option<AsciiString>) -> Result<Expr> {
+        let pos = self.pos();
+        self.advance();
+
+        // `loop pattern in iterable { ... }`
+        let mut iteration = None;
+        if !self.check(Operator::CurlyBraceOpen) {
+            let pattern = self.pattern()?;
+            if !self.matches_keyword(Keyword::In) {
+                return self.unexpected("`in`");
+            }
+            iteration = Some((pattern, self.without_structs(Self::expression)?));
+        }
+
+        self.loops.push(label.clone());
+        let body = self.block();
+        self.loops.pop();
+        let body = Box::new(body?);
+
+        Ok(match iteration {
+            Some((pattern, iterable)) => {
+                Expr::loop_in(label, pattern, Box::new(iterable), body, pos)
+            }
+            None => Expr::loop_(label, body, pos),
+        })
+    }
+
+    fn labeled_loop(&mut self, label: AsciiString) -> Result<Expr> {
+        self.advance();
+        self.consume(Operator::Colon)?;
+        if !self.check_keyword(Keyword::Loop) {
+            return self.unexpected("`loop`");
+        }
+        self.loop_expr(Some(label))
+    }
+
+    fn assignment(&mut self) -> Result<Expr> {
+        let expr = self.range()?;
+        if self.check(Operator::Assign) {
+            let pos = self.pos();
+            self.advance();
+            let value = self.assignment()?;
+            return match expr {
+                Expr::Variable { .. }
+                | Expr::Index { .. }
+                | Expr::Field { safe: false, .. }
+                | Expr::TupleIndex { safe: false, .. } => {
+                    Ok(Expr::assign(Box::new(expr), Box::new(value), pos))
+                }
+                _ => Error::InvalidAssignment.throw(expr.pos()),
+            };
+        }
+        Ok(expr)
+    }
+
+    fn range(&mut self) -> Result<Expr> {
+        let start = if self.check(Operator::Range) || self.check(Operator::RangeInclusive) {
+            None
+        } else {
+            Some(self.coalesce()?)
+        };
+
+        let Token::Operator(operator @ (Operator::Range | Operator::RangeInclusive)) = *self.peek()
+        else {
+            // `start` is only missing when the range operator follows
+            return Ok(start.unwrap());
+        };
+        let pos = self.pos();
+        self.advance();
+
+        let inclusive = operator == Operator::RangeInclusive;
+        let end = if self.ends_expression() || self.check(Operator::CurlyBraceOpen) {
+            if inclusive {
+                return self.unexpected("the end of the range");
+            }
+            None
+        } else {
+            Some(Box::new(self.coalesce()?))
+        };
+        Ok(Expr::range(start.map(Box::new), end, inclusive, pos))
+    }
+
+    // `a ?? b` only evaluates `b` when `a` is `?`
+    fn coalesce(&mut self) -> Result<Expr> {
+        let expr = self.or()?;
+        if self.check(Operator::Coalesce) {
+            let pos = self.pos();
+            self.advance();
+            let right = self.coalesce()?;
+            return Ok(Expr::logical(
+                Box::new(expr),
+                Operator::Coalesce,
+                Box::new(right),
+                pos,
+            ));
+        }
+        Ok(expr)
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.check(Operator::Or) {
+            let pos = self.pos();
+            self.advance();
+            let right = self.and()?;
+            expr = Expr::logical(Box::new(expr), Operator::Or, Box::new(right), pos);
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.equality()?;
+        while self.check(Operator::And) {
+            let pos = self.pos();
+            self.advance();
+            let right = self.equality()?;
+            expr = Expr::logical(Box::new(expr), Operator::And, Box::new(right), pos);
+        }
+        Ok(expr)
+    }
+
+    fn binary(
+        &mut self,
+        operators: &[Operator],
+        operand: fn(&mut Self) -> Result<Expr>,
+    ) -> Result<Expr> {
+        let mut expr = operand(self)?;
+        while let Token::Operator(operator) = *self.peek() {
+            if !operators.contains(&operator) {
+                break;
+            }
+            let pos = self.pos();
+            self.advance();
+            let right = operand(self)?;
+            expr = Expr::binary(Box::new(expr), operator, Box::new(right), pos);
+        }
+        Ok(expr)
+    }
+
+    fn equality(&mut self) -> Result<Expr> {
+        self.binary(&[Operator::EqualTo, Operator::NotEqualTo], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        self.binary(
+            &[
+                Operator::LessThan,
+                Operator::LessThanOrEqualTo,
+                Operator::GreaterThan,
+                Operator::GreaterThanOrEqualTo,
+            ],
+            Self::term,
+        )
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        self.binary(&[Operator::Plus, Operator::Minus], Self::factor)
+    }
+
+    fn factor(&mut self) -> Result<Expr> {
+        self.binary(
+            &[Operator::Asterisk, Operator::Slash, Operator::Percent],
+            Self::cast,
+        )
+    }
+
+    // `as` binds tighter than the binary operators, so `-x as u8 * 2` is
+    // `((-x) as u8) * 2`
+    fn cast(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        while self.check_keyword(Keyword::As) {
+            let pos = self.pos();
+            self.advance();
+            let ty = self.type_expr()?;
+            expr = Expr::cast(Box::new(expr), ty, pos);
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if let Token::Operator(
+            operator @ (Operator::Exclamation
+            | Operator::Minus
+            | Operator::Increment
+            | Operator::Decrement),
+        ) = *self.peek()
+        {
+            let pos = self.pos();
+            self.advance();
+            let right = self.unary()?;
+            // A negated number is a negative literal, so the least value of a
+            // signed type can be written
+            match (operator, &right) {
+                (
+                    Operator::Minus,
+                    Expr::Literal {
+                        value: Literal::Unsuffixed(n),
+                        ..
+                    },
+                ) => return Ok(Expr::literal(Literal::Unsuffixed(n.negate()), pos)),
+                (
+                    Operator::Minus,
+                    Expr::Literal {
+                        value: Literal::Number(n),
+                        ..
+                    },
+                ) => {
+                    return match n.negate() {
+                        Ok(n) => Ok(Expr::literal(Literal::Number(n), pos)),
+                        Err(error) => error.throw(&pos),
+                    }
+                }
+                _ => {}
+            }
+            return Ok(Expr::unary(operator, Side::Right(Box::new(right)), pos));
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.check(Operator::ParenOpen) {
+                let pos = self.pos();
+                self.advance();
+                let arguments = self.list(Operator::ParenClose)?;
+                expr = Expr::call(Box::new(expr), arguments, pos);
+            } else if self.check(Operator::BracketOpen) {
+                let pos = self.pos();
+                self.advance();
+                let index = self.expression()?;
+                self.consume(Operator::BracketClose)?;
+                expr = Expr::index(Box::new(expr), Box::new(index), pos);
+            } else if self.check(Operator::Dot) || self.check(Operator::SafeAccess) {
+                let safe = self.check(Operator::SafeAccess);
+                self.advance();
+                // Only unsuffixed integers index tuples, the lexer keeps `.0.1` apart.
+                // A `-` after the `.` is an operator, so the index is never negative.
+                if let Token::Literal(Literal::Unsuffixed(index)) = self.peek() {
+                    if let Ok(index) = index.digits.parse() {
+                        let pos = self.pos();
+                        self.advance();
+                        expr = Expr::tuple_index(Box::new(expr), index, safe, pos);
+                        continue;
+                    }
+                }
+                let (name, pos) = self.identifier("a field, method or tuple index")?;
+                if self.matches(Operator::ParenOpen) {
+                    let arguments = self.list(Operator::ParenClose)?;
+                    expr = Expr::method(Box::new(expr), name, arguments, safe, pos);
+                } else {
+                    expr = Expr::field(Box::new(expr), name, safe, pos);
+                }
+            } else if let Token::Operator(operator @ (Operator::Increment | Operator::Decrement)) =
+                *self.peek()
+            {
+                let pos = self.pos();
+                self.advance();
+                expr = Expr::unary(operator, Side::Left(Box::new(expr)), pos);
+            } else {
+                break;
+            }
+        }
+        Ok(expr)
+    }
+
+    fn with_structs<T>(&mut self, allowed: bool, parse: fn(&mut Self) -> Result<T>) -> Result<T> {
+        let no_struct = mem::replace(&mut self.no_struct, !allowed);
+        let result = parse(self);
+        self.no_struct = no_struct;
+        result
+    }
+
+    fn without_structs<T>(&mut self, parse: fn(&mut Self) -> Result<T>) -> Result<T> {
+        self.with_structs(false, parse)
+    }
+
+    // Comma separated expressions up to `close`, allowing a trailing comma
+    fn list(&mut self, close: Operator) -> Result<Vec<Expr>> {
+        let mut elements = Vec::new();
+        while !self.check(close) {
+            elements.push(self.with_structs(true, Self::expression)?);
+            if !self.matches(Operator::Comma) {
+                break;
+            }
+        }
+        self.consume(close)?;
+        Ok(elements)
+    }
+
+    // `Name { field: value, ... }` or `Enum::Variant { ... }`, where
+    // `Name { field }` is short for `Name { field: field }`
+    fn struct_literal(&mut self, path: Vec<AsciiString>, pos: Position) -> Result<Expr> {
+        self.advance();
+        let mut fields = Vec::new();
+        while !self.check(Operator::CurlyBraceClose) {
+            let (field, field_pos) = self.identifier("a field name")?;
+            let value = if self.matches(Operator::Colon) {
+                self.with_structs(true, Self::expression)?
+            } else {
+                Expr::variable(field.clone(), field_pos)
+            };
+            fields.push((field, value));
+            if !self.matches(Operator::Comma) {
+                break;
+            }
+        }
+        self.consume(Operator::CurlyBraceClose)?;
+        Ok(Expr::struct_(path, fields, pos))
+    }
+
+    // `#{ key: value, ... }`, the `#` keeps maps apart from blocks
+    fn map(&mut self) -> Result<Expr> {
+        let pos = self.pos();
+        self.advance();
+        self.consume(Operator::CurlyBraceOpen)?;
+        let mut entries = Vec::new();
+        while !self.check(Operator::CurlyBraceClose) {
+            let key = self.expression()?;
+            self.consume(Operator::Colon)?;
+            let value = self.expression()?;
+            entries.push((key, value));
+            if !self.matches(Operator::Comma) {
+                break;
+            }
+        }
+        self.consume(Operator::CurlyBraceClose)?;
+        Ok(Expr::map(entries, pos))
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let pos = self.pos();
+        match self.peek().clone() {
+            Token::Literal(value) => {
+                self.advance();
+                Ok(Expr::literal(value, pos))
+            }
+            Token::Operator(Operator::Question) => {
+                self.advance();
+                Ok(Expr::literal(Literal::Null, pos))
+            }
+            Token::Identifier(name) => {
+                self.advance();
+                let mut segments = vec![name];
+                let mut args = None;
+                while self.matches(Operator::Acceses) {
+                    // `max::<i32>` gives the type arguments, `max<i32>` would be a comparison
+                    if self.check(Operator::LessThan) {
+                        let pos = self.pos();
+                        self.advance();
+                        args = Some((self.type_list(Operator::GreaterThan)?, pos));
+                        break;
+                    }
+                    segments.push(self.identifier("a name")?.0);
+                }
+                let expr = if self.check(Operator::CurlyBraceOpen) && !self.no_struct {
+                    self.struct_literal(segments, pos)?
+                } else if segments.len() > 1 {
+                    Expr::path(segments, pos)
+                } else {
+                    Expr::variable(segments.pop().unwrap(), pos)
+                };
+                Ok(match args {
+                    Some((args, pos)) => Expr::type_args(Box::new(expr), args, pos),
+                    None => expr,
+                })
+            }
+            Token::Operator(Operator::ParenOpen) => {
+                self.advance();
+                if self.matches(Operator::ParenClose) {
+                    return Ok(Expr::tuple(Vec::new(), pos));
+                }
+                let expr = self.with_structs(true, Self::expression)?;
+                // A comma turns the group into a tuple, `(x,)` has a single element
+                if self.matches(Operator::Comma) {
+                    let mut elements = vec![expr];
+                    elements.append(&mut self.list(Operator::ParenClose)?);
+                    return Ok(Expr::tuple(elements, pos));
+                }
+                self.consume(Operator::ParenClose)?;
+                Ok(Expr::group(Box::new(expr)))
+            }
+            Token::Operator(Operator::BracketOpen) => {
+                self.advance();
+                let elements = self.list(Operator::BracketClose)?;
+                Ok(Expr::array(elements, pos))
+            }
+            Token::Operator(Operator::Hash) => self.map(),
+            Token::Operator(Operator::CurlyBraceOpen) => self.block(),
+            Token::Keyword(Keyword::If) => self.if_expr(),
+            Token::Keyword(Keyword::Match) => self.match_expr(),
+            Token::Keyword(Keyword::Move)
+            | Token::Operator(Operator::VerticalBar | Operator::Or) => self.closure(),
+            Token::Keyword(Keyword::Loop) => self.loop_expr(None),
+            Token::Label(label) => self.labeled_loop(label),
+            _ => self.unexpected("an expression"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Parser;
+    use crate::{
+        ast::{
+            expr::Expr,
+            lexer::Lexer,
+            stmt::Stmt,
+            token::{Literal, Number},
+        },
+        error::{Error, Result},
+    };
+
+    fn parse(source: &str) -> Result<Vec<Stmt>> {
+        Parser::new(Lexer::new("test", source).tokenize()?).parse()
+    }
+
+    fn expression(source: &str) -> Expr {
+        match parse(source).unwrap().pop() {
+            Some(Stmt::Expr { expresion }) => expresion,
+            _ => panic!("`{source}` is not an expression"),
+        }
+    }
+
+    fn literal(source: &str) -> Option<Literal> {
+        match expression(source) {
+            Expr::Literal { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn negated_numbers_are_literals() {
+        let number = |n| Some(Literal::Number(n));
+        assert_eq!(literal("-128i8"), number(Number::I8(-128)));
+        assert_eq!(literal("-5i64"), number(Number::I64(-5)));
+        assert_eq!(literal("-1.5f32"), number(Number::F32(-1.5)));
+        assert!(matches!(literal("-5"), Some(Literal::Unsuffixed(n)) if n.digits == "-5"));
+        // The sign of a method call's receiver applies to the result
+        assert_eq!(literal("-5i8.max(1i8)"), None);
+        assert_eq!(literal("2i8 - 1i8"), None);
+        assert!(parse("-5u8").is_err());
+    }
+
+    #[test]
+    fn functions_declare_their_parameters_and_return_type() {
+        let stmts = parse("fn add(a: i32, mut b,) -> i32 { a + b }").unwrap();
+        let Some(Stmt::Function { decl }) = stmts.first() else {
+            panic!("`fn` declares a function");
+        };
+        let params = decl.params.iter().map(|param| param.name.as_str());
+        assert_eq!(params.collect::<Vec<_>>(), ["a", "b"]);
+        assert!(decl.params[0].ty.is_some() && decl.params[1].ty.is_none());
+        assert_eq!(
+            decl.ret.as_ref().map(ToString::to_string).as_deref(),
+            Some("i32")
+        );
+        assert!(matches!(
+            parse("fn (a: i32) {}"),
+            Err(Error::UnexpectedToken(..))
+        ));
+        assert!(matches!(
+            parse("fn f(a: i32"),
+            Err(Error::UnexpectedEndOfFile(_))
+        ));
+        assert!(matches!(parse("return 1;"), Err(Error::Misplaced(..))));
+    }
+}
@@ -0,0 +1,80 @@
+use super::token::Literal;
+use crate::position::Position;
+use ascii::AsciiString;
+use std::cell::OnceCell;
+
+pub enum Pattern {
+    // `name` or `mut name`. A name the checker finds to be a constant matches
+    // its value instead, which it sets.
+    Binding {
+        name: AsciiString,
+        mutable: bool,
+        pos: Position,
+        constant: OnceCell<Literal>,
+    },
+    Literal {
+        value: Literal,
+        pos: Position,
+    },
+    // `'a'..='z'`, `..0` or `10..`
+    Range {
+        start: Option<Literal>,
+        end: Option<Literal>,
+        inclusive: bool,
+        pos: Position,
+    },
+    // `Point { x, y: 0 }` or `Shape::Rect { w, .. }`, unlisted fields match anything
+    Struct {
+        path: Vec<AsciiString>,
+        fields: Vec<(AsciiString, Self)>,
+        pos: Position,
+    },
+    Tuple {
+        elements: Vec<Self>,
+        pos: Position,
+    },
+    // `Shape::Circle(r)`
+    Variant {
+        path: Vec<AsciiString>,
+        elements: Vec<Self>,
+        pos: Position,
+    },
+    // `Shape::Empty`
+    Path {
+        path: Vec<AsciiString>,
+        pos: Position,
+    },
+    Wildcard {
+        pos: Position,
+    },
+}
+
+impl Pattern {
+    pub fn binding(name: AsciiString, mutable: bool, pos: Position) -> Self {
+        Self::Binding {
+            name,
+            mutable,
+            pos,
+            constant: OnceCell::new(),
+        }
+    }
+
+    pub fn pos(&self) -> &Position {
+        match self {
+            Self::Binding { pos, .. }
+            | Self::Literal { pos, .. }
+            | Self::Range { pos, .. }
+            | Self::Struct { pos, .. }
+            | Self::Tuple { pos, .. }
+            | Self::Variant { pos, .. }
+            | Self::Path { pos, .. }
+            | Self::Wildcard { pos } => pos,
+        }
+    }
+}
+
+// `Shape::Circle`
+pub fn path_name(path: &[AsciiString]) -> String {
+    let segments = path.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+    segments.join("::")
+}
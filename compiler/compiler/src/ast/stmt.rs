@@ -0,0 +1,261 @@
+use super::{expr::Expr, pattern::Pattern, token::Literal, types::TypeExpr};
+use crate::position::Position;
+use ascii::AsciiString;
+use std::{cell::OnceCell, rc::Rc};
+
+pub enum Stmt {
+    Expr {
+        expresion: Expr,
+    },
+    Const {
+        decl: Rc<ConstDecl>,
+    },
+    Enum {
+        decl: Rc<EnumDecl>,
+    },
+    Function {
+        decl: Rc<FnDecl>,
+    },
+    // `impl<T> Name<T> { ... }`, where `args` are the type arguments of `ty`.
+    // `impl Trait for Name { ... }` implements a trait for the type.
+    Impl {
+        generics: Vec<Generic>,
+        trait_: Option<(AsciiString, Position)>,
+        ty: AsciiString,
+        args: Vec<TypeExpr>,
+        methods: Vec<Rc<FnDecl>>,
+        pos: Position,
+    },
+    Let {
+        pattern: Pattern,
+        ty: Option<TypeExpr>,
+        value: Option<Box<Expr>>,
+        pos: Position,
+    },
+    Struct {
+        decl: Rc<StructDecl>,
+    },
+    Trait {
+        decl: Rc<TraitDecl>,
+    },
+    // `use a::b::{c, d};` imports `c` and `d` from the module `a::b`
+    Use {
+        path: Vec<AsciiString>,
+        items: Vec<(AsciiString, Position)>,
+        pos: Position,
+    },
+}
+
+// `#[allow(a, b)]`, silencing the lints named on the statement after it,
+// which spans the source from `start` to `end`
+pub struct Allow {
+    pub lints: Vec<(AsciiString, Position)>,
+    pub start: Position,
+    pub end: Position,
+}
+
+// `const NAME: Type = value;`, computed by the checker before the program runs
+pub struct ConstDecl {
+    pub name: AsciiString,
+    pub public: bool,
+    pub ty: TypeExpr,
+    pub value: Expr,
+    pub pos: Position,
+    // The value the checker computed, which the interpreter defines the name as
+    pub folded: OnceCell<Literal>,
+}
+
+pub struct FnDecl {
+    pub name: AsciiString,
+    pub public: bool,
+    // `const fn`, which constants can call
+    pub constant: bool,
+    pub generics: Vec<Generic>,
+    pub params: Vec<Param>,
+    pub ret: Option<TypeExpr>,
+    pub body: Expr,
+    pub pos: Position,
+}
+
+pub struct Param {
+    pub name: AsciiString,
+    pub mutable: bool,
+    pub ty: Option<TypeExpr>,
+    pub pos: Position,
+}
+
+// A type parameter of a generic declaration, like `T` in `fn max<T>`.
+// `T: Show + Eq` bounds it to types implementing the traits.
+pub struct Generic {
+    pub name: AsciiString,
+    pub bounds: Vec<(AsciiString, Position)>,
+    pub pos: Position,
+}
+
+pub struct StructDecl {
+    pub name: AsciiString,
+    pub public: bool,
+    pub generics: Vec<Generic>,
+    pub fields: Vec<Field>,
+    pub pos: Position,
+}
+
+pub struct Field {
+    pub name: AsciiString,
+    pub ty: TypeExpr,
+    pub pos: Position,
+}
+
+pub struct EnumDecl {
+    pub name: AsciiString,
+    pub public: bool,
+    pub generics: Vec<Generic>,
+    pub variants: Vec<Variant>,
+    pub pos: Position,
+}
+
+pub struct Variant {
+    pub name: AsciiString,
+    pub payload: Payload,
+    pub pos: Position,
+}
+
+pub struct TraitDecl {
+    pub name: AsciiString,
+    pub public: bool,
+    pub methods: Vec<TraitMethod>,
+    pub pos: Position,
+}
+
+// A method of a trait, the ones it does not provide have an empty body and
+// are implemented by every `impl` of the trait
+pub struct TraitMethod {
+    pub decl: Rc<FnDecl>,
+    pub provided: bool,
+}
+
+// What a variant carries, `Circle(f64)` is a tuple and `Rect { w: f64, h: f64 }` a struct
+pub enum Payload {
+    Unit,
+    Tuple(Vec<TypeExpr>),
+    Struct(Vec<Field>),
+}
+
+impl EnumDecl {
+    pub fn variant(&self, name: &AsciiString) -> Option<(usize, &Variant)> {
+        self.variants
+            .iter()
+            .enumerate()
+            .find(|(_, variant)| &variant.name == name)
+    }
+
+    // `Shape::Circle`
+    pub fn path(&self, index: usize) -> String {
+        format!("{}::{}", self.name, self.variants[index].name)
+    }
+
+    // How the variant is written, e.g. `Shape::Circle(..)`
+    pub fn usage(&self, index: usize) -> String {
+        let path = self.path(index);
+        match self.variants[index].payload {
+            Payload::Unit => format!("`{path}`"),
+            Payload::Tuple(_) => format!("`{path}(..)`"),
+            Payload::Struct(_) => format!("`{path} {{ .. }}`"),
+        }
+    }
+}
+
+impl TraitDecl {
+    pub fn method(&self, name: &AsciiString) -> Option<&TraitMethod> {
+        self.methods.iter().find(|method| &method.decl.name == name)
+    }
+}
+
+impl FnDecl {
+    // Methods take their receiver as a first parameter named `self`
+    pub fn is_method(&self) -> bool {
+        self.params
+            .first()
+            .is_some_and(|param| param.name == "self")
+    }
+}
+
+impl Stmt {
+    pub fn expr(expresion: Expr) -> Self {
+        Self::Expr { expresion }
+    }
+    pub fn enum_(decl: EnumDecl) -> Self {
+        Self::Enum {
+            decl: Rc::new(decl),
+        }
+    }
+    pub fn function(decl: FnDecl) -> Self {
+        Self::Function {
+            decl: Rc::new(decl),
+        }
+    }
+    pub fn impl_(
+        generics: Vec<Generic>,
+        trait_: Option<(AsciiString, Position)>,
+        ty: AsciiString,
+        args: Vec<TypeExpr>,
+        methods: Vec<FnDecl>,
+        pos: Position,
+    ) -> Self {
+        Self::Impl {
+            generics,
+            trait_,
+            ty,
+            args,
+            methods: methods.into_iter().map(Rc::new).collect(),
+            pos,
+        }
+    }
+    pub fn let_(
+        pattern: Pattern,
+        ty: Option<TypeExpr>,
+        value: Option<Expr>,
+        pos: Position,
+    ) -> Self {
+        Self::Let {
+            pattern,
+            ty,
+            value: value.map(Box::new),
+            pos,
+        }
+    }
+    pub fn const_(decl: ConstDecl) -> Self {
+        Self::Const {
+            decl: Rc::new(decl),
+        }
+    }
+    pub fn struct_(decl: StructDecl) -> Self {
+        Self::Struct {
+            decl: Rc::new(decl),
+        }
+    }
+    pub fn trait_(decl: TraitDecl) -> Self {
+        Self::Trait {
+            decl: Rc::new(decl),
+        }
+    }
+    pub fn use_(
+        path: Vec<AsciiString>,
+        items: Vec<(AsciiString, Position)>,
+        pos: Position,
+    ) -> Self {
+        Self::Use { path, items, pos }
+    }
+
+    // The name other modules import the statement by and whether it is `pub`
+    pub fn item(&self) -> Option<(&AsciiString, bool)> {
+        match self {
+            Self::Const { decl } => Some((&decl.name, decl.public)),
+            Self::Function { decl } => Some((&decl.name, decl.public)),
+            Self::Struct { decl } => Some((&decl.name, decl.public)),
+            Self::Enum { decl } => Some((&decl.name, decl.public)),
+            Self::Trait { decl } => Some((&decl.name, decl.public)),
+            _ => None,
+        }
+    }
+}
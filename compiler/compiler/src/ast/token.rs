@@ -1,53 +1,136 @@
+use crate::runtime::{bigint::BigInt, decimal::Decimal};
 use ascii::AsciiString;
+use std::{cell::RefCell, fmt::Display, rc::Rc};
 
-#[derive(Debug)]
-#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Eof,
     Keyword(Keyword),
     Literal(Literal),
     Operator(Operator),
     Identifier(AsciiString),
+    Label(AsciiString),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Literal {
     Null,
     Char(char),
     Boolean(bool),
     Number(Number),
+    Unsuffixed(Unsuffixed),
     String(String),
 }
 
-#[derive(Debug)]
+// A number written without a suffix, kept as written until the checker infers
+// its type from the context and sets `number`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unsuffixed {
+    pub digits: String,
+    pub number: Rc<RefCell<Option<Number>>>,
+}
+
+impl Unsuffixed {
+    pub fn new(digits: String) -> Self {
+        Self {
+            digits,
+            number: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        self.digits.contains('.')
+    }
+
+    // The parser folds a `-` before a number into a negative literal
+    pub fn negate(&self) -> Self {
+        match self.digits.strip_prefix('-') {
+            Some(digits) => Self::new(digits.to_owned()),
+            None => Self::new(format!("-{}", self.digits)),
+        }
+    }
+
+    pub fn value(&self) -> Number {
+        self.number
+            .borrow()
+            .clone()
+            .expect("unsuffixed numbers are typed by the checker")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Keyword {
+    Fn,
     In,
     If,
+    Dyn,
+    For,
+    Let,
     Mut,
+    Pub,
     Use,
     Else,
+    Enum,
+    Impl,
     Loop,
+    Move,
+    Break,
+    Match,
+    Trait,
+    Struct,
+    Return,
+    Continue,
+    Const,
+    As,
 }
 
 impl Keyword {
-    pub const KEYWORDS: [&str; 6] = ["in", "if", "mut", "use", "else", "loop"];
+    pub const KEYWORDS: [&str; 22] = [
+        "fn", "in", "if", "dyn", "for", "let", "mut", "pub", "use", "else", "enum", "impl", "loop",
+        "move", "break", "match", "trait", "struct", "return", "continue", "const", "as",
+    ];
 }
 
 impl From<AsciiString> for Keyword {
     fn from(keyword: AsciiString) -> Self {
         match keyword.to_string().as_str() {
+            "fn" => Self::Fn,
             "in" => Self::In,
             "if" => Self::If,
+            "dyn" => Self::Dyn,
+            "for" => Self::For,
+            "let" => Self::Let,
             "mut" => Self::Mut,
+            "pub" => Self::Pub,
             "use" => Self::Use,
             "else" => Self::Else,
+            "enum" => Self::Enum,
+            "impl" => Self::Impl,
             "loop" => Self::Loop,
+            "move" => Self::Move,
+            "break" => Self::Break,
+            "match" => Self::Match,
+            "trait" => Self::Trait,
+            "struct" => Self::Struct,
+            "return" => Self::Return,
+            "continue" => Self::Continue,
+            "const" => Self::Const,
+            "as" => Self::As,
             _ => unimplemented!(),
         }
     }
 }
 
-#[derive(Debug)]
+impl Display for Keyword {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keyword = format!("{self:?}").to_lowercase();
+        write!(f, "{keyword}")
+    }
+}
+
+// `BigInt` and `Decimal` are written with the suffixes `n` and `d`, as in
+// `123n` and `1.10d`, and shared since they do not change once computed
+#[derive(Debug, Clone, PartialEq)]
 pub enum Number {
     I8(i8),
     I16(i16),
@@ -63,16 +146,18 @@ pub enum Number {
     USize(usize),
     F32(f32),
     F64(f64),
+    BigInt(Rc<BigInt>),
+    Decimal(Rc<Decimal>),
 }
 
 impl Number {
-    pub const SUFFIXES: [&'static str; 16] = [
+    pub const SUFFIXES: [&'static str; 18] = [
         "i8", "i16", "i32", "i64", "i128", "isize", "u", "u8", "u16", "u32", "u64", "u128",
-        "usize", "f", "f32", "f64",
+        "usize", "f", "f32", "f64", "n", "d",
     ];
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Operator {
     Exclamation,
     Quotation,
@@ -112,6 +197,14 @@ pub enum Operator {
     LessThanOrEqualTo,
     GreaterThanOrEqualTo,
     Acceses,
+    And,
+    Or,
+    Arrow,
+    FatArrow,
+    Range,
+    RangeInclusive,
+    SafeAccess,
+    Coalesce,
 }
 
 impl From<char> for Operator {
@@ -152,3 +245,80 @@ impl From<char> for Operator {
         }
     }
 }
+
+impl Operator {
+    pub const fn symbol(&self) -> &'static str {
+        match self {
+            Operator::Exclamation => "!",
+            Operator::Quotation => "\"",
+            Operator::Hash => "#",
+            Operator::Dollar => "$",
+            Operator::Percent => "%",
+            Operator::Ampersand => "&",
+            Operator::Apostrophe => "'",
+            Operator::ParenOpen => "(",
+            Operator::ParenClose => ")",
+            Operator::Asterisk => "*",
+            Operator::Plus => "+",
+            Operator::Comma => ",",
+            Operator::Minus => "-",
+            Operator::Dot => ".",
+            Operator::Slash => "/",
+            Operator::Colon => ":",
+            Operator::Semicolon => ";",
+            Operator::LessThan => "<",
+            Operator::Assign => "=",
+            Operator::GreaterThan => ">",
+            Operator::Question => "?",
+            Operator::At => "@",
+            Operator::BracketOpen => "[",
+            Operator::BackSlash => "\\",
+            Operator::BracketClose => "]",
+            Operator::Caret => "^",
+            Operator::Grave => "`",
+            Operator::CurlyBraceOpen => "{",
+            Operator::VerticalBar => "|",
+            Operator::CurlyBraceClose => "}",
+            Operator::Tilde => "~",
+            Operator::EqualTo => "==",
+            Operator::NotEqualTo => "!=",
+            Operator::Increment => "++",
+            Operator::Decrement => "--",
+            Operator::LessThanOrEqualTo => "<=",
+            Operator::GreaterThanOrEqualTo => ">=",
+            Operator::Acceses => "::",
+            Operator::And => "&&",
+            Operator::Or => "||",
+            Operator::Arrow => "->",
+            Operator::FatArrow => "=>",
+            Operator::Range => "..",
+            Operator::RangeInclusive => "..=",
+            Operator::SafeAccess => "?.",
+            Operator::Coalesce => "??",
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Eof => write!(f, "end of file"),
+            Token::Keyword(keyword) => write!(f, "`{keyword}`"),
+            Token::Literal(Literal::Null) => write!(f, "`?`"),
+            Token::Literal(Literal::Char(c)) => write!(f, "{c:?}"),
+            Token::Literal(Literal::Boolean(b)) => write!(f, "`{b}`"),
+            Token::Literal(Literal::Number(n)) => write!(f, "`{n}`"),
+            Token::Literal(Literal::Unsuffixed(n)) => write!(f, "`{}`", n.digits),
+            Token::Literal(Literal::String(s)) => write!(f, "{s:?}"),
+            Token::Operator(operator) => write!(f, "`{operator}`"),
+            Token::Identifier(ident) => write!(f, "`{ident}`"),
+            Token::Label(label) => write!(f, "`'{label}`"),
+        }
+    }
+}
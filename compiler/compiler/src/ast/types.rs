@@ -0,0 +1,116 @@
+use super::expr::Expr;
+use crate::position::Position;
+use ascii::AsciiString;
+use std::fmt::Display;
+
+pub enum TypeExpr {
+    // `i32`, `Point` or `Map<String, i32>`
+    Named {
+        name: AsciiString,
+        args: Vec<Self>,
+        pos: Position,
+    },
+    // `(i32, String)`, where `()` is the unit type
+    Tuple {
+        elements: Vec<Self>,
+        pos: Position,
+    },
+    // `i32?`, the only types `?` can be assigned to
+    Optional {
+        inner: Box<Self>,
+        pos: Position,
+    },
+    // `[i32]`, or `[i32; N]` with exactly the elements the constant
+    // expression `N` counts
+    Array {
+        element: Box<Self>,
+        length: Option<Box<Expr>>,
+        pos: Position,
+    },
+    // `fn(i32, i32) -> bool`, returning `()` without an arrow
+    Function {
+        params: Vec<Self>,
+        ret: Option<Box<Self>>,
+        pos: Position,
+    },
+    // `dyn Shape`, a value of any type implementing the trait
+    Dyn {
+        name: AsciiString,
+        pos: Position,
+    },
+}
+
+impl TypeExpr {
+    pub fn named(name: AsciiString, args: Vec<Self>, pos: Position) -> Self {
+        Self::Named { name, args, pos }
+    }
+
+    pub fn tuple(elements: Vec<Self>, pos: Position) -> Self {
+        Self::Tuple { elements, pos }
+    }
+
+    pub fn optional(inner: Self, pos: Position) -> Self {
+        Self::Optional {
+            inner: Box::new(inner),
+            pos,
+        }
+    }
+
+    pub fn array(element: Self, length: Option<Expr>, pos: Position) -> Self {
+        Self::Array {
+            element: Box::new(element),
+            length: length.map(Box::new),
+            pos,
+        }
+    }
+
+    pub fn function(params: Vec<Self>, ret: Option<Self>, pos: Position) -> Self {
+        Self::Function {
+            params,
+            ret: ret.map(Box::new),
+            pos,
+        }
+    }
+
+    pub fn dyn_(name: AsciiString, pos: Position) -> Self {
+        Self::Dyn { name, pos }
+    }
+
+    pub fn pos(&self) -> &Position {
+        match self {
+            Self::Named { pos, .. }
+            | Self::Tuple { pos, .. }
+            | Self::Optional { pos, .. }
+            | Self::Array { pos, .. }
+            | Self::Function { pos, .. }
+            | Self::Dyn { pos, .. } => pos,
+        }
+    }
+}
+
+// Separates the displayed types with commas
+fn list(types: &[TypeExpr]) -> String {
+    let types = types.iter().map(TypeExpr::to_string).collect::<Vec<_>>();
+    types.join(", ")
+}
+
+impl Display for TypeExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Named { name, args, .. } if args.is_empty() => write!(f, "{name}"),
+            Self::Named { name, args, .. } => write!(f, "{name}<{}>", list(args)),
+            Self::Optional { inner, .. } => write!(f, "{inner}?"),
+            Self::Tuple { elements, .. } if elements.len() == 1 => write!(f, "({},)", elements[0]),
+            Self::Tuple { elements, .. } => write!(f, "({})", list(elements)),
+            Self::Array { element, .. } => write!(f, "[{element}]"),
+            Self::Function { params, ret, .. } => {
+                write!(f, "fn({})", list(params))?;
+                match ret {
+                    Some(ret) => write!(f, " -> {ret}"),
+                    None => Ok(()),
+                }
+            }
+            Self::Dyn { name, .. } => write!(f, "dyn {name}"),
+        }
+    }
+}
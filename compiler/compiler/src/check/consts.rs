@@ -0,0 +1,572 @@
+use super::{
+    resolve::{Resolution, SymbolKind},
+    Checker,
+};
+use crate::{
+    ast::{
+        expr::{Expr, Side},
+        pattern::Pattern,
+        stmt::{ConstDecl, FnDecl, Stmt},
+        token::{Literal, Number, Operator},
+    },
+    error::*,
+    position::Position,
+    runtime::{
+        interpreter::{self, Control, Flow},
+        value::Value,
+    },
+};
+use ascii::AsciiString;
+use std::{collections::HashMap, mem};
+
+const MAX_DEPTH: usize = 64;
+// The loop iterations and calls computing a constant may take
+const MAX_STEPS: usize = 1_000_000;
+
+// Computes constants, the lengths of array types and operations on literals
+// before the program runs, with the semantics of the interpreter. Only
+// literals, operators, constants, blocks, `let`, `if`, loops and calls to
+// `const fn`s can be computed.
+pub struct Evaluator<'a> {
+    checker: &'a Checker,
+    // The resolution of the module whose code is computed, which changes
+    // while a `const fn` of another module runs
+    resolution: &'a Resolution,
+    // The variables of the running `const fn`, by block
+    locals: Vec<HashMap<AsciiString, Value>>,
+    // The constants being computed, which can not depend on themselves
+    computing: Vec<Position>,
+    depth: usize,
+    steps: usize,
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(checker: &'a Checker) -> Self {
+        Self {
+            checker,
+            resolution: checker.resolution(),
+            locals: Vec::new(),
+            computing: Vec::new(),
+            depth: 0,
+            steps: 0,
+        }
+    }
+
+    // The value of a constant, computed once
+    pub fn constant(&mut self, decl: &ConstDecl) -> Result<Literal> {
+        if let Some(value) = decl.folded.get() {
+            return Ok(value.clone());
+        }
+        if self.computing.contains(&decl.pos) {
+            return Error::RecursiveConst(decl.name.to_string()).throw(&decl.pos);
+        }
+        self.computing.push(decl.pos.clone());
+        let locals = mem::take(&mut self.locals);
+        let result = self.run(&decl.value);
+        self.locals = locals;
+        self.computing.pop();
+        let value = literal(result?, decl.value.pos())?;
+        Ok(decl.folded.get_or_init(|| value).clone())
+    }
+
+    // The length of an array type, a `usize`
+    pub fn length(&mut self, length: &Expr) -> Result<usize> {
+        match self.run(length)? {
+            Value::Number(Number::USize(length)) => Ok(length),
+            value => Error::MismatchedTypes(String::from("usize"), value.type_name(), None)
+                .throw(length.pos()),
+        }
+    }
+
+    // Computes an operation on literals and constants, to report what would
+    // fail at runtime before the program runs
+    pub fn fold(&mut self, expr: &Expr) -> Result<Value> {
+        self.run(expr)
+    }
+
+    fn run(&mut self, expr: &Expr) -> Result<Value> {
+        match self.evaluate(expr) {
+            Ok(value) | Err(Control::Return(value)) => Ok(value),
+            Err(Control::Error(error)) => Err(error),
+            // The parser rejects `break` and `continue` outside of loops
+            Err(_) => unreachable!(),
+        }
+    }
+
+    fn not_const<T>(what: String, pos: &Position) -> Flow<T> {
+        Ok(Error::NotConst(what).throw(pos)?)
+    }
+
+    // Counts a loop iteration or a call against the limit
+    fn step(&mut self, pos: &Position) -> Result<()> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return Error::EvaluationLimit.throw(pos);
+        }
+        Ok(())
+    }
+
+    fn scoped(&mut self, evaluate: impl FnOnce(&mut Self) -> Flow<Value>) -> Flow<Value> {
+        self.locals.push(HashMap::new());
+        let result = evaluate(self);
+        self.locals.pop();
+        result
+    }
+
+    fn local(&mut self, name: &AsciiString) -> Option<&mut Value> {
+        self.locals
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+    }
+
+    fn evaluate(&mut self, expr: &Expr) -> Flow<Value> {
+        Ok(match expr {
+            Expr::Literal { value, pos } => match value {
+                // Only the lengths of array types are not typed by the type
+                // checker, where numbers are `usize`
+                Literal::Unsuffixed(number) if number.number.borrow().is_none() => {
+                    let suffix = if number.is_float() { "f64" } else { "usize" };
+                    match Number::parse(&number.digits, suffix) {
+                        Ok(number) => Value::Number(number),
+                        Err(error) => error.throw(pos)?,
+                    }
+                }
+                value => Value::from(value.clone()),
+            },
+            Expr::Group { expresion } | Expr::TypeArgs { expresion, .. } => {
+                self.evaluate(expresion)?
+            }
+            Expr::Cast { expresion, ty, pos } => {
+                interpreter::cast(self.evaluate(expresion)?, ty, pos)?
+            }
+            Expr::Variable { name, pos } => self.variable(name, pos)?,
+            Expr::Unary {
+                operator,
+                expresion,
+                pos,
+            } => self.unary(*operator, expresion, pos)?,
+            Expr::Binary {
+                left,
+                operator,
+                right,
+                pos,
+            } => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                interpreter::binary(&left, *operator, &right, pos)?
+            }
+            Expr::Logical {
+                left,
+                operator: Operator::Coalesce,
+                right,
+                ..
+            } => match self.evaluate(left)? {
+                Value::Null => self.evaluate(right)?,
+                value => value,
+            },
+            Expr::Logical {
+                left,
+                operator,
+                right,
+                pos,
+            } => {
+                let left = interpreter::truthy(self.evaluate(left)?, *operator, pos)?;
+                if left == (*operator == Operator::Or) {
+                    Value::Boolean(left)
+                } else {
+                    let right = interpreter::truthy(self.evaluate(right)?, *operator, pos)?;
+                    Value::Boolean(right)
+                }
+            }
+            Expr::Block { stmts, tail, .. } => self.scoped(|evaluator| {
+                for stmt in stmts {
+                    evaluator.stmt(stmt)?;
+                }
+                match tail {
+                    Some(tail) => evaluator.evaluate(tail),
+                    None => Ok(Value::Unit),
+                }
+            })?,
+            Expr::Assign { target, value, pos } => {
+                let value = self.evaluate(value)?;
+                self.assign(target, value.clone(), pos)?;
+                value
+            }
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => match self.evaluate(condition)? {
+                Value::Boolean(true) => self.evaluate(then_branch)?,
+                Value::Boolean(false) => match else_branch {
+                    Some(else_branch) => self.evaluate(else_branch)?,
+                    None => Value::Unit,
+                },
+                value => Error::InvalidCondition(value.type_name()).throw(condition.pos())?,
+            },
+            Expr::Loop { label, body, pos } => loop {
+                self.step(pos)?;
+                if let Some(value) = interpreter::iteration(label, self.evaluate(body))? {
+                    break value;
+                }
+            },
+            Expr::LoopIn {
+                label,
+                pattern,
+                iterable,
+                body,
+                pos,
+            } => self.loop_in(label, pattern, iterable, body, pos)?,
+            Expr::Break { label, value, .. } => {
+                let value = match value {
+                    Some(value) => self.evaluate(value)?,
+                    None => Value::Unit,
+                };
+                return Err(Control::Break(label.clone(), value));
+            }
+            Expr::Continue { label, .. } => return Err(Control::Continue(label.clone())),
+            Expr::Return { value, .. } => {
+                let value = match value {
+                    Some(value) => self.evaluate(value)?,
+                    None => Value::Unit,
+                };
+                return Err(Control::Return(value));
+            }
+            Expr::Tuple { elements, .. } if elements.is_empty() => Value::Unit,
+            Expr::Call {
+                callee,
+                arguments,
+                pos,
+            } => self.call(callee, arguments, pos)?,
+            expr => Self::not_const(String::from(describe(expr)), expr.pos())?,
+        })
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> Flow<()> {
+        match stmt {
+            Stmt::Expr { expresion } => {
+                self.evaluate(expresion)?;
+            }
+            Stmt::Let {
+                pattern,
+                value: Some(value),
+                ..
+            } => {
+                let value = self.evaluate(value)?;
+                self.bind(pattern, value)?;
+            }
+            Stmt::Let { pattern, .. } => self.bind(pattern, Value::Unit)?,
+            // Items are computed where they are used
+            Stmt::Const { .. } | Stmt::Function { .. } => {}
+            stmt => {
+                let what = String::from("a declaration of a type or trait");
+                let pos = match stmt {
+                    Stmt::Struct { decl } => &decl.pos,
+                    Stmt::Enum { decl } => &decl.pos,
+                    Stmt::Trait { decl } => &decl.pos,
+                    Stmt::Impl { pos, .. } | Stmt::Use { pos, .. } => pos,
+                    _ => unreachable!(),
+                };
+                Self::not_const(what, pos)?
+            }
+        }
+        Ok(())
+    }
+
+    // Only plain variables can be declared
+    fn bind(&mut self, pattern: &Pattern, value: Value) -> Flow<()> {
+        match pattern {
+            Pattern::Binding { name, constant, .. } if constant.get().is_none() => {
+                self.locals.last_mut().unwrap().insert(name.clone(), value);
+                Ok(())
+            }
+            Pattern::Wildcard { .. } => Ok(()),
+            pattern => Self::not_const(String::from("a destructuring pattern"), pattern.pos()),
+        }
+    }
+
+    fn assign(&mut self, target: &Expr, value: Value, pos: &Position) -> Flow<()> {
+        if let Expr::Variable { name, .. } = target {
+            if let Some(variable) = self.local(name) {
+                *variable = value;
+                return Ok(());
+            }
+        }
+        let what = String::from("an assignment to anything but a local variable");
+        Self::not_const(what, pos)
+    }
+
+    // What a name refers to. Constants of other modules were computed when
+    // the modules were checked.
+    fn variable(&mut self, name: &AsciiString, pos: &Position) -> Flow<Value> {
+        let index = self.resolution.uses.get(pos).copied();
+        let kind = index.map(|index| self.resolution.symbols[index].kind);
+        if let Some(SymbolKind::Variable { .. } | SymbolKind::Parameter { .. }) = kind {
+            if let Some(value) = self.local(name) {
+                return Ok(value.clone());
+            }
+        }
+        match index {
+            Some(index) if self.resolution.is_constant(index) => {
+                Ok(Value::from(self.named(index)?))
+            }
+            _ => Self::not_const(format!("`{name}`"), pos),
+        }
+    }
+
+    // The value of the constant declared or imported as the symbol `index`
+    pub fn named(&mut self, index: usize) -> Result<Literal> {
+        let resolution = self.resolution;
+        if let Some(decl) = resolution.consts.get(&index) {
+            return self.constant(decl);
+        }
+        let imported = &resolution.imported[&index];
+        let module = self.checker.module_resolution(&imported.module).unwrap();
+        let previous = mem::replace(&mut self.resolution, module);
+        let value = self.constant(&module.consts[&imported.index]);
+        self.resolution = previous;
+        value
+    }
+
+    fn unary(
+        &mut self,
+        operator: Operator,
+        expresion: &Side<Box<Expr>>,
+        pos: &Position,
+    ) -> Flow<Value> {
+        let operand = match expresion {
+            Side::Left(operand) | Side::Right(operand) => operand,
+        };
+        let value = self.evaluate(operand)?;
+        Ok(match (operator, &value) {
+            (Operator::Exclamation, Value::Boolean(b)) => Value::Boolean(!b),
+            (Operator::Minus, Value::Number(n)) => match n.negate() {
+                Ok(n) => Value::Number(n),
+                Err(error) => error.throw(pos)?,
+            },
+            (Operator::Increment | Operator::Decrement, Value::Number(n)) => {
+                let by = if operator == Operator::Increment {
+                    Operator::Plus
+                } else {
+                    Operator::Minus
+                };
+                let updated = match n.arithmetic(by, &n.one()) {
+                    Ok(n) => Value::Number(n),
+                    Err(error) => error.throw(pos)?,
+                };
+                self.assign(operand, updated.clone(), pos)?;
+                match expresion {
+                    Side::Right(_) => updated,
+                    Side::Left(_) => value,
+                }
+            }
+            _ => Error::InvalidOperand(operator.to_string(), value.type_name()).throw(pos)?,
+        })
+    }
+
+    // `loop i in a..b` over numbers
+    fn loop_in(
+        &mut self,
+        label: &Option<AsciiString>,
+        pattern: &Pattern,
+        iterable: &Expr,
+        body: &Expr,
+        pos: &Position,
+    ) -> Flow<Value> {
+        let Expr::Range {
+            start: Some(start),
+            end: Some(end),
+            inclusive,
+            pos: range,
+        } = iterable
+        else {
+            let what = String::from("a loop over anything but a range of numbers");
+            return Self::not_const(what, iterable.pos());
+        };
+        let mut current = self.evaluate(start)?;
+        let end = self.evaluate(end)?;
+        let (Value::Number(first), Value::Number(_)) = (&current, &end) else {
+            let what = String::from("a loop over anything but a range of numbers");
+            return Self::not_const(what, range);
+        };
+        let one = Value::Number(first.one());
+        let comparison = if *inclusive {
+            Operator::LessThanOrEqualTo
+        } else {
+            Operator::LessThan
+        };
+        loop {
+            if let Value::Boolean(false) = interpreter::binary(&current, comparison, &end, range)? {
+                break Ok(Value::Unit);
+            }
+            self.step(pos)?;
+            let value = current.clone();
+            let result = self.scoped(|evaluator| {
+                evaluator.bind(pattern, value)?;
+                evaluator.evaluate(body)
+            });
+            if let Some(value) = interpreter::iteration(label, result)? {
+                break Ok(value);
+            }
+            // The last number of an inclusive range may have no successor
+            if *inclusive
+                && matches!(
+                    interpreter::binary(&current, Operator::EqualTo, &end, range)?,
+                    Value::Boolean(true)
+                )
+            {
+                break Ok(Value::Unit);
+            }
+            current = interpreter::binary(&current, Operator::Plus, &one, range)?;
+        }
+    }
+
+    // Only `const fn`s can be called, by name
+    fn call(&mut self, callee: &Expr, arguments: &[Expr], pos: &Position) -> Flow<Value> {
+        let resolution = self.resolution;
+        let index = match callee {
+            Expr::Variable { pos, .. } => resolution.uses.get(pos).copied(),
+            _ => None,
+        };
+        let (decl, module) = match index {
+            Some(index) if resolution.const_fns.contains_key(&index) => {
+                (&resolution.const_fns[&index], resolution)
+            }
+            Some(index) if resolution.imported.contains_key(&index) => {
+                let imported = &resolution.imported[&index];
+                let module = self.checker.module_resolution(&imported.module).unwrap();
+                match module.const_fns.get(&imported.index) {
+                    Some(decl) => (decl, module),
+                    None => return self.not_callable(callee),
+                }
+            }
+            _ => return self.not_callable(callee),
+        };
+        if arguments.len() != decl.params.len() {
+            let name = decl.name.to_string();
+            let declared = Some(decl.pos.clone());
+            Error::ArityMismatch(name, decl.params.len(), arguments.len(), declared).throw(pos)?;
+        }
+        let mut frame = HashMap::new();
+        for (param, argument) in decl.params.iter().zip(arguments) {
+            frame.insert(param.name.clone(), self.evaluate(argument)?);
+        }
+        if self.depth == MAX_DEPTH {
+            Error::StackOverflow(decl.name.to_string()).throw(pos)?;
+        }
+        self.step(pos)?;
+        Ok(self.function(decl, frame, module)?)
+    }
+
+    fn function(
+        &mut self,
+        decl: &FnDecl,
+        frame: HashMap<AsciiString, Value>,
+        module: &'a Resolution,
+    ) -> Result<Value> {
+        let locals = mem::replace(&mut self.locals, vec![frame]);
+        let resolution = mem::replace(&mut self.resolution, module);
+        self.depth += 1;
+        let result = self.run(&decl.body);
+        self.depth -= 1;
+        self.resolution = resolution;
+        self.locals = locals;
+        result
+    }
+
+    fn not_callable(&self, callee: &Expr) -> Flow<Value> {
+        let what = match callee {
+            Expr::Variable { name, .. } => {
+                format!("a call to `{name}`, which is not a `const fn`,")
+            }
+            _ => String::from("a call to anything but a `const fn`"),
+        };
+        Self::not_const(what, callee.pos())
+    }
+}
+
+// Whether an expression is made of nothing but literals, constants and
+// operators, which the checker computes wherever they appear
+pub fn foldable(resolution: &Resolution, expr: &Expr) -> bool {
+    match expr {
+        Expr::Literal { .. } => true,
+        Expr::Group { expresion } => foldable(resolution, expresion),
+        Expr::Variable { pos, .. } => resolution
+            .uses
+            .get(pos)
+            .is_some_and(|&index| resolution.is_constant(index)),
+        Expr::Unary {
+            operator: Operator::Minus | Operator::Exclamation,
+            expresion: Side::Left(operand) | Side::Right(operand),
+            ..
+        } => foldable(resolution, operand),
+        Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+            foldable(resolution, left) && foldable(resolution, right)
+        }
+        Expr::Cast { expresion, .. } => foldable(resolution, expresion),
+        _ => false,
+    }
+}
+
+// Constants hold values a literal can write
+fn literal(value: Value, pos: &Position) -> Result<Literal> {
+    Ok(match value {
+        Value::Null => Literal::Null,
+        Value::Char(c) => Literal::Char(c),
+        Value::Boolean(b) => Literal::Boolean(b),
+        Value::Number(n) => Literal::Number(n),
+        Value::String(s) => Literal::String(s),
+        value => {
+            return Error::NotConst(format!("a value of type {}", value.type_name())).throw(pos)
+        }
+    })
+}
+
+fn describe(expr: &Expr) -> &'static str {
+    match expr {
+        Expr::Array { .. } => "an array",
+        Expr::Closure { .. } => "a closure",
+        Expr::Field { .. } | Expr::TupleIndex { .. } => "a field access",
+        Expr::Index { .. } => "an index",
+        Expr::Map { .. } => "a map",
+        Expr::Match { .. } => "a `match`",
+        Expr::Method { .. } => "a method call",
+        Expr::Path { .. } | Expr::Struct { .. } => "a struct or enum value",
+        Expr::Range { .. } => "a range",
+        Expr::Tuple { .. } => "a tuple",
+        _ => "the expression",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::Error, io::run};
+
+    #[test]
+    fn constants_are_computed_before_the_program_runs() {
+        // The program would never get to the overflow
+        let overflow = run("fn f() { loop {} } f(); 200u8 + 100u8");
+        assert!(matches!(overflow, Err(Error::NumOverFlow(_, ty)) if ty == "u8"));
+        let source = "const fn sq(n: i32) -> i32 { n * n } const M: i32 = sq(4) + 1; M";
+        assert_eq!(run(source).unwrap(), "17");
+        let source = "const N: usize = 2 + 3; let a: [i32; N] = [1, 2, 3, 4, 5]; a.len()";
+        assert_eq!(run(source).unwrap(), "5");
+        let length = run("const N: usize = 2; let a: [i32; N] = [1, 2, 3];");
+        assert!(matches!(length, Err(Error::ArrayLength(2, 3))));
+        // Patterns naming a constant compare against it
+        let source = "const N: i32 = 3; (match 3 { N => 1, _ => 2 }, match 4 { N => 1, _ => 2 })";
+        assert_eq!(run(source).unwrap(), "(1, 2)");
+    }
+
+    #[test]
+    fn constants_only_use_what_is_known_before_running() {
+        let not_const = run("let x = 1; const N: i32 = x;");
+        assert!(matches!(not_const, Err(Error::NotConst(_))));
+        let recursive = run("const A: i32 = B; const B: i32 = A;");
+        assert!(matches!(recursive, Err(Error::RecursiveConst(_))));
+        let endless = run("const fn f(n: i32) -> i32 { loop {} } const X: i32 = f(1);");
+        assert!(matches!(endless, Err(Error::EvaluationLimit)));
+    }
+}
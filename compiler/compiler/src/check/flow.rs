@@ -0,0 +1,719 @@
+use super::resolve::{Resolution, SymbolKind};
+use crate::{
+    ast::{
+        expr::{Expr, Side},
+        pattern::Pattern,
+        stmt::{FnDecl, Stmt},
+        token::Operator,
+    },
+    error::*,
+    position::Position,
+};
+use ascii::AsciiString;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    mem,
+};
+
+// What a node of the graph does to the local variables, which are the indices
+// of their symbols
+enum Event {
+    // Where paths split and join
+    Nop,
+    // `let x;`
+    Declare(usize),
+    // Declaring a variable with a value, at the `let` it comes from. Parameters
+    // and the bindings of loops and arms are not assignments to warn about.
+    Bind(usize, Option<Position>),
+    Assign(usize, Position),
+    Read(usize, Position),
+}
+
+impl Event {
+    fn variable(&self) -> Option<usize> {
+        match self {
+            Self::Nop => None,
+            Self::Declare(index)
+            | Self::Bind(index, _)
+            | Self::Assign(index, _)
+            | Self::Read(index, _) => Some(*index),
+        }
+    }
+}
+
+// Why a path takes an edge, told when a read misses the assignment
+#[derive(Clone)]
+enum Step {
+    True(Position),
+    False(Position),
+    Arm(Position),
+    GuardFails(Position),
+    Runs(Position),
+    Ends(Position),
+    Repeats(Position),
+    Break(Position),
+    Continue(Position),
+    Skips(Operator, Position),
+    Null(Position),
+}
+
+type Edge = (usize, Option<Step>);
+
+struct Node {
+    event: Event,
+    succs: Vec<Edge>,
+}
+
+struct Loop {
+    label: Option<AsciiString>,
+    head: usize,
+    breaks: Vec<Edge>,
+}
+
+// The variables each path to a node may leave uninitialized and the ones it
+// may have assigned
+#[derive(Clone, Default, PartialEq)]
+struct State {
+    uninit: HashSet<usize>,
+    init: HashSet<usize>,
+}
+
+// Rejects reading a variable declared by `let x;` where some path to the read
+// does not assign it, and assigning it twice unless it is `mut`. Every function
+// body gets a graph of its own, closures are part of the one creating them.
+// Warns about the variables never read, the values assigned and never read
+// after, and the code no path reaches.
+pub fn check(resolution: &Resolution, stmts: &[Stmt]) -> Result<Vec<(Warning, Position)>> {
+    let declared = resolution
+        .symbols
+        .iter()
+        .enumerate()
+        .filter(|(_, symbol)| {
+            matches!(
+                symbol.kind,
+                SymbolKind::Variable { .. } | SymbolKind::Parameter { .. }
+            )
+        })
+        .filter_map(|(index, symbol)| Some((symbol.pos.clone()?, index)))
+        .collect();
+    let mut graph = Graph::new(resolution, &declared);
+    graph.block(stmts, None);
+    let mut bodies = mem::take(&mut graph.bodies);
+    let mut graphs = vec![graph];
+    while let Some(decl) = bodies.pop() {
+        let mut graph = Graph::new(resolution, &declared);
+        graph.function(decl);
+        bodies.append(&mut graph.bodies);
+        graphs.push(graph);
+    }
+
+    // Variables closures or other functions use can be read at any time
+    let mut graphs_using: HashMap<usize, usize> = HashMap::new();
+    let mut shared = HashSet::new();
+    let mut read = HashSet::new();
+    for graph in &graphs {
+        for index in &graph.used {
+            *graphs_using.entry(*index).or_default() += 1;
+        }
+        shared.extend(&graph.captured);
+        read.extend(graph.nodes.iter().filter_map(|node| match node.event {
+            Event::Read(index, _) => Some(index),
+            _ => None,
+        }));
+    }
+    shared.extend(
+        graphs_using
+            .into_iter()
+            .filter(|&(_, n)| n > 1)
+            .map(|(index, _)| index),
+    );
+
+    let mut warnings = Vec::new();
+    for graph in &graphs {
+        let states = graph.analyze()?;
+        warnings.extend(
+            graph
+                .unreachable
+                .iter()
+                .map(|pos| (Warning::UnreachableCode, pos.clone())),
+        );
+        for (index, pos) in graph.unused_assignments(&states) {
+            if read.contains(&index) && !shared.contains(&index) {
+                let name = resolution.symbols[index].name.to_string();
+                warnings.push((Warning::UnusedAssignment(name), pos));
+            }
+        }
+        for node in &graph.nodes {
+            let (Event::Declare(index) | Event::Bind(index, _)) = node.event else {
+                continue;
+            };
+            let symbol = &resolution.symbols[index];
+            let ignored = symbol.name.as_str().starts_with('_') || symbol.name == "self";
+            if let Some(pos) = symbol
+                .pos
+                .as_ref()
+                .filter(|_| !read.contains(&index) && !ignored)
+            {
+                let name = symbol.name.to_string();
+                warnings.push((Warning::UnusedVariable(name), pos.clone()));
+            }
+        }
+    }
+    Ok(warnings)
+}
+
+struct Graph<'a> {
+    resolution: &'a Resolution,
+    // The symbol each variable declared in the program is
+    declared: &'a HashMap<Position, usize>,
+    nodes: Vec<Node>,
+    // The edges into the node emitted next, empty where the code is unreachable
+    frontier: Vec<Edge>,
+    loops: Vec<Loop>,
+    // The functions declared in the code, which are analyzed after it
+    bodies: Vec<&'a FnDecl>,
+    // How many closures the code emitted is in
+    closures: usize,
+    // The variables the events are about and the ones closures use
+    used: HashSet<usize>,
+    captured: HashSet<usize>,
+    // The first statement of each block that no path reaches
+    unreachable: Vec<Position>,
+}
+
+impl<'a> Graph<'a> {
+    fn new(resolution: &'a Resolution, declared: &'a HashMap<Position, usize>) -> Self {
+        let entry = Node {
+            event: Event::Nop,
+            succs: Vec::new(),
+        };
+        Self {
+            resolution,
+            declared,
+            nodes: vec![entry],
+            frontier: vec![(0, None)],
+            loops: Vec::new(),
+            bodies: Vec::new(),
+            closures: 0,
+            used: HashSet::new(),
+            captured: HashSet::new(),
+            unreachable: Vec::new(),
+        }
+    }
+
+    fn emit(&mut self, event: Event) -> usize {
+        if let Some(index) = event.variable() {
+            self.used.insert(index);
+            if self.closures > 0 {
+                self.captured.insert(index);
+            }
+        }
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            event,
+            succs: Vec::new(),
+        });
+        for (pred, step) in mem::take(&mut self.frontier) {
+            self.nodes[pred].succs.push((node, step));
+        }
+        self.frontier = vec![(node, None)];
+        node
+    }
+
+    // Continues at `target`, the code after the jump is unreachable
+    fn jump(&mut self, target: usize, step: Step) {
+        let node = self.emit(Event::Nop);
+        self.nodes[node].succs.push((target, Some(step)));
+        self.frontier.clear();
+    }
+
+    // The loop a `break` or `continue` leaves, the parser keeps them in loops
+    fn target(&mut self, label: &Option<AsciiString>) -> Option<&mut Loop> {
+        self.loops
+            .iter_mut()
+            .rev()
+            .find(|target| label.is_none() || &target.label == label)
+    }
+
+    // The local variable used at `pos`
+    fn tracked(&self, pos: &Position) -> Option<usize> {
+        let index = *self.resolution.uses.get(pos)?;
+        matches!(
+            self.resolution.symbols[index].kind,
+            SymbolKind::Variable { .. } | SymbolKind::Parameter { .. }
+        )
+        .then_some(index)
+    }
+
+    fn function(&mut self, decl: &'a FnDecl) {
+        for param in &decl.params {
+            if let Some(&index) = self.declared.get(&param.pos) {
+                self.emit(Event::Bind(index, None));
+            }
+        }
+        self.expr(&decl.body);
+    }
+
+    // Declares the variables of a pattern, `at` is where a `let` assigns them
+    fn bind(&mut self, pattern: &Pattern, at: bool) {
+        match pattern {
+            Pattern::Binding { pos, .. } => {
+                if let Some(&index) = self.declared.get(pos) {
+                    self.emit(Event::Bind(index, at.then(|| pos.clone())));
+                }
+            }
+            Pattern::Struct { fields, .. } => {
+                for (_, pattern) in fields {
+                    self.bind(pattern, at);
+                }
+            }
+            Pattern::Tuple { elements, .. } | Pattern::Variant { elements, .. } => {
+                for pattern in elements {
+                    self.bind(pattern, at);
+                }
+            }
+            Pattern::Literal { .. }
+            | Pattern::Range { .. }
+            | Pattern::Path { .. }
+            | Pattern::Wildcard { .. } => {}
+        }
+    }
+
+    // Only the first statement no path reaches is reported, in blocks a path
+    // reaches, items are not code that runs
+    fn block(&mut self, stmts: &'a [Stmt], tail: Option<&'a Expr>) {
+        let mut reported = self.frontier.is_empty();
+        let mut reach = |graph: &mut Self, pos: &Position| {
+            if !reported && graph.frontier.is_empty() {
+                graph.unreachable.push(pos.clone());
+                reported = true;
+            }
+        };
+        for stmt in stmts {
+            match stmt {
+                Stmt::Expr { expresion } => reach(self, expresion.start()),
+                Stmt::Let { pos, .. } => reach(self, pos),
+                _ => {}
+            }
+            self.stmt(stmt);
+        }
+        if let Some(tail) = tail {
+            reach(self, tail.start());
+            self.expr(tail);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &'a Stmt) {
+        match stmt {
+            Stmt::Expr { expresion } => self.expr(expresion),
+            Stmt::Let { pattern, value, .. } => match (value, pattern) {
+                (Some(value), _) => {
+                    self.expr(value);
+                    self.bind(pattern, true);
+                }
+                (None, Pattern::Binding { pos, .. }) => {
+                    if let Some(&index) = self.declared.get(pos) {
+                        self.emit(Event::Declare(index));
+                    }
+                }
+                (None, _) => {}
+            },
+            Stmt::Function { decl } => self.bodies.push(decl),
+            Stmt::Impl { methods, .. } => self.bodies.extend(methods.iter().map(|m| &**m)),
+            Stmt::Trait { decl } => {
+                let provided = decl.methods.iter().filter(|method| method.provided);
+                self.bodies.extend(provided.map(|method| &*method.decl));
+            }
+            // Constants are computed before the program runs
+            Stmt::Const { .. } | Stmt::Struct { .. } | Stmt::Enum { .. } | Stmt::Use { .. } => {}
+        }
+    }
+
+    fn exprs(&mut self, exprs: impl IntoIterator<Item = &'a Expr>) {
+        for expr in exprs {
+            self.expr(expr);
+        }
+    }
+
+    // Follows the order the interpreter evaluates the expression in
+    fn expr(&mut self, expr: &'a Expr) {
+        match expr {
+            Expr::Array { elements, .. } | Expr::Tuple { elements, .. } => self.exprs(elements),
+            Expr::Assign { target, value, pos } => {
+                self.expr(value);
+                match &**target {
+                    Expr::Variable { pos: used, .. } => {
+                        if let Some(index) = self.tracked(used) {
+                            self.emit(Event::Assign(index, pos.clone()));
+                        }
+                    }
+                    target => self.expr(target),
+                }
+            }
+            Expr::Binary { left, right, .. } => self.exprs([&**left, &**right]),
+            Expr::Block { stmts, tail, .. } => self.block(stmts, tail.as_deref()),
+            Expr::Break { label, value, pos } => {
+                self.exprs(value.as_deref());
+                let node = self.emit(Event::Nop);
+                self.frontier.clear();
+                if let Some(target) = self.target(label) {
+                    target.breaks.push((node, Some(Step::Break(pos.clone()))));
+                }
+            }
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                self.expr(callee);
+                self.exprs(arguments);
+            }
+            // The body reads what the variables hold when the closure is
+            // created, what it assigns does not leave it
+            Expr::Closure { decl, .. } => {
+                let node = self.emit(Event::Nop);
+                let loops = mem::take(&mut self.loops);
+                self.closures += 1;
+                self.function(decl);
+                self.closures -= 1;
+                self.loops = loops;
+                self.frontier = vec![(node, None)];
+            }
+            Expr::Continue { label, pos } => {
+                if let Some(head) = self.target(label).map(|target| target.head) {
+                    self.jump(head, Step::Continue(pos.clone()));
+                }
+            }
+            Expr::Field { object, .. } | Expr::TupleIndex { object, .. } => self.expr(object),
+            Expr::Group { expresion }
+            | Expr::TypeArgs { expresion, .. }
+            | Expr::Cast { expresion, .. } => self.expr(expresion),
+            Expr::Index { object, index, .. } => self.exprs([&**object, &**index]),
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.expr(condition);
+                let split = self.emit(Event::Nop);
+                let pos = condition.pos().clone();
+                self.frontier = vec![(split, Some(Step::True(pos.clone())))];
+                self.expr(then_branch);
+                let then = mem::take(&mut self.frontier);
+                self.frontier = vec![(split, Some(Step::False(pos)))];
+                self.exprs(else_branch.as_deref());
+                self.frontier.extend(then);
+            }
+            Expr::Logical {
+                left,
+                operator,
+                right,
+                pos,
+            } => {
+                self.expr(left);
+                let split = self.emit(Event::Nop);
+                self.expr(right);
+                let skipped = Step::Skips(*operator, pos.clone());
+                self.frontier.push((split, Some(skipped)));
+            }
+            Expr::Map { entries, .. } => {
+                for (key, value) in entries {
+                    self.exprs([key, value]);
+                }
+            }
+            Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                self.expr(scrutinee);
+                self.emit(Event::Nop);
+                let mut ends = Vec::new();
+                for arm in arms {
+                    // An arm is tried when the ones before it do not match
+                    let tried = self.emit(Event::Nop);
+                    let pos = arm.pattern.pos().clone();
+                    self.frontier = vec![(tried, Some(Step::Arm(pos)))];
+                    self.bind(&arm.pattern, false);
+                    let mut next = vec![(tried, None)];
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                        let split = self.emit(Event::Nop);
+                        next.push((split, Some(Step::GuardFails(guard.pos().clone()))));
+                    }
+                    self.expr(&arm.body);
+                    ends.append(&mut self.frontier);
+                    self.frontier = next;
+                }
+                // Matches are exhaustive, no path runs out of arms
+                self.frontier = ends;
+            }
+            Expr::Method {
+                receiver,
+                arguments,
+                safe,
+                pos,
+                ..
+            } => {
+                self.expr(receiver);
+                let split = self.emit(Event::Nop);
+                self.exprs(arguments);
+                if *safe {
+                    self.frontier.push((split, Some(Step::Null(pos.clone()))));
+                }
+            }
+            Expr::Loop { label, body, pos } => {
+                let head = self.emit(Event::Nop);
+                self.looped(label, head, body, pos);
+            }
+            Expr::LoopIn {
+                label,
+                pattern,
+                iterable,
+                body,
+                pos,
+            } => {
+                self.expr(iterable);
+                let head = self.emit(Event::Nop);
+                self.frontier = vec![(head, Some(Step::Runs(pos.clone())))];
+                self.bind(pattern, false);
+                self.looped(label, head, body, pos);
+                self.frontier.push((head, Some(Step::Ends(pos.clone()))));
+            }
+            Expr::Range { start, end, .. } => {
+                self.exprs(start.as_deref());
+                self.exprs(end.as_deref());
+            }
+            Expr::Return { value, .. } => {
+                self.exprs(value.as_deref());
+                self.frontier.clear();
+            }
+            Expr::Struct { fields, .. } => self.exprs(fields.iter().map(|(_, value)| value)),
+            Expr::Unary {
+                expresion: Side::Left(operand) | Side::Right(operand),
+                ..
+            } => self.expr(operand),
+            Expr::Variable { pos, .. } => {
+                if let Some(index) = self.tracked(pos) {
+                    self.emit(Event::Read(index, pos.clone()));
+                }
+            }
+            Expr::Literal { .. } | Expr::Path { .. } => {}
+        }
+    }
+
+    // The body runs again after its end and `continue`, the loop ends at `break`
+    fn looped(&mut self, label: &Option<AsciiString>, head: usize, body: &'a Expr, pos: &Position) {
+        self.loops.push(Loop {
+            label: label.clone(),
+            head,
+            breaks: Vec::new(),
+        });
+        self.expr(body);
+        self.jump(head, Step::Repeats(pos.clone()));
+        let done = self.loops.pop().unwrap();
+        self.frontier = done.breaks;
+    }
+
+    // Finds what each path may leave uninitialized, then checks the reads
+    // and assignments in the order they were emitted. Nodes no path reaches
+    // have no state.
+    fn analyze(&self) -> Result<Vec<Option<State>>> {
+        let mut states: Vec<Option<State>> = vec![None; self.nodes.len()];
+        states[0] = Some(State::default());
+        let mut work = VecDeque::from([0]);
+        while let Some(node) = work.pop_front() {
+            let mut state = states[node].clone().unwrap();
+            match &self.nodes[node].event {
+                Event::Declare(index) => {
+                    state.uninit.insert(*index);
+                    state.init.remove(index);
+                }
+                Event::Assign(index, _) => {
+                    state.uninit.remove(index);
+                    state.init.insert(*index);
+                }
+                Event::Nop | Event::Bind(..) | Event::Read(..) => {}
+            }
+            for (succ, _) in &self.nodes[node].succs {
+                let merged = match &states[*succ] {
+                    Some(old) => State {
+                        uninit: old.uninit.union(&state.uninit).copied().collect(),
+                        init: old.init.union(&state.init).copied().collect(),
+                    },
+                    None => state.clone(),
+                };
+                if states[*succ].as_ref() != Some(&merged) {
+                    states[*succ] = Some(merged);
+                    work.push_back(*succ);
+                }
+            }
+        }
+
+        for (node, state) in self.nodes.iter().zip(&states) {
+            let Some(state) = state else {
+                continue;
+            };
+            match &node.event {
+                Event::Read(index, pos) if state.uninit.contains(index) => {
+                    let name = self.resolution.symbols[*index].name.to_string();
+                    let path = self.path(*index, pos, &states);
+                    return Error::UninitializedRead(name, path).throw(pos);
+                }
+                Event::Assign(index, pos)
+                    if state.init.contains(index) && self.resolution.deferred.contains(index) =>
+                {
+                    let symbol = &self.resolution.symbols[*index];
+                    if symbol.kind == (SymbolKind::Variable { mutable: false }) {
+                        let name = symbol.name.to_string();
+                        return Error::ImmutableAssignment(name, symbol.pos.clone()).throw(pos);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(states)
+    }
+
+    // The assignments of a `let` or `=` no path reads before the variable is
+    // assigned again or goes out of scope, found by what each node may read
+    // later on
+    fn unused_assignments(&self, states: &[Option<State>]) -> Vec<(usize, Position)> {
+        let mut live: Vec<HashSet<usize>> = vec![HashSet::new(); self.nodes.len()];
+        let live_after = |live: &[HashSet<usize>], node: &Node| {
+            let mut after = HashSet::new();
+            for (succ, _) in &node.succs {
+                after.extend(&live[*succ]);
+            }
+            after
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (index, node) in self.nodes.iter().enumerate().rev() {
+                let mut before = live_after(&live, node);
+                match &node.event {
+                    Event::Read(variable, _) => {
+                        before.insert(*variable);
+                    }
+                    Event::Declare(variable)
+                    | Event::Bind(variable, _)
+                    | Event::Assign(variable, _) => {
+                        before.remove(variable);
+                    }
+                    Event::Nop => {}
+                }
+                if before != live[index] {
+                    live[index] = before;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut unused = Vec::new();
+        for (node, state) in self.nodes.iter().zip(states) {
+            let (Event::Bind(variable, Some(pos)) | Event::Assign(variable, pos)) = &node.event
+            else {
+                continue;
+            };
+            if state.is_some() && !live_after(&live, node).contains(variable) {
+                unused.push((*variable, pos.clone()));
+            }
+        }
+        unused
+    }
+
+    // How a read is reached from the declaration without an assignment, the
+    // shortest such path found searching back from the read
+    fn path(&self, index: usize, read: &Position, states: &[Option<State>]) -> Vec<String> {
+        let mut preds: Vec<Vec<(usize, &Option<Step>)>> = vec![Vec::new(); self.nodes.len()];
+        for (node, Node { succs, .. }) in self.nodes.iter().enumerate() {
+            for (succ, step) in succs {
+                preds[*succ].push((node, step));
+            }
+        }
+        let start = self
+            .nodes
+            .iter()
+            .position(
+                |node| matches!(&node.event, Event::Read(i, pos) if *i == index && pos == read),
+            )
+            .unwrap();
+        let mut next: HashMap<usize, (usize, &Option<Step>)> = HashMap::new();
+        let mut work = VecDeque::from([start]);
+        let mut declared = None;
+        while let Some(node) = work.pop_front() {
+            if matches!(self.nodes[node].event, Event::Declare(i) if i == index) {
+                declared = Some(node);
+                break;
+            }
+            for &(pred, step) in &preds[node] {
+                let assigns = matches!(self.nodes[pred].event, Event::Assign(i, _) if i == index);
+                if assigns || states[pred].is_none() || pred == start || next.contains_key(&pred) {
+                    continue;
+                }
+                next.insert(pred, (node, step));
+                work.push_back(pred);
+            }
+        }
+
+        let mut steps = Vec::new();
+        let mut node = declared;
+        while let Some((succ, step)) = node.and_then(|node| next.get(&node)) {
+            if let Some(step) = step {
+                steps.push(step);
+            }
+            node = Some(*succ);
+        }
+        let mut runs = HashSet::new();
+        steps
+            .into_iter()
+            .map(|step| match step {
+                Step::True(pos) => format!("the condition at {pos:?} is true"),
+                Step::False(pos) => format!("the condition at {pos:?} is false"),
+                Step::Arm(pos) => format!("the arm at {pos:?} matches"),
+                Step::GuardFails(pos) => format!("the guard at {pos:?} is false"),
+                Step::Runs(pos) => {
+                    runs.insert(pos);
+                    format!("the loop at {pos:?} runs")
+                }
+                Step::Ends(pos) if runs.contains(pos) => format!("the loop at {pos:?} ends"),
+                Step::Ends(pos) => format!("the loop at {pos:?} runs zero times"),
+                Step::Repeats(pos) => format!("the loop at {pos:?} runs again"),
+                Step::Break(pos) => format!("`break` at {pos:?} leaves the loop"),
+                Step::Continue(pos) => format!("`continue` at {pos:?} skips the rest of the loop"),
+                Step::Skips(operator, pos) => {
+                    format!("`{operator}` at {pos:?} skips its right side")
+                }
+                Step::Null(pos) => format!("the receiver at {pos:?} is `?`"),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::Error, io::run};
+
+    // The steps of the path on which a read misses the assignment, `None`
+    // when the program checks
+    fn missed(source: &str) -> Option<Vec<String>> {
+        match run(source) {
+            Err(Error::UninitializedRead(_, path)) => Some(path),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn reads_follow_an_assignment_on_every_path() {
+        let path = missed("let c = true; let x; if c { x = 1; } x").unwrap();
+        assert_eq!(path, ["the condition at <test>:1:25 is false"]);
+        let path = missed("let x; loop { if true { break; } x = 1; break; } x").unwrap();
+        assert_eq!(path.len(), 2);
+        // Closures read where they are created
+        assert_eq!(missed("let x: i32; let f = || x; f()"), Some(Vec::new()));
+        assert!(missed("let c = true; let x; if c { x = 1; } else { x = 2; } x").is_none());
+        let source =
+            "fn f(c: bool) -> i32 { let x; if c { return 0; } else { x = 2; } x } f(false)";
+        assert_eq!(run(source).unwrap(), "2");
+        let twice = run("let x; x = 1; x = 2;");
+        assert!(matches!(twice, Err(Error::ImmutableAssignment(..))));
+    }
+}
@@ -0,0 +1,170 @@
+use super::{
+    resolve::{Resolution, SymbolKind},
+    typeck::TypeChecker,
+};
+use crate::{
+    ast::{
+        expr::{Expr, Side},
+        stmt::Stmt,
+        token::{Literal, Operator},
+    },
+    error::*,
+    position::Position,
+};
+use std::collections::HashSet;
+
+// Warns about the imports and private functions nothing uses, among the
+// symbols from `first` on that the last resolved program declares
+pub fn unused_items(
+    resolution: &Resolution,
+    first: usize,
+    typeck: &TypeChecker,
+    stmts: &[Stmt],
+) -> Vec<(Warning, Position)> {
+    let used: HashSet<usize> = resolution.uses.values().copied().collect();
+    let exported: HashSet<&Position> = stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Function { decl } if decl.public => Some(&decl.pos),
+            _ => None,
+        })
+        .collect();
+    let mut warnings = Vec::new();
+    for (index, symbol) in resolution.symbols.iter().enumerate().skip(first) {
+        let Some(pos) = &symbol.pos else {
+            continue;
+        };
+        if used.contains(&index) || symbol.name.as_str().starts_with('_') {
+            continue;
+        }
+        let name = symbol.name.to_string();
+        match symbol.kind {
+            SymbolKind::Import if !typeck.calls_trait(&symbol.name) => {
+                warnings.push((Warning::UnusedImport(name), pos.clone()));
+            }
+            SymbolKind::Function if !exported.contains(pos) => {
+                warnings.push((Warning::UnusedFunction(name), pos.clone()));
+            }
+            _ => {}
+        }
+    }
+    warnings
+}
+
+// The value of a condition made of `true`, `false`, `!`, `&&` and `||`, which
+// `&&` and `||` know from their left side when it decides the result. The
+// conditions the checker can compute as a whole are not left to this.
+pub fn constant(condition: &Expr) -> Option<bool> {
+    match condition {
+        Expr::Literal {
+            value: Literal::Boolean(value),
+            ..
+        } => Some(*value),
+        Expr::Group { expresion } => constant(expresion),
+        Expr::Unary {
+            operator: Operator::Exclamation,
+            expresion: Side::Left(operand) | Side::Right(operand),
+            ..
+        } => constant(operand).map(|value| !value),
+        Expr::Logical {
+            left,
+            operator,
+            right,
+            ..
+        } => match (constant(left), operator) {
+            (Some(false), Operator::And) => Some(false),
+            (Some(true), Operator::Or) => Some(true),
+            (Some(_), _) => constant(right),
+            (None, _) => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{lexer::Lexer, parser::Parser},
+        check::Checker,
+        error::{Error, Level, Levels, Lint, Warning},
+        io::warnings,
+    };
+
+    // The lints warning about the program, in the order of the source
+    fn lints(source: &str) -> Vec<&'static str> {
+        let warnings = warnings(source).into_iter();
+        warnings.map(|warning| warning.lint().name()).collect()
+    }
+
+    fn constant(source: &str) -> Vec<bool> {
+        let warnings = warnings(source).into_iter();
+        warnings
+            .filter_map(|warning| match warning {
+                Warning::ConstantCondition(value) => Some(value),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn conditions_known_before_the_program_runs_warn() {
+        assert_eq!(constant("if true {}"), [true]);
+        assert_eq!(constant("if !(false || false) {}"), [true]);
+        assert_eq!(constant("if 1 + 1 == 2 {}"), [true]);
+        assert_eq!(constant("const N: i32 = 3; if N > 5 {}"), [false]);
+        assert_eq!(
+            constant("fn f() -> bool { true } if true || f() {}"),
+            [true]
+        );
+        assert_eq!(constant("match 1 { x if 2 < 1 => {}, _ => {} }"), [false]);
+        assert!(constant("fn f() -> bool { true } if f() && true {}").is_empty());
+        assert!(constant("let x = 1; if x == 1 {}").is_empty());
+    }
+
+    #[test]
+    fn unused_and_unreachable_code_warns() {
+        assert_eq!(lints("let x = 1;"), ["unused_variables"]);
+        assert_eq!(lints("fn f() {}"), ["unused_functions"]);
+        let assignments = lints("let mut x = 1; x = 2; x = 3; print(x);");
+        assert_eq!(assignments, ["unused_assignments", "unused_assignments"]);
+        assert_eq!(
+            lints("fn f() -> i32 { return 1; print(2); } print(f());"),
+            ["unreachable_code"]
+        );
+        assert_eq!(lints("loop { break; print(1); }"), ["unreachable_code"]);
+        assert!(lints("let _x = 1;").is_empty());
+        assert!(lints("#[allow(unused_variables)] let x = 1;").is_empty());
+        assert!(lints("#[allow(warnings)] fn f() { let x = 1; }").is_empty());
+    }
+
+    #[test]
+    fn casts_that_can_change_the_value_warn() {
+        assert_eq!(lints("let x: i64 = 5; x as i32;"), ["lossy_casts"]);
+        assert_eq!(lints("let x = 2.5; x as i32;"), ["lossy_casts"]);
+        assert!(lints("let x: u8 = 5; x as u16;").is_empty());
+        assert!(lints("let x: i64 = 5; x.to_i32();").is_empty());
+    }
+
+    #[test]
+    fn denied_lints_fail_the_program() {
+        let check = |source: &str, level| {
+            let tokens = Lexer::new("test", source).tokenize().unwrap();
+            let mut parser = Parser::new(tokens);
+            let stmts = parser.parse().unwrap();
+            let mut levels = Levels::default();
+            levels.set(Lint::UnusedVariables, level);
+            let mut checker = Checker::new(levels);
+            let result = checker.check(&stmts, &parser.allows());
+            result.map(|()| checker.warnings().len())
+        };
+        let denied = check("let x = 1;", Level::Deny);
+        assert!(matches!(
+            denied,
+            Err(Error::Denied(Warning::UnusedVariable(_)))
+        ));
+        assert!(matches!(check("let x = 1;", Level::Allow), Ok(0)));
+        assert!(matches!(check("let x = 1;", Level::Warn), Ok(1)));
+        let unknown = check("#[allow(unused_things)] let x = 1;", Level::Warn);
+        assert!(matches!(unknown, Err(Error::UnknownLint(_))));
+    }
+}
@@ -0,0 +1,567 @@
+pub mod consts;
+pub mod flow;
+pub mod lints;
+pub mod mutability;
+pub mod patterns;
+pub mod resolve;
+pub mod typeck;
+pub mod types;
+
+use crate::{
+    ast::{
+        expr::{Expr, Side},
+        pattern::{path_name, Pattern},
+        stmt::{Allow, EnumDecl, FnDecl, Stmt, StructDecl},
+        token::Operator,
+        types::TypeExpr,
+    },
+    error::*,
+    module::Module,
+    position::Position,
+    prelude,
+    runtime::value::Value,
+};
+use ascii::AsciiString;
+use consts::Evaluator;
+use resolve::{Imported, Resolution, Resolver};
+use std::{collections::HashMap, mem, rc::Rc};
+use typeck::TypeChecker;
+
+// A declaration patterns can name
+#[derive(Clone)]
+pub enum TypeDecl {
+    Struct(Rc<StructDecl>),
+    Enum(Rc<EnumDecl>),
+}
+
+// What the checker knows about a module
+struct Scope {
+    types: HashMap<AsciiString, TypeDecl>,
+    resolver: Resolver,
+    typeck: TypeChecker,
+}
+
+// Every module starts out with the prelude
+impl Default for Scope {
+    fn default() -> Self {
+        let types = prelude::stmts()
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Enum { decl } => Some((decl.name.clone(), TypeDecl::Enum(Rc::clone(decl)))),
+                _ => None,
+            })
+            .collect();
+        Self {
+            types,
+            resolver: Resolver::default(),
+            typeck: TypeChecker::default(),
+        }
+    }
+}
+
+// Checks a program before it runs. Declarations are remembered across calls,
+// so the REPL can check each line against the ones before it.
+#[derive(Default)]
+pub struct Checker {
+    scope: Scope,
+    // The scope each checked module ended with
+    modules: HashMap<String, Scope>,
+    levels: Levels,
+    // The warnings found in the program being checked, before their levels
+    // apply, and the ones of the checked programs not reported yet
+    found: Vec<(Warning, Position)>,
+    warnings: Vec<(Warning, Position)>,
+}
+
+impl Checker {
+    pub fn new(levels: Levels) -> Self {
+        Self {
+            levels,
+            ..Self::default()
+        }
+    }
+
+    // Types are checked before constants and patterns, which compare the
+    // values of numbers the type checker infers, and mutability, which needs
+    // to know the methods that are called. The lints run on programs that check.
+    pub fn check(&mut self, stmts: &[Stmt], allows: &[Allow]) -> Result<()> {
+        let first = self.resolution().symbols.len();
+        let imports = self.imported_consts(stmts);
+        self.scope.resolver.import_consts(imports);
+        self.scope.resolver.resolve(stmts)?;
+        self.import(stmts)?;
+        self.scope.typeck.check(stmts)?;
+        self.found.clear();
+        self.stmts(stmts)?;
+        let mut warnings = mem::take(&mut self.found);
+        warnings.extend(flow::check(self.resolution(), stmts)?);
+        let resolution = self.resolution();
+        warnings.extend(lints::unused_items(
+            resolution,
+            first,
+            &self.scope.typeck,
+            stmts,
+        ));
+        self.lint(warnings, allows)
+    }
+
+    // Modules only see what they declare and import
+    pub fn check_module(&mut self, module: &Module) -> Result<()> {
+        let scope = mem::take(&mut self.scope);
+        let result = self.check(&module.stmts, &module.allows);
+        let declared = mem::replace(&mut self.scope, scope);
+        self.modules.insert(module.name.clone(), declared);
+        result
+    }
+
+    // The warnings of the programs checked since the last call, in the order
+    // of the source
+    pub fn warnings(&mut self) -> Vec<(Warning, Position)> {
+        mem::take(&mut self.warnings)
+    }
+
+    // Keeps the warnings of lints set to warn that no `#[allow(..)]` around
+    // them silences. The first one of a denied lint fails the program
+    // instead, without the others.
+    fn lint(&mut self, mut warnings: Vec<(Warning, Position)>, allows: &[Allow]) -> Result<()> {
+        for (name, pos) in allows.iter().flat_map(|allow| &allow.lints) {
+            if name != "warnings" && Lint::named(name.as_str()).is_none() {
+                return Error::UnknownLint(name.to_string()).throw(pos);
+            }
+        }
+        warnings.retain(|(warning, pos)| {
+            let lint = warning.lint().name();
+            !allows.iter().any(|allow| {
+                let named = allow
+                    .lints
+                    .iter()
+                    .any(|(name, _)| name == "warnings" || name == lint);
+                named && pos.within(&allow.start, &allow.end)
+            })
+        });
+        warnings.sort_by_key(|(_, pos)| (pos.file(), pos.line(), pos.column()));
+        let levels = &self.levels;
+        let level = |(warning, _): &(Warning, Position)| levels.level(warning.lint());
+        if let Some(denied) = warnings
+            .iter()
+            .position(|warning| level(warning) == Level::Deny)
+        {
+            let (warning, pos) = warnings.swap_remove(denied);
+            return Error::Denied(warning).throw(&pos);
+        }
+        warnings.retain(|warning| level(warning) == Level::Warn);
+        self.warnings.append(&mut warnings);
+        Ok(())
+    }
+
+    // Where the names of the program are declared
+    pub fn resolution(&self) -> &Resolution {
+        self.scope.resolver.resolution()
+    }
+
+    pub fn module_resolution(&self, name: &str) -> Option<&Resolution> {
+        Some(self.modules.get(name)?.resolver.resolution())
+    }
+
+    // The imported items that are constants or `const fn`s, by where the
+    // `use` names them
+    fn imported_consts(&self, stmts: &[Stmt]) -> HashMap<Position, Imported> {
+        let mut imports = HashMap::new();
+        for stmt in stmts {
+            let Stmt::Use { path, items, .. } = stmt else {
+                continue;
+            };
+            let module = path_name(path);
+            let Some(scope) = self.modules.get(&module) else {
+                continue;
+            };
+            let resolution = scope.resolver.resolution();
+            for (item, pos) in items {
+                let Some(index) = scope.resolver.global(item) else {
+                    continue;
+                };
+                let constant = resolution.consts.contains_key(&index);
+                if constant || resolution.const_fns.contains_key(&index) {
+                    let module = module.clone();
+                    let imported = Imported {
+                        module,
+                        index,
+                        constant,
+                    };
+                    imports.insert(pos.clone(), imported);
+                }
+            }
+        }
+        imports
+    }
+
+    // Computes the lengths of the array types in `ty`, and checks them
+    // against the array literals of `value`
+    fn lengths(&mut self, ty: &TypeExpr, value: Option<&Expr>) -> Result<()> {
+        match (ty, value) {
+            (
+                TypeExpr::Array {
+                    element, length, ..
+                },
+                value,
+            ) => {
+                let elements = match value {
+                    Some(Expr::Array { elements, .. }) => Some(elements),
+                    _ => None,
+                };
+                if let Some(length) = length {
+                    let length = Evaluator::new(self).length(length)?;
+                    if let (Some(elements), Some(value)) = (elements, value) {
+                        if elements.len() != length {
+                            return Error::ArrayLength(length, elements.len()).throw(value.pos());
+                        }
+                    }
+                }
+                match elements {
+                    Some(elements) if !elements.is_empty() => {
+                        for element_value in elements {
+                            self.lengths(element, Some(element_value))?;
+                        }
+                        Ok(())
+                    }
+                    _ => self.lengths(element, None),
+                }
+            }
+            (
+                TypeExpr::Tuple { elements, .. },
+                Some(Expr::Tuple {
+                    elements: values, ..
+                }),
+            ) if elements.len() == values.len() => {
+                for (ty, value) in elements.iter().zip(values) {
+                    self.lengths(ty, Some(value))?;
+                }
+                Ok(())
+            }
+            (TypeExpr::Optional { inner, .. }, value) => self.lengths(inner, value),
+            (
+                TypeExpr::Named { args: types, .. }
+                | TypeExpr::Tuple {
+                    elements: types, ..
+                },
+                _,
+            ) => self.all_lengths(types),
+            (TypeExpr::Function { params, ret, .. }, _) => {
+                self.all_lengths(params)?;
+                self.all_lengths(ret.as_deref())
+            }
+            (TypeExpr::Dyn { .. }, _) => Ok(()),
+        }
+    }
+
+    fn all_lengths<'a>(&mut self, types: impl IntoIterator<Item = &'a TypeExpr>) -> Result<()> {
+        for ty in types {
+            self.lengths(ty, None)?;
+        }
+        Ok(())
+    }
+
+    fn signature(&mut self, decl: &FnDecl) -> Result<()> {
+        self.all_lengths(decl.params.iter().filter_map(|param| param.ty.as_ref()))?;
+        self.all_lengths(&decl.ret)
+    }
+
+    // Gives the names of constants in a pattern their value to match
+    fn constants(&mut self, pattern: &Pattern) -> Result<()> {
+        match pattern {
+            Pattern::Binding { pos, constant, .. } => {
+                let resolution = self.resolution();
+                match resolution.uses.get(pos) {
+                    Some(&index) if resolution.is_constant(index) => {
+                        let value = Evaluator::new(self).named(index)?;
+                        let _ = constant.set(value);
+                        Ok(())
+                    }
+                    _ => Ok(()),
+                }
+            }
+            Pattern::Struct { fields, .. } => {
+                for (_, pattern) in fields {
+                    self.constants(pattern)?;
+                }
+                Ok(())
+            }
+            Pattern::Variant { elements, .. } | Pattern::Tuple { elements, .. } => {
+                for pattern in elements {
+                    self.constants(pattern)?;
+                }
+                Ok(())
+            }
+            Pattern::Literal { .. }
+            | Pattern::Range { .. }
+            | Pattern::Path { .. }
+            | Pattern::Wildcard { .. } => Ok(()),
+        }
+    }
+
+    // The loader runs the checker on imported modules first. The trait
+    // implementations of a module come along with anything imported from it.
+    fn import(&mut self, stmts: &[Stmt]) -> Result<()> {
+        for stmt in stmts {
+            let Stmt::Use { path, items, .. } = stmt else {
+                continue;
+            };
+            let module = &self.modules[&path_name(path)];
+            self.scope.typeck.import_impls(&module.typeck)?;
+            for (item, _) in items {
+                if let Some(ty) = module.types.get(item) {
+                    self.scope.types.insert(item.clone(), ty.clone());
+                }
+                self.scope.typeck.import(item, &module.typeck);
+            }
+        }
+        Ok(())
+    }
+
+    // Types can be used before the statement declaring them
+    fn stmts(&mut self, stmts: &[Stmt]) -> Result<()> {
+        for stmt in stmts {
+            match stmt {
+                Stmt::Struct { decl } => {
+                    let ty = TypeDecl::Struct(Rc::clone(decl));
+                    self.scope.types.insert(decl.name.clone(), ty);
+                }
+                Stmt::Enum { decl } => {
+                    let ty = TypeDecl::Enum(Rc::clone(decl));
+                    self.scope.types.insert(decl.name.clone(), ty);
+                }
+                _ => {}
+            }
+        }
+        for stmt in stmts {
+            self.stmt(stmt)?;
+        }
+        Ok(())
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> Result<()> {
+        match stmt {
+            Stmt::Expr { expresion } => self.expr(expresion),
+            Stmt::Function { decl } => {
+                self.signature(decl)?;
+                self.expr(&decl.body)
+            }
+            Stmt::Const { decl } => {
+                Evaluator::new(self).constant(decl)?;
+                self.lengths(&decl.ty, Some(&decl.value))?;
+                self.expr(&decl.value)
+            }
+            Stmt::Impl { methods, .. } => {
+                for method in methods {
+                    self.signature(method)?;
+                    self.expr(&method.body)?;
+                }
+                Ok(())
+            }
+            Stmt::Trait { decl } => {
+                for method in &decl.methods {
+                    self.expr(&method.decl.body)?;
+                }
+                Ok(())
+            }
+            Stmt::Let {
+                pattern, ty, value, ..
+            } => {
+                if let Some(ty) = ty {
+                    self.lengths(ty, value.as_deref())?;
+                }
+                self.exprs(value.as_deref())?;
+                self.constants(pattern)?;
+                patterns::check_irrefutable(&self.scope.types, pattern, "`let`")
+            }
+            Stmt::Struct { decl } => self.all_lengths(decl.fields.iter().map(|field| &field.ty)),
+            Stmt::Enum { .. } | Stmt::Use { .. } => Ok(()),
+        }
+    }
+
+    // Warns about a number cast that can change the value, unless the
+    // value it casts is a constant the cast keeps
+    fn lossy_cast(&mut self, operand: &Expr, pos: &Position) -> Result<()> {
+        let Some((from, to)) = self.scope.typeck.cast(pos) else {
+            return Ok(());
+        };
+        if from.lossless(to) {
+            return Ok(());
+        }
+        if consts::foldable(self.resolution(), operand) {
+            if let Value::Number(n) = Evaluator::new(self).fold(operand)? {
+                if n.convert(to.name()).is_some() {
+                    return Ok(());
+                }
+            }
+        }
+        let warning = Warning::LossyCast(from.name().to_string(), to.name().to_string());
+        self.found.push((warning, pos.clone()));
+        Ok(())
+    }
+
+    // Warns about a condition known before the program runs, computed when
+    // it is made of literals and constants
+    fn constant_condition(&mut self, condition: &Expr) -> Result<()> {
+        let value = if consts::foldable(self.resolution(), condition) {
+            match Evaluator::new(self).fold(condition)? {
+                Value::Boolean(value) => Some(value),
+                _ => None,
+            }
+        } else {
+            lints::constant(condition)
+        };
+        if let Some(value) = value {
+            let warning = Warning::ConstantCondition(value);
+            self.found.push((warning, condition.pos().clone()));
+        }
+        Ok(())
+    }
+
+    // The casts inside of an expression folded as a whole
+    fn folded_casts(&mut self, expr: &Expr) -> Result<()> {
+        match expr {
+            Expr::Cast { expresion, pos, .. } => {
+                self.lossy_cast(expresion, pos)?;
+                self.folded_casts(expresion)
+            }
+            Expr::Group { expresion }
+            | Expr::Unary {
+                expresion: Side::Left(expresion) | Side::Right(expresion),
+                ..
+            } => self.folded_casts(expresion),
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.folded_casts(left)?;
+                self.folded_casts(right)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn exprs<'a>(&mut self, exprs: impl IntoIterator<Item = &'a Expr>) -> Result<()> {
+        for expr in exprs {
+            self.expr(expr)?;
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<()> {
+        match expr {
+            Expr::Array { elements, .. } | Expr::Tuple { elements, .. } => self.exprs(elements),
+            Expr::Assign { target, value, pos } => {
+                self.exprs([&**target, &**value])?;
+                mutability::check_mutable(self.resolution(), target, pos)
+            }
+            // What only literals and constants make up fails before the program runs
+            Expr::Binary { .. } | Expr::Logical { .. } | Expr::Unary { .. } | Expr::Cast { .. }
+                if consts::foldable(self.resolution(), expr) =>
+            {
+                Evaluator::new(self).fold(expr)?;
+                self.folded_casts(expr)
+            }
+            Expr::Cast { expresion, pos, .. } => {
+                self.lossy_cast(expresion, pos)?;
+                self.expr(expresion)
+            }
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.exprs([&**left, &**right])
+            }
+            Expr::Block { stmts, tail, .. } => {
+                self.stmts(stmts)?;
+                self.exprs(tail.as_deref())
+            }
+            Expr::Break { value, .. } | Expr::Return { value, .. } => self.exprs(value.as_deref()),
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                self.expr(callee)?;
+                self.exprs(arguments)
+            }
+            Expr::Field { object, .. } | Expr::TupleIndex { object, .. } => self.expr(object),
+            Expr::Group { expresion } | Expr::TypeArgs { expresion, .. } => self.expr(expresion),
+            Expr::Index { object, index, .. } => self.exprs([&**object, &**index]),
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.constant_condition(condition)?;
+                self.exprs([&**condition, &**then_branch])?;
+                self.exprs(else_branch.as_deref())
+            }
+            Expr::Map { entries, .. } => {
+                for (key, value) in entries {
+                    self.exprs([key, value])?;
+                }
+                Ok(())
+            }
+            Expr::Match {
+                scrutinee,
+                arms,
+                pos,
+            } => {
+                self.expr(scrutinee)?;
+                for arm in arms {
+                    self.constants(&arm.pattern)?;
+                    if let Some(guard) = &arm.guard {
+                        self.constant_condition(guard)?;
+                    }
+                    self.exprs(arm.guard.as_ref())?;
+                    self.expr(&arm.body)?;
+                }
+                patterns::check_match(&self.scope.types, arms, pos)
+            }
+            Expr::Method {
+                receiver,
+                arguments,
+                pos,
+                ..
+            } => {
+                self.expr(receiver)?;
+                self.exprs(arguments)?;
+                if self.scope.typeck.mutates(pos) {
+                    mutability::check_mutable(self.resolution(), receiver, pos)?;
+                }
+                Ok(())
+            }
+            Expr::Loop { body, .. } => self.expr(body),
+            Expr::LoopIn {
+                pattern,
+                iterable,
+                body,
+                ..
+            } => {
+                self.exprs([&**iterable, &**body])?;
+                self.constants(pattern)?;
+                patterns::check_irrefutable(&self.scope.types, pattern, "`loop`")
+            }
+            Expr::Range { start, end, .. } => {
+                self.exprs(start.as_deref())?;
+                self.exprs(end.as_deref())
+            }
+            Expr::Struct { fields, .. } => self.exprs(fields.iter().map(|(_, value)| value)),
+            Expr::Unary {
+                operator,
+                expresion: Side::Left(operand) | Side::Right(operand),
+                pos,
+            } => {
+                self.expr(operand)?;
+                match operator {
+                    Operator::Increment | Operator::Decrement => {
+                        mutability::check_mutable(self.resolution(), operand, pos)
+                    }
+                    _ => Ok(()),
+                }
+            }
+            Expr::Closure { decl, .. } => {
+                self.signature(decl)?;
+                self.expr(&decl.body)
+            }
+            Expr::Continue { .. }
+            | Expr::Literal { .. }
+            | Expr::Path { .. }
+            | Expr::Variable { .. } => Ok(()),
+        }
+    }
+}
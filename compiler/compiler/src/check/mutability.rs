@@ -0,0 +1,74 @@
+use super::resolve::{Resolution, SymbolKind};
+use crate::{ast::expr::Expr, error::*, position::Position};
+use ascii::AsciiString;
+
+// Rejects changing a variable or parameter not declared `mut`, directly or
+// through its fields and elements. `target` is what an assignment writes to,
+// what `++` and `--` update or the receiver of a method changing it.
+pub fn check_mutable(resolution: &Resolution, target: &Expr, pos: &Position) -> Result<()> {
+    let Some((name, used)) = root(target) else {
+        return Ok(());
+    };
+    let Some(&index) = resolution.uses.get(used) else {
+        return Ok(());
+    };
+    // Initializing `let x;` is up to the definite assignment analysis
+    if resolution.deferred.contains(&index) && matches!(target, Expr::Variable { .. }) {
+        return Ok(());
+    }
+    let symbol = &resolution.symbols[index];
+    match symbol.kind {
+        SymbolKind::Variable { mutable: false } | SymbolKind::Parameter { mutable: false } => {
+            Error::ImmutableAssignment(name.to_string(), symbol.pos.clone()).throw(pos)
+        }
+        _ => Ok(()),
+    }
+}
+
+// The variable a chain of field accesses and indexing starts from
+fn root(expr: &Expr) -> Option<(&AsciiString, &Position)> {
+    match expr {
+        Expr::Variable { name, pos } => Some((name, pos)),
+        Expr::Field { object, .. }
+        | Expr::TupleIndex { object, .. }
+        | Expr::Index { object, .. } => root(object),
+        Expr::Group { expresion } => root(expresion),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::Error, io::run};
+
+    // The name of the binding that would have to be `mut` and the column it is declared at
+    fn immutable(source: &str) -> Option<(String, u16)> {
+        match run(source) {
+            Err(Error::ImmutableAssignment(name, declared)) => Some((name, declared?.column())),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn only_mut_bindings_change() {
+        let x = Some(("x".to_string(), 5));
+        assert_eq!(immutable("let x = 1; x = 2;"), x);
+        assert_eq!(immutable("let x = 1; x++;"), x);
+        assert_eq!(immutable("let x = [[1]]; x[0][0] = 2;"), x);
+        assert_eq!(immutable("let x = #{1: 2}; x.insert(3, 4);"), x);
+        let p = "struct P { x: i32 } let p = P { x: 1 };";
+        assert_eq!(
+            immutable(&format!("{p} p.x = 2;")),
+            Some(("p".to_string(), 25))
+        );
+        let a = Some(("a".to_string(), 6));
+        assert_eq!(immutable("fn f(a: [i32]) { a.push(1); }"), a);
+        // Mutability belongs to the binding, not to the value it holds
+        assert_eq!(
+            immutable("let mut a = [1]; let b = a; b.push(2);"),
+            Some(("b".to_string(), 22))
+        );
+        assert_eq!(run("let mut x = 1; x++; x--; x++; x").unwrap(), "2");
+        assert_eq!(run("let x; x = 1; x").unwrap(), "1");
+    }
+}
@@ -0,0 +1,861 @@
+use super::{types::NumberType, TypeDecl};
+use crate::{
+    ast::{
+        expr::Arm,
+        pattern::{self, Pattern},
+        stmt::{EnumDecl, Payload, StructDecl},
+        token::{Literal, Number},
+    },
+    error::*,
+    position::Position,
+    runtime::bigint::BigInt,
+};
+use ascii::AsciiString;
+use std::{collections::HashMap, rc::Rc, slice};
+
+// A pattern reduced to what decides which values it covers
+#[derive(Clone)]
+enum Pat {
+    Any,
+    Ctor(Ctor, Vec<Pat>),
+}
+
+// What a pattern requires of the outermost layer of a value. Fields and
+// payloads are the arguments of a constructor, in declaration order.
+#[derive(Clone)]
+enum Ctor {
+    Variant(Rc<EnumDecl>, usize),
+    Struct(Rc<StructDecl>),
+    Tuple(usize),
+    Bool(bool),
+    // Integers and chars, which are listed as the intervals the patterns
+    // matching them split their type into
+    Interval(Interval),
+    // Values of types too large to list, like floats and strings
+    Literal(Literal),
+    Range(Option<Literal>, Option<Literal>, bool),
+}
+
+// Integers and chars by their code, the ones from `start` to `end` both
+// included. The bounds of `bigint` can be `None`, past any value.
+#[derive(Clone, PartialEq)]
+struct Interval {
+    start: Option<BigInt>,
+    end: Option<BigInt>,
+    // The name of the number type, or `char`
+    ty: &'static str,
+}
+
+impl Interval {
+    // Every value of the type, the chars without the surrogates
+    fn domain(ty: &'static str) -> Vec<Self> {
+        let interval = |start, end| Self { start, end, ty };
+        if ty == "char" {
+            let code = |c: u32| Some(BigInt::from_u128(c.into()));
+            return vec![
+                interval(code(0), code(0xD7FF)),
+                interval(code(0xE000), code(0x10FFFF)),
+            ];
+        }
+        match NumberType::from_name(ty).and_then(|ty| ty.range()) {
+            Some((min, max)) => vec![interval(Some(min), Some(max))],
+            None => vec![interval(None, None)],
+        }
+    }
+
+    // The values a literal or a range matches, if they are integers or
+    // chars. Missing bounds are the ones of the type.
+    fn of(start: Option<&Literal>, end: Option<&Literal>, inclusive: bool) -> Option<Self> {
+        let code = |literal: &Literal| match literal {
+            Literal::Char(c) => Some((BigInt::from_u128(u32::from(*c).into()), "char")),
+            Literal::Number(n) if !NumberType::of(n).is_fractional() => match n.cast("bigint") {
+                Ok(Number::BigInt(code)) => Some(((*code).clone(), n.type_name())),
+                _ => None,
+            },
+            _ => None,
+        };
+        let (start, end) = (start.map(code), end.map(code));
+        let ty = match (&start, &end) {
+            (Some(Some((_, ty))), _) | (_, Some(Some((_, ty)))) => *ty,
+            _ => return None,
+        };
+        let domain = Self::domain(ty);
+        let start = match start {
+            Some(start) => Some(start?.0),
+            None => domain[0].start.clone(),
+        };
+        let end = match end {
+            Some(end) if inclusive => Some(end?.0),
+            Some(end) => Some(&end?.0 - &BigInt::from_u128(1)),
+            None => domain[domain.len() - 1].end.clone(),
+        };
+        Some(Self { start, end, ty })
+    }
+
+    // Whether every value of `other` is one of `self`
+    fn contains(&self, other: &Self) -> bool {
+        let starts_before = match (&self.start, &other.start) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(a), Some(b)) => a <= b,
+        };
+        let ends_after = match (&self.end, &other.end) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(a), Some(b)) => a >= b,
+        };
+        starts_before && ends_after
+    }
+
+    // The interval cut where any of `others` starts or ends, so each piece
+    // is either within or outside each of them. Empty intervals have none.
+    fn split(&self, others: &[&Self]) -> Vec<Self> {
+        let one = BigInt::from_u128(1);
+        if let (Some(start), Some(end)) = (&self.start, &self.end) {
+            if start > end {
+                return Vec::new();
+            }
+        }
+        let mut cuts: Vec<BigInt> = others
+            .iter()
+            .flat_map(|other| {
+                let after = other.end.as_ref().map(|end| end + &one);
+                other.start.clone().into_iter().chain(after)
+            })
+            .filter(|cut| {
+                self.start.as_ref().is_none_or(|start| cut > start)
+                    && self.end.as_ref().is_none_or(|end| cut <= end)
+            })
+            .collect();
+        cuts.sort();
+        cuts.dedup();
+        let mut pieces = Vec::with_capacity(cuts.len() + 1);
+        let mut start = self.start.clone();
+        for cut in cuts {
+            let end = Some(&cut - &one);
+            pieces.push(Self {
+                start,
+                end,
+                ty: self.ty,
+            });
+            start = Some(cut);
+        }
+        pieces.push(Self {
+            start,
+            end: self.end.clone(),
+            ty: self.ty,
+        });
+        pieces
+    }
+
+    // A value or a range pattern matching the interval, leaving out the
+    // bounds that are the least or the greatest value of the type
+    fn show(&self) -> String {
+        let code = |code: &BigInt| match self.ty {
+            "char" => format!("{:?}", char::from_u32(code.wrapping_u128() as u32).unwrap()),
+            _ => code.to_string(),
+        };
+        let domain = Self::domain(self.ty);
+        let start = self
+            .start
+            .as_ref()
+            .filter(|&start| Some(start) != domain[0].start.as_ref());
+        let end = self
+            .end
+            .as_ref()
+            .filter(|&end| Some(end) != domain[domain.len() - 1].end.as_ref());
+        match (start, end) {
+            (Some(start), Some(end)) if start == end => code(start),
+            (Some(start), Some(end)) => format!("{}..={}", code(start), code(end)),
+            (Some(start), None) => format!("{}..", code(start)),
+            (None, Some(end)) => format!("..={}", code(end)),
+            (None, None) => String::from("_"),
+        }
+    }
+}
+
+impl PartialEq for Ctor {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Ctor::Variant(a, i), Ctor::Variant(b, j)) => Rc::ptr_eq(a, b) && i == j,
+            (Ctor::Struct(a), Ctor::Struct(b)) => Rc::ptr_eq(a, b),
+            (Ctor::Tuple(a), Ctor::Tuple(b)) => a == b,
+            (Ctor::Bool(a), Ctor::Bool(b)) => a == b,
+            (Ctor::Interval(a), Ctor::Interval(b)) => a == b,
+            (Ctor::Literal(a), Ctor::Literal(b)) => a == b,
+            (Ctor::Range(a, b, x), Ctor::Range(c, d, y)) => a == c && b == d && x == y,
+            _ => false,
+        }
+    }
+}
+
+impl Ctor {
+    fn arity(&self) -> usize {
+        match self {
+            Ctor::Variant(decl, index) => match &decl.variants[*index].payload {
+                Payload::Unit => 0,
+                Payload::Tuple(types) => types.len(),
+                Payload::Struct(fields) => fields.len(),
+            },
+            Ctor::Struct(decl) => decl.fields.len(),
+            Ctor::Tuple(arity) => *arity,
+            Ctor::Bool(_) | Ctor::Interval(_) | Ctor::Literal(_) | Ctor::Range(..) => 0,
+        }
+    }
+
+    // The type of the values the constructor builds
+    fn type_name(&self) -> String {
+        match self {
+            Ctor::Variant(decl, _) => decl.name.to_string(),
+            Ctor::Struct(decl) => decl.name.to_string(),
+            Ctor::Tuple(arity) => format!("({})", vec!["_"; *arity].join(", ")),
+            Ctor::Bool(_) => String::from("bool"),
+            Ctor::Interval(interval) => interval.ty.to_string(),
+            Ctor::Literal(value)
+            | Ctor::Range(Some(value), ..)
+            | Ctor::Range(_, Some(value), _) => String::from(match value {
+                Literal::Null => "?",
+                Literal::Char(_) => "char",
+                Literal::Boolean(_) => "bool",
+                Literal::Number(n) => n.type_name(),
+                Literal::Unsuffixed(n) => n.value().type_name(),
+                Literal::String(_) => "String",
+            }),
+            Ctor::Range(..) => String::from("_"),
+        }
+    }
+
+    // Every constructor of the type, `None` when there are too many to list
+    fn all(&self) -> Option<Vec<Ctor>> {
+        match self {
+            Ctor::Variant(decl, _) => Some(
+                (0..decl.variants.len())
+                    .map(|index| Ctor::Variant(Rc::clone(decl), index))
+                    .collect(),
+            ),
+            Ctor::Struct(_) | Ctor::Tuple(_) => Some(vec![self.clone()]),
+            Ctor::Bool(_) => Some(vec![Ctor::Bool(false), Ctor::Bool(true)]),
+            Ctor::Interval(interval) => Some(
+                Interval::domain(interval.ty)
+                    .into_iter()
+                    .map(Ctor::Interval)
+                    .collect(),
+            ),
+            Ctor::Literal(_) | Ctor::Range(..) => None,
+        }
+    }
+
+    // Whether every value the constructor `other` builds is one `self` builds
+    fn covers(&self, other: &Ctor) -> bool {
+        match (self, other) {
+            (Ctor::Interval(a), Ctor::Interval(b)) => a.contains(b),
+            _ => self == other,
+        }
+    }
+
+    // The constructor cut where the intervals among `heads` start and end,
+    // so each of them covers a piece entirely or not at all
+    fn split(&self, heads: &[Ctor]) -> Vec<Ctor> {
+        let Ctor::Interval(interval) = self else {
+            return vec![self.clone()];
+        };
+        let others: Vec<&Interval> = heads
+            .iter()
+            .filter_map(|head| match head {
+                Ctor::Interval(other) => Some(other),
+                _ => None,
+            })
+            .collect();
+        interval
+            .split(&others)
+            .into_iter()
+            .map(Ctor::Interval)
+            .collect()
+    }
+}
+
+// A literal pattern, an interval when it is an integer or a char
+fn literal(value: Literal) -> Ctor {
+    match Interval::of(Some(&value), Some(&value), true) {
+        Some(interval) => Ctor::Interval(interval),
+        None => Ctor::Literal(value),
+    }
+}
+
+// Rejects arms that can never run and matches missing some values
+pub fn check_match(
+    types: &HashMap<AsciiString, TypeDecl>,
+    arms: &[Arm],
+    pos: &Position,
+) -> Result<()> {
+    let mut lowered: Vec<Pat> = Vec::with_capacity(arms.len());
+    for arm in arms {
+        let pat = lower(types, &arm.pattern)?;
+        for earlier in &lowered {
+            if let Some((expected, found)) = conflict(earlier, &pat) {
+                return Error::MismatchedPatterns(expected, found).throw(arm.pattern.pos());
+            }
+        }
+        lowered.push(pat);
+    }
+
+    // Guarded arms may not run, so they cover nothing
+    let mut rows: Vec<Vec<Pat>> = Vec::new();
+    for (arm, pat) in arms.iter().zip(lowered) {
+        if !useful(&rows, slice::from_ref(&pat)) {
+            return Error::UnreachablePattern.throw(arm.pattern.pos());
+        }
+        if arm.guard.is_none() {
+            rows.push(vec![pat]);
+        }
+    }
+
+    let missing = missing(&rows, 1);
+    if !missing.is_empty() {
+        let patterns = missing.iter().map(|witness| show(&witness[0])).collect();
+        return Error::NonExhaustive(patterns).throw(pos);
+    }
+    Ok(())
+}
+
+// Rejects patterns in `let` and `loop` heads that do not match every value
+pub fn check_irrefutable(
+    types: &HashMap<AsciiString, TypeDecl>,
+    pattern: &Pattern,
+    place: &str,
+) -> Result<()> {
+    let rows = vec![vec![lower(types, pattern)?]];
+    let missing = missing(&rows, 1);
+    if !missing.is_empty() {
+        let patterns = missing.iter().map(|witness| show(&witness[0])).collect();
+        return Error::RefutablePattern(patterns, place.to_string()).throw(pattern.pos());
+    }
+    Ok(())
+}
+
+// Unsuffixed numbers compare by the value the type checker gave them, and
+// decimals by their value whatever digits they are written with
+fn typed(literal: &Literal) -> Literal {
+    match literal {
+        Literal::Unsuffixed(number) => match number.value() {
+            Number::Decimal(decimal) => {
+                Literal::Number(Number::Decimal(Rc::new(decimal.normalized())))
+            }
+            number => Literal::Number(number),
+        },
+        Literal::Number(Number::Decimal(decimal)) => {
+            Literal::Number(Number::Decimal(Rc::new(decimal.normalized())))
+        }
+        literal => literal.clone(),
+    }
+}
+
+fn lower(types: &HashMap<AsciiString, TypeDecl>, pattern: &Pattern) -> Result<Pat> {
+    let all = |patterns: &[Pattern]| -> Result<Vec<Pat>> {
+        patterns
+            .iter()
+            .map(|pattern| lower(types, pattern))
+            .collect()
+    };
+    Ok(match pattern {
+        Pattern::Wildcard { .. } => Pat::Any,
+        // Names of constants match their value
+        Pattern::Binding { constant, .. } => match constant.get() {
+            Some(Literal::Boolean(b)) => Pat::Ctor(Ctor::Bool(*b), Vec::new()),
+            Some(value) => Pat::Ctor(literal(typed(value)), Vec::new()),
+            None => Pat::Any,
+        },
+        Pattern::Literal {
+            value: Literal::Boolean(b),
+            ..
+        } => Pat::Ctor(Ctor::Bool(*b), Vec::new()),
+        Pattern::Literal { value, .. } => Pat::Ctor(literal(typed(value)), Vec::new()),
+        Pattern::Range {
+            start,
+            end,
+            inclusive,
+            ..
+        } => {
+            let (start, end) = (start.as_ref().map(typed), end.as_ref().map(typed));
+            let ctor = match Interval::of(start.as_ref(), end.as_ref(), *inclusive) {
+                Some(interval) => Ctor::Interval(interval),
+                None => Ctor::Range(start, end, *inclusive),
+            };
+            Pat::Ctor(ctor, Vec::new())
+        }
+        Pattern::Tuple { elements, .. } => Pat::Ctor(Ctor::Tuple(elements.len()), all(elements)?),
+        Pattern::Struct { path, fields, pos } => {
+            let (ctor, names): (Ctor, Vec<AsciiString>) = match resolve(types, path, pos)? {
+                Resolved::Struct(decl) => {
+                    let names = decl.fields.iter().map(|field| field.name.clone()).collect();
+                    (Ctor::Struct(decl), names)
+                }
+                Resolved::Variant(decl, index) => match &decl.variants[index].payload {
+                    Payload::Struct(fields) => {
+                        let names = fields.iter().map(|field| field.name.clone()).collect();
+                        (Ctor::Variant(Rc::clone(&decl), index), names)
+                    }
+                    _ => return Error::VariantForm(decl.path(index), decl.usage(index)).throw(pos),
+                },
+            };
+            let mut args: Vec<Option<Pat>> = vec![None; names.len()];
+            for (name, pattern) in fields {
+                let Some(i) = names.iter().position(|field| field == name) else {
+                    return Error::UnknownField(name.to_string(), pattern::path_name(path))
+                        .throw(pattern.pos());
+                };
+                if args[i].is_some() {
+                    return Error::DuplicateField(name.to_string()).throw(pattern.pos());
+                }
+                args[i] = Some(lower(types, pattern)?);
+            }
+            Pat::Ctor(
+                ctor,
+                args.into_iter()
+                    .map(|arg| arg.unwrap_or(Pat::Any))
+                    .collect(),
+            )
+        }
+        Pattern::Variant {
+            path,
+            elements,
+            pos,
+        } => match resolve(types, path, pos)? {
+            Resolved::Variant(decl, index) => match &decl.variants[index].payload {
+                Payload::Tuple(types) if types.len() != elements.len() => {
+                    return Error::ArityMismatch(
+                        decl.path(index),
+                        types.len(),
+                        elements.len(),
+                        Some(decl.variants[index].pos.clone()),
+                    )
+                    .throw(pos)
+                }
+                Payload::Tuple(_) => {
+                    Pat::Ctor(Ctor::Variant(Rc::clone(&decl), index), all(elements)?)
+                }
+                _ => return Error::VariantForm(decl.path(index), decl.usage(index)).throw(pos),
+            },
+            Resolved::Struct(decl) => {
+                return Error::UnknownType(decl.name.to_string()).throw(pos);
+            }
+        },
+        Pattern::Path { path, pos } => match resolve(types, path, pos)? {
+            Resolved::Variant(decl, index) => match &decl.variants[index].payload {
+                Payload::Unit => Pat::Ctor(Ctor::Variant(decl, index), Vec::new()),
+                _ => return Error::VariantForm(decl.path(index), decl.usage(index)).throw(pos),
+            },
+            Resolved::Struct(decl) => {
+                return Error::UnknownType(decl.name.to_string()).throw(pos);
+            }
+        },
+    })
+}
+
+// What the path of a struct or variant pattern names
+enum Resolved {
+    Struct(Rc<StructDecl>),
+    Variant(Rc<EnumDecl>, usize),
+}
+
+fn resolve(
+    types: &HashMap<AsciiString, TypeDecl>,
+    path: &[AsciiString],
+    pos: &Position,
+) -> Result<Resolved> {
+    match (types.get(&path[0]), path) {
+        (Some(TypeDecl::Struct(decl)), [_]) => Ok(Resolved::Struct(Rc::clone(decl))),
+        (Some(TypeDecl::Enum(decl)), [_, name]) => match decl.variant(name) {
+            Some((index, _)) => Ok(Resolved::Variant(Rc::clone(decl), index)),
+            None => Error::UnknownVariant(name.to_string(), decl.name.to_string()).throw(pos),
+        },
+        _ => Error::UnknownType(pattern::path_name(path)).throw(pos),
+    }
+}
+
+// The types of two patterns that can not match values of the same type
+fn conflict(a: &Pat, b: &Pat) -> Option<(String, String)> {
+    let (Pat::Ctor(a, a_args), Pat::Ctor(b, b_args)) = (a, b) else {
+        return None;
+    };
+    let (a_type, b_type) = (a.type_name(), b.type_name());
+    if a_type != b_type && a_type != "_" && b_type != "_" {
+        return Some((a_type, b_type));
+    }
+    if a != b {
+        return None;
+    }
+    a_args.iter().zip(b_args).find_map(|(a, b)| conflict(a, b))
+}
+
+// The rows whose first pattern covers `ctor`, with that pattern replaced by its arguments
+fn specialize(rows: &[Vec<Pat>], ctor: &Ctor) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter_map(|row| {
+            let (head, tail) = row.split_first()?;
+            let mut specialized = match head {
+                Pat::Any => vec![Pat::Any; ctor.arity()],
+                Pat::Ctor(head, args) if head.covers(ctor) => args.clone(),
+                Pat::Ctor(..) => return None,
+            };
+            specialized.extend_from_slice(tail);
+            Some(specialized)
+        })
+        .collect()
+}
+
+// The rows starting with a pattern matching anything, without that pattern
+fn default(rows: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter_map(|row| match row.split_first()? {
+            (Pat::Any, tail) => Some(tail.to_vec()),
+            _ => None,
+        })
+        .collect()
+}
+
+// The constructors the first column starts with
+fn heads(rows: &[Vec<Pat>]) -> Vec<Ctor> {
+    let mut heads: Vec<Ctor> = Vec::new();
+    for row in rows {
+        if let Some(Pat::Ctor(ctor, _)) = row.first() {
+            if !heads.contains(ctor) {
+                heads.push(ctor.clone());
+            }
+        }
+    }
+    heads
+}
+
+// Every constructor of the type of the heads, with its intervals split by them
+fn constructors(heads: &[Ctor]) -> Option<Vec<Ctor>> {
+    let all = heads.first().and_then(Ctor::all)?;
+    Some(all.iter().flat_map(|ctor| ctor.split(heads)).collect())
+}
+
+// Every constructor of the type when the first column covers all of them
+fn complete(rows: &[Vec<Pat>]) -> Option<Vec<Ctor>> {
+    let heads = heads(rows);
+    constructors(&heads).filter(|all| {
+        all.iter()
+            .all(|ctor| heads.iter().any(|head| head.covers(ctor)))
+    })
+}
+
+// Whether some value matches `vector` but none of the rows
+fn useful(rows: &[Vec<Pat>], vector: &[Pat]) -> bool {
+    let Some((head, tail)) = vector.split_first() else {
+        return rows.is_empty();
+    };
+    let with = |ctor: &Ctor, args: Vec<Pat>| {
+        let mut vector = args;
+        vector.extend_from_slice(tail);
+        useful(&specialize(rows, ctor), &vector)
+    };
+    match head {
+        // An interval is useful where any piece of it is
+        Pat::Ctor(ctor, args) => ctor
+            .split(&heads(rows))
+            .iter()
+            .any(|piece| with(piece, args.clone())),
+        Pat::Any => match complete(rows) {
+            Some(all) => all
+                .iter()
+                .any(|ctor| with(ctor, vec![Pat::Any; ctor.arity()])),
+            None => useful(&default(rows), tail),
+        },
+    }
+}
+
+// Vectors of `width` patterns matching values that none of the rows match
+fn missing(rows: &[Vec<Pat>], width: usize) -> Vec<Vec<Pat>> {
+    if width == 0 {
+        return if rows.is_empty() {
+            vec![Vec::new()]
+        } else {
+            Vec::new()
+        };
+    }
+    let heads = heads(rows);
+    let mut witnesses = Vec::new();
+    // Values starting with a constructor no row starts with are only matched
+    // by the rows matching anything there
+    let absent = |head: Pat, witnesses: &mut Vec<Vec<Pat>>| {
+        for tail in missing(&default(rows), width - 1) {
+            let mut witness = vec![head.clone()];
+            witness.extend(tail);
+            witnesses.push(witness);
+        }
+    };
+    let Some(all) = constructors(&heads) else {
+        absent(Pat::Any, &mut witnesses);
+        return witnesses;
+    };
+    for ctor in all {
+        let arity = ctor.arity();
+        if !heads.iter().any(|head| head.covers(&ctor)) {
+            absent(Pat::Ctor(ctor, vec![Pat::Any; arity]), &mut witnesses);
+            continue;
+        }
+        for witness in missing(&specialize(rows, &ctor), arity + width - 1) {
+            let (args, tail) = witness.split_at(arity);
+            let mut witness = vec![Pat::Ctor(ctor.clone(), args.to_vec())];
+            witness.extend_from_slice(tail);
+            witnesses.push(witness);
+        }
+    }
+    witnesses
+}
+
+// How a missing pattern is shown in diagnostics
+fn show(pat: &Pat) -> String {
+    let list = |args: &[Pat]| args.iter().map(show).collect::<Vec<_>>().join(", ");
+    let fields = |name: String, names: Vec<&AsciiString>, args: &[Pat]| {
+        let mut fields = names
+            .iter()
+            .zip(args)
+            .filter(|(_, arg)| !matches!(arg, Pat::Any))
+            .map(|(name, arg)| format!("{name}: {}", show(arg)))
+            .collect::<Vec<_>>();
+        if fields.len() < args.len() {
+            fields.push(String::from(".."));
+        }
+        format!("{name} {{ {} }}", fields.join(", "))
+    };
+    let Pat::Ctor(ctor, args) = pat else {
+        return String::from("_");
+    };
+    match ctor {
+        Ctor::Variant(decl, index) => match &decl.variants[*index].payload {
+            Payload::Unit => decl.path(*index),
+            Payload::Tuple(_) => format!("{}({})", decl.path(*index), list(args)),
+            Payload::Struct(declared) => fields(
+                decl.path(*index),
+                declared.iter().map(|field| &field.name).collect(),
+                args,
+            ),
+        },
+        Ctor::Struct(decl) => fields(
+            decl.name.to_string(),
+            decl.fields.iter().map(|field| &field.name).collect(),
+            args,
+        ),
+        Ctor::Tuple(1) => format!("({},)", show(&args[0])),
+        Ctor::Tuple(_) => format!("({})", list(args)),
+        Ctor::Bool(b) => b.to_string(),
+        Ctor::Interval(interval) => interval.show(),
+        // Their types can not be listed, so `_` stands in for what they miss
+        Ctor::Literal(_) | Ctor::Range(..) => String::from("_"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::run;
+
+    fn value(n: Number) -> Pat {
+        Pat::Ctor(literal(Literal::Number(n)), Vec::new())
+    }
+
+    fn range(start: Option<Number>, end: Option<Number>, inclusive: bool) -> Pat {
+        let (start, end) = (start.map(Literal::Number), end.map(Literal::Number));
+        let interval = Interval::of(start.as_ref(), end.as_ref(), inclusive).unwrap();
+        Pat::Ctor(Ctor::Interval(interval), Vec::new())
+    }
+
+    fn chars(start: char, end: char) -> Pat {
+        let (start, end) = (Literal::Char(start), Literal::Char(end));
+        let interval = Interval::of(Some(&start), Some(&end), true).unwrap();
+        Pat::Ctor(Ctor::Interval(interval), Vec::new())
+    }
+
+    fn tuple(elements: Vec<Pat>) -> Pat {
+        Pat::Ctor(Ctor::Tuple(elements.len()), elements)
+    }
+
+    // The patterns missing from a match with an arm for each pattern
+    fn witnesses(arms: Vec<Pat>) -> Vec<String> {
+        let rows: Vec<Vec<Pat>> = arms.into_iter().map(|arm| vec![arm]).collect();
+        missing(&rows, 1)
+            .iter()
+            .map(|witness| show(&witness[0]))
+            .collect()
+    }
+
+    // Whether each arm matches a value none of the arms before it matches
+    fn reachable(arms: Vec<Pat>) -> Vec<bool> {
+        let mut rows = Vec::new();
+        arms.into_iter()
+            .map(|arm| {
+                let useful = useful(&rows, slice::from_ref(&arm));
+                rows.push(vec![arm]);
+                useful
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ranges_covering_the_type_are_exhaustive() {
+        let u8 = |n| Some(Number::U8(n));
+        assert!(witnesses(vec![
+            range(u8(0), u8(100), true),
+            range(u8(101), u8(255), true)
+        ])
+        .is_empty());
+        assert!(witnesses(vec![range(None, u8(10), false), range(u8(10), None, true)]).is_empty());
+        let i8 = |n| Some(Number::I8(n));
+        let arms = vec![
+            range(None, i8(0), false),
+            value(Number::I8(0)),
+            range(i8(1), None, true),
+        ];
+        assert!(witnesses(arms).is_empty());
+    }
+
+    #[test]
+    fn witnesses_list_the_uncovered_intervals() {
+        let u8 = |n| Some(Number::U8(n));
+        let arms = vec![range(u8(0), u8(100), true), value(Number::U8(150))];
+        assert_eq!(witnesses(arms), ["101..=149", "151.."]);
+        let i32 = |n| Some(Number::I32(n));
+        let arms = vec![value(Number::I32(0)), range(i32(5), i32(10), false)];
+        assert_eq!(witnesses(arms), ["..=-1", "1..=4", "10.."]);
+        assert_eq!(witnesses(vec![value(Number::U8(0))]), ["1.."]);
+    }
+
+    #[test]
+    fn bigints_have_no_least_or_greatest_value() {
+        let big = |n: &str| Number::parse(n, "bigint").ok();
+        let arms = vec![range(big("0"), big("9"), true)];
+        assert_eq!(witnesses(arms), ["..=-1", "10.."]);
+        let arms = vec![range(None, big("0"), false), range(big("0"), None, true)];
+        assert!(witnesses(arms).is_empty());
+    }
+
+    #[test]
+    fn chars_skip_the_surrogates() {
+        assert_eq!(
+            witnesses(vec![chars('a', 'z')]),
+            ["..='`'", "'{'..='\\u{d7ff}'", "'\\u{e000}'.."]
+        );
+        let arms = vec![chars('\0', '\u{d7ff}'), chars('\u{e000}', '\u{10ffff}')];
+        assert!(witnesses(arms).is_empty());
+    }
+
+    #[test]
+    fn witnesses_nest_in_tuples() {
+        let u8 = |n| Some(Number::U8(n));
+        let bool = |b| Pat::Ctor(Ctor::Bool(b), Vec::new());
+        let arms = vec![
+            tuple(vec![bool(true), range(u8(0), u8(127), true)]),
+            tuple(vec![bool(false), range(u8(200), None, true)]),
+        ];
+        assert_eq!(witnesses(arms), ["(false, ..=199)", "(true, 128..)"]);
+    }
+
+    #[test]
+    fn ranges_inside_earlier_ones_are_unreachable() {
+        let u8 = |n| Some(Number::U8(n));
+        let arms = vec![
+            range(u8(0), u8(100), true),
+            range(u8(5), u8(7), true),
+            value(Number::U8(100)),
+            range(u8(50), u8(150), true),
+            Pat::Any,
+        ];
+        assert_eq!(reachable(arms), [true, false, false, true, true]);
+        let arms = vec![range(u8(0), None, true), Pat::Any];
+        assert_eq!(reachable(arms), [true, false]);
+        // An empty range matches nothing
+        assert_eq!(reachable(vec![range(u8(5), u8(5), false)]), [false]);
+    }
+
+    #[test]
+    fn other_values_are_not_listed() {
+        let float = Pat::Ctor(Ctor::Literal(Literal::Number(Number::F64(1.5))), Vec::new());
+        assert_eq!(witnesses(vec![float]), ["_"]);
+        assert_eq!(witnesses(Vec::new()), ["_"]);
+    }
+
+    #[test]
+    fn arms_bind_what_their_patterns_match() {
+        let shown = |source: &str| run(source).unwrap();
+        let shape = "enum Shape { Circle(f64), Rect { w: f64, h: f64 }, Empty }
+            fn area(s: Shape) -> f64 {
+                match s { Shape::Circle(r) => r * r * 3.0, Shape::Rect { w, h } => w * h, _ => 0.0 }
+            }";
+        let source =
+            format!("{shape} [area(Shape::Circle(1.0)), area(Shape::Rect {{ w: 2.0, h: 3.0 }})]");
+        assert_eq!(shown(&source), "[3.0, 6.0]");
+        let kind = "fn kind(n: i32) -> String {
+                match n {
+                    0 => \"zero\", x if x < 0 => \"negative\", 1..=9 => \"digit\", _ => \"many\"
+                }
+            }";
+        let source = format!("{kind} [kind(0), kind(-3), kind(5), kind(50)]");
+        assert_eq!(
+            shown(&source),
+            "[\"zero\", \"negative\", \"digit\", \"many\"]"
+        );
+        let point = "struct P { x: i32, y: i32 }
+            fn at(p: P) -> i32 { match (p) { P { x: 0, y } => y, P { x, .. } => x } }";
+        assert_eq!(
+            shown(&format!(
+                "{point} (at(P {{ x: 0, y: 7 }}), at(P {{ x: 2, y: 7 }}))"
+            )),
+            "(7, 2)"
+        );
+    }
+
+    #[test]
+    fn matches_cover_every_value_once() {
+        let missing = |source: &str| match run(source) {
+            Err(Error::NonExhaustive(patterns)) => patterns,
+            _ => Vec::new(),
+        };
+        let s = "enum S { A(i32), B, C }";
+        assert_eq!(
+            missing(&format!("{s} match S::B {{ S::A(_) => 1, S::B => 2 }}")),
+            ["S::C"]
+        );
+        assert_eq!(missing("match true { true => 1 }"), ["false"]);
+        // Guarded arms may not run
+        assert_eq!(missing("match 1 { x if x > 0 => 1 }"), ["_"]);
+        let unreachable = run("match 3 { _ => 1, 2 => 2 }");
+        assert!(matches!(unreachable, Err(Error::UnreachablePattern)));
+        let mismatched = run("match 1 { 1 => 1, \"a\" => 2, _ => 3 }");
+        assert!(matches!(mismatched, Err(Error::MismatchedPatterns(..))));
+    }
+
+    #[test]
+    fn lets_and_loops_destructure_every_value() {
+        let shown = |source: &str| run(source).unwrap();
+        assert_eq!(
+            shown("let (a, (b, _)) = (1, (\"x\", 2)); (b, a)"),
+            "(\"x\", 1)"
+        );
+        let point = "struct P { x: i32, y: i32 }";
+        assert_eq!(
+            shown(&format!(
+                "{point} let P {{ x, y }} = P {{ x: 1, y: 2 }}; x + y"
+            )),
+            "3"
+        );
+        let source = "let mut sum = 0; loop (k, v) in #{1: 2, 3: 4} { sum = sum + k * v; } sum";
+        assert_eq!(shown(source), "14");
+        let refutable = |source: &str| match run(source) {
+            Err(Error::RefutablePattern(patterns, place)) => (patterns, place),
+            _ => (Vec::new(), String::new()),
+        };
+        let e = "enum E { A(i32), B }";
+        let (patterns, place) = refutable(&format!("{e} let E::A(x) = E::B;"));
+        assert_eq!(
+            (patterns, place),
+            (vec!["E::B".to_string()], "`let`".to_string())
+        );
+        let (patterns, place) = refutable("loop (i, 0) in [(1, 0)] {}");
+        assert_eq!(patterns, ["(_, ..=-1)", "(_, 1..)"]);
+        assert_eq!(place, "`loop`");
+    }
+}
@@ -0,0 +1,616 @@
+use crate::{
+    ast::{
+        expr::{Expr, Side},
+        pattern::Pattern,
+        stmt::{ConstDecl, FnDecl, Generic, Payload, Stmt},
+        types::TypeExpr,
+    },
+    error::*,
+    position::Position,
+    prelude,
+    runtime::builtins,
+};
+use ascii::AsciiString;
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Builtin,
+    Import,
+    Function,
+    Const,
+    Struct,
+    Enum,
+    Trait,
+    Parameter { mutable: bool },
+    Variable { mutable: bool },
+}
+
+pub struct Symbol {
+    pub name: AsciiString,
+    pub kind: SymbolKind,
+    // Builtins are not declared anywhere
+    pub pos: Option<Position>,
+}
+
+// A constant or `const fn` of a checked module, by the index of its symbol
+// in the module's resolution
+#[derive(Clone)]
+pub struct Imported {
+    pub module: String,
+    pub index: usize,
+    // Whether it is a constant rather than a `const fn`
+    pub constant: bool,
+}
+
+// What the resolver found out, kept for the phases after it
+#[derive(Default)]
+pub struct Resolution {
+    // Every declaration seen so far, builtins first
+    pub symbols: Vec<Symbol>,
+    // Where each name is used and the index of the symbol it refers to
+    pub uses: HashMap<Position, usize>,
+    // Declarations hiding an earlier one of the same name, as (hiding, hidden)
+    pub shadows: Vec<(usize, usize)>,
+    // Variables declared by a `let` without a value, which their first
+    // assignment initializes
+    pub deferred: HashSet<usize>,
+    // The constants and `const fn`s the checker evaluates, by their symbol,
+    // and the imports naming the ones of other modules
+    pub consts: HashMap<usize, Rc<ConstDecl>>,
+    pub const_fns: HashMap<usize, Rc<FnDecl>>,
+    pub imported: HashMap<usize, Imported>,
+}
+
+impl Resolution {
+    // The declaration of the name used at `pos`
+    pub fn symbol_at(&self, pos: &Position) -> Option<&Symbol> {
+        self.uses.get(pos).map(|&index| &self.symbols[index])
+    }
+
+    // Whether the symbol is a constant, declared here or imported
+    pub fn is_constant(&self, index: usize) -> bool {
+        self.consts.contains_key(&index)
+            || self
+                .imported
+                .get(&index)
+                .is_some_and(|imported| imported.constant)
+    }
+}
+
+// Binds every name to its declaration. Functions, structs, enums and imports
+// are visible in their whole block, variables only after their `let`.
+pub struct Resolver {
+    resolution: Resolution,
+    // The names visible in each enclosing scope, the globals first
+    scopes: Vec<HashMap<AsciiString, usize>>,
+    // The items of the next resolved program's imports that are constants
+    // or `const fn`s, by where the `use` names them
+    imports: HashMap<Position, Imported>,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        let mut resolver = Self {
+            resolution: Resolution::default(),
+            scopes: vec![HashMap::new()],
+            imports: HashMap::new(),
+        };
+        for name in builtins::names() {
+            let name = AsciiString::from_ascii(name).unwrap();
+            resolver.declare(name, SymbolKind::Builtin, None);
+        }
+        resolver
+            .resolve(&prelude::stmts())
+            .expect("the prelude resolves");
+        resolver
+    }
+}
+
+impl Resolver {
+    pub fn resolution(&self) -> &Resolution {
+        &self.resolution
+    }
+
+    // The globals are kept, so the REPL can resolve each line against the ones before it
+    pub fn resolve(&mut self, stmts: &[Stmt]) -> Result<()> {
+        let result = self.stmts(stmts);
+        self.scopes.truncate(1);
+        self.imports.clear();
+        result
+    }
+
+    // The symbol of a global, which other modules import
+    pub fn global(&self, name: &AsciiString) -> Option<usize> {
+        self.scopes[0].get(name).copied()
+    }
+
+    // Tells which items of the imports of the next program are constants, as
+    // patterns naming them compare against them instead of binding
+    pub fn import_consts(&mut self, imports: HashMap<Position, Imported>) {
+        self.imports = imports;
+    }
+
+    fn declare(&mut self, name: AsciiString, kind: SymbolKind, pos: Option<Position>) {
+        let index = self.resolution.symbols.len();
+        if let Some(hidden) = self.lookup(&name) {
+            self.resolution.shadows.push((index, hidden));
+        }
+        let scope = self.scopes.last_mut().unwrap();
+        scope.insert(name.clone(), index);
+        self.resolution.symbols.push(Symbol { name, kind, pos });
+    }
+
+    fn lookup(&self, name: &AsciiString) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    // Values and types share a namespace, but report unknown names by what was expected
+    fn use_name(
+        &mut self,
+        name: &AsciiString,
+        pos: &Position,
+        unknown: fn(String) -> Error,
+    ) -> Result<()> {
+        match self.lookup(name) {
+            Some(index) => {
+                self.resolution.uses.insert(pos.clone(), index);
+                Ok(())
+            }
+            None => unknown(name.to_string()).throw(pos),
+        }
+    }
+
+    // Types refer to their declarations like values do, but the type checker
+    // reports the unknown ones
+    fn mention(&mut self, name: &AsciiString, pos: &Position) {
+        if let Some(index) = self.lookup(name) {
+            self.resolution.uses.insert(pos.clone(), index);
+        }
+    }
+
+    // The lengths of array types are expressions, resolved like any other
+    fn types<'a>(&mut self, types: impl IntoIterator<Item = &'a TypeExpr>) -> Result<()> {
+        for ty in types {
+            match ty {
+                TypeExpr::Named { name, args, pos } => {
+                    self.mention(name, pos);
+                    self.types(args)?;
+                }
+                TypeExpr::Tuple { elements, .. } => self.types(elements)?,
+                TypeExpr::Optional { inner, .. } => self.types([&**inner])?,
+                TypeExpr::Array {
+                    element, length, ..
+                } => {
+                    self.types([&**element])?;
+                    self.exprs(length.as_deref())?;
+                }
+                TypeExpr::Function { params, ret, .. } => {
+                    self.types(params)?;
+                    self.types(ret.as_deref())?;
+                }
+                TypeExpr::Dyn { name, pos } => self.mention(name, pos),
+            }
+        }
+        Ok(())
+    }
+
+    fn generics(&mut self, generics: &[Generic]) {
+        for (name, pos) in generics.iter().flat_map(|generic| &generic.bounds) {
+            self.mention(name, pos);
+        }
+    }
+
+    fn scoped(&mut self, resolve: impl FnOnce(&mut Self) -> Result<()>) -> Result<()> {
+        self.scopes.push(HashMap::new());
+        let result = resolve(self);
+        self.scopes.pop();
+        result
+    }
+
+    fn stmts(&mut self, stmts: &[Stmt]) -> Result<()> {
+        // Two items of a block can not share a name
+        let mut items: HashMap<&AsciiString, &Position> = HashMap::new();
+        for stmt in stmts {
+            let declared: Vec<(&AsciiString, &Position, SymbolKind)> = match stmt {
+                Stmt::Function { decl } => vec![(&decl.name, &decl.pos, SymbolKind::Function)],
+                Stmt::Const { decl } => vec![(&decl.name, &decl.pos, SymbolKind::Const)],
+                Stmt::Struct { decl } => vec![(&decl.name, &decl.pos, SymbolKind::Struct)],
+                Stmt::Enum { decl } => vec![(&decl.name, &decl.pos, SymbolKind::Enum)],
+                Stmt::Trait { decl } => vec![(&decl.name, &decl.pos, SymbolKind::Trait)],
+                Stmt::Use { items, .. } => items
+                    .iter()
+                    .map(|(name, pos)| (name, pos, SymbolKind::Import))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            for (name, pos, kind) in declared {
+                if let Some(first) = items.insert(name, pos) {
+                    return Error::DuplicateDefinition(name.to_string(), first.clone()).throw(pos);
+                }
+                let index = self.resolution.symbols.len();
+                self.declare(name.clone(), kind, Some(pos.clone()));
+                if let Some(imported) = self.imports.get(pos) {
+                    self.resolution.imported.insert(index, imported.clone());
+                }
+            }
+            match stmt {
+                Stmt::Const { decl } => {
+                    let index = self.resolution.symbols.len() - 1;
+                    self.resolution.consts.insert(index, Rc::clone(decl));
+                }
+                Stmt::Function { decl } if decl.constant => {
+                    let index = self.resolution.symbols.len() - 1;
+                    self.resolution.const_fns.insert(index, Rc::clone(decl));
+                }
+                _ => {}
+            }
+        }
+        for stmt in stmts {
+            self.stmt(stmt)?;
+        }
+        Ok(())
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> Result<()> {
+        match stmt {
+            Stmt::Expr { expresion } => self.expr(expresion),
+            Stmt::Function { decl } => self.function(decl),
+            Stmt::Const { decl } => {
+                self.types([&decl.ty])?;
+                self.expr(&decl.value)
+            }
+            Stmt::Impl {
+                generics,
+                trait_,
+                ty,
+                args,
+                methods,
+                pos,
+            } => {
+                if let Some((name, pos)) = trait_ {
+                    self.use_name(name, pos, Error::UnknownTrait)?;
+                }
+                self.use_name(ty, pos, Error::UnknownType)?;
+                self.generics(generics);
+                self.types(args)?;
+                for method in methods {
+                    self.function(method)?;
+                }
+                Ok(())
+            }
+            // Methods without a body only have types to resolve
+            Stmt::Trait { decl } => {
+                for method in &decl.methods {
+                    if method.provided {
+                        self.function(&method.decl)?;
+                    } else {
+                        self.signature(&method.decl)?;
+                    }
+                }
+                Ok(())
+            }
+            // The value is resolved first, so `let x = x + 1;` reads the outer `x`
+            Stmt::Let {
+                pattern, ty, value, ..
+            } => {
+                self.types(ty)?;
+                if let Some(value) = value {
+                    self.expr(value)?;
+                }
+                self.pattern(pattern)?;
+                // Only a plain variable can be declared without a value
+                if value.is_none() {
+                    let index = self.resolution.symbols.len() - 1;
+                    self.resolution.deferred.insert(index);
+                }
+                Ok(())
+            }
+            Stmt::Struct { decl } => {
+                unique(decl.fields.iter().map(|field| (&field.name, &field.pos)))?;
+                self.generics(&decl.generics);
+                self.types(decl.fields.iter().map(|field| &field.ty))
+            }
+            Stmt::Enum { decl } => {
+                unique(
+                    decl.variants
+                        .iter()
+                        .map(|variant| (&variant.name, &variant.pos)),
+                )?;
+                self.generics(&decl.generics);
+                for variant in &decl.variants {
+                    match &variant.payload {
+                        Payload::Unit => {}
+                        Payload::Tuple(types) => self.types(types)?,
+                        Payload::Struct(fields) => {
+                            unique(fields.iter().map(|field| (&field.name, &field.pos)))?;
+                            self.types(fields.iter().map(|field| &field.ty))?
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Stmt::Use { .. } => Ok(()),
+        }
+    }
+
+    fn signature(&mut self, decl: &FnDecl) -> Result<()> {
+        self.generics(&decl.generics);
+        self.types(decl.params.iter().filter_map(|param| param.ty.as_ref()))?;
+        self.types(&decl.ret)
+    }
+
+    fn function(&mut self, decl: &FnDecl) -> Result<()> {
+        self.signature(decl)?;
+        self.scoped(|resolver| {
+            let mut params: HashMap<&AsciiString, &Position> = HashMap::new();
+            for param in &decl.params {
+                if let Some(first) = params.insert(&param.name, &param.pos) {
+                    let name = param.name.to_string();
+                    return Error::DuplicateDefinition(name, first.clone()).throw(&param.pos);
+                }
+                let kind = SymbolKind::Parameter {
+                    mutable: param.mutable,
+                };
+                resolver.declare(param.name.clone(), kind, Some(param.pos.clone()));
+            }
+            resolver.expr(&decl.body)
+        })
+    }
+
+    // Resolves the paths in a pattern and declares its bindings in the current scope
+    fn pattern(&mut self, pattern: &Pattern) -> Result<()> {
+        let mut bindings = Vec::new();
+        self.bindings(pattern, &mut bindings)?;
+        let mut declared: HashMap<&AsciiString, &Position> = HashMap::new();
+        for (name, mutable, pos) in bindings {
+            if let Some(first) = declared.insert(name, pos) {
+                return Error::DuplicateDefinition(name.to_string(), first.clone()).throw(pos);
+            }
+            self.declare(
+                name.clone(),
+                SymbolKind::Variable { mutable },
+                Some(pos.clone()),
+            );
+        }
+        Ok(())
+    }
+
+    fn bindings<'a>(
+        &mut self,
+        pattern: &'a Pattern,
+        bindings: &mut Vec<(&'a AsciiString, bool, &'a Position)>,
+    ) -> Result<()> {
+        match pattern {
+            // A name of a constant is compared against instead
+            Pattern::Binding {
+                name,
+                mutable: false,
+                pos,
+                ..
+            } if self
+                .lookup(name)
+                .is_some_and(|index| self.resolution.is_constant(index)) =>
+            {
+                self.use_name(name, pos, Error::UnknownIdent)?
+            }
+            Pattern::Binding {
+                name, mutable, pos, ..
+            } => bindings.push((name, *mutable, pos)),
+            Pattern::Struct { path, fields, pos } => {
+                self.use_name(&path[0], pos, Error::UnknownType)?;
+                for (_, pattern) in fields {
+                    self.bindings(pattern, bindings)?;
+                }
+            }
+            Pattern::Variant {
+                path,
+                elements,
+                pos,
+            } => {
+                self.use_name(&path[0], pos, Error::UnknownType)?;
+                for pattern in elements {
+                    self.bindings(pattern, bindings)?;
+                }
+            }
+            Pattern::Tuple { elements, .. } => {
+                for pattern in elements {
+                    self.bindings(pattern, bindings)?;
+                }
+            }
+            Pattern::Path { path, pos } => self.use_name(&path[0], pos, Error::UnknownType)?,
+            Pattern::Literal { .. } | Pattern::Range { .. } | Pattern::Wildcard { .. } => {}
+        }
+        Ok(())
+    }
+
+    fn exprs<'a>(&mut self, exprs: impl IntoIterator<Item = &'a Expr>) -> Result<()> {
+        for expr in exprs {
+            self.expr(expr)?;
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<()> {
+        match expr {
+            Expr::Array { elements, .. } | Expr::Tuple { elements, .. } => self.exprs(elements),
+            Expr::Assign { target, value, .. } => self.exprs([&**target, &**value]),
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.exprs([&**left, &**right])
+            }
+            Expr::Block { stmts, tail, .. } => self.scoped(|resolver| {
+                resolver.stmts(stmts)?;
+                resolver.exprs(tail.as_deref())
+            }),
+            Expr::Break { value, .. } | Expr::Return { value, .. } => self.exprs(value.as_deref()),
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                self.expr(callee)?;
+                self.exprs(arguments)
+            }
+            Expr::Closure { decl, .. } => self.function(decl),
+            Expr::Field { object, .. } | Expr::TupleIndex { object, .. } => self.expr(object),
+            Expr::Group { expresion } => self.expr(expresion),
+            Expr::TypeArgs {
+                expresion, args, ..
+            } => {
+                self.types(args)?;
+                self.expr(expresion)
+            }
+            Expr::Cast { expresion, ty, .. } => {
+                self.types([ty])?;
+                self.expr(expresion)
+            }
+            Expr::Index { object, index, .. } => self.exprs([&**object, &**index]),
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.exprs([&**condition, &**then_branch])?;
+                self.exprs(else_branch.as_deref())
+            }
+            Expr::Map { entries, .. } => {
+                for (key, value) in entries {
+                    self.exprs([key, value])?;
+                }
+                Ok(())
+            }
+            Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                self.expr(scrutinee)?;
+                for arm in arms {
+                    self.scoped(|resolver| {
+                        resolver.pattern(&arm.pattern)?;
+                        resolver.exprs(arm.guard.as_ref())?;
+                        resolver.expr(&arm.body)
+                    })?;
+                }
+                Ok(())
+            }
+            Expr::Method {
+                receiver,
+                arguments,
+                ..
+            } => {
+                self.expr(receiver)?;
+                self.exprs(arguments)
+            }
+            Expr::Loop { body, .. } => self.expr(body),
+            Expr::LoopIn {
+                pattern,
+                iterable,
+                body,
+                ..
+            } => {
+                self.expr(iterable)?;
+                self.scoped(|resolver| {
+                    resolver.pattern(pattern)?;
+                    resolver.expr(body)
+                })
+            }
+            Expr::Path { segments, pos } => self.use_name(&segments[0], pos, Error::UnknownType),
+            Expr::Range { start, end, .. } => {
+                self.exprs(start.as_deref())?;
+                self.exprs(end.as_deref())
+            }
+            Expr::Struct { path, fields, pos } => {
+                self.use_name(&path[0], pos, Error::UnknownType)?;
+                self.exprs(fields.iter().map(|(_, value)| value))
+            }
+            Expr::Unary {
+                expresion: Side::Left(operand) | Side::Right(operand),
+                ..
+            } => self.expr(operand),
+            Expr::Variable { name, pos } => self.use_name(name, pos, Error::UnknownIdent),
+            Expr::Continue { .. } | Expr::Literal { .. } => Ok(()),
+        }
+    }
+}
+
+// Rejects the second of two fields or variants of the same name
+fn unique<'a>(names: impl Iterator<Item = (&'a AsciiString, &'a Position)>) -> Result<()> {
+    let mut first: HashMap<&AsciiString, &Position> = HashMap::new();
+    for (name, pos) in names {
+        if let Some(first) = first.insert(name, pos) {
+            return Error::DuplicateDefinition(name.to_string(), first.clone()).throw(pos);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Resolution, Resolver, SymbolKind};
+    use crate::{
+        ast::{lexer::Lexer, parser::Parser},
+        error::{Error, Result},
+    };
+
+    fn resolve(source: &str) -> Result<Resolver> {
+        let tokens = Lexer::new("test", source).tokenize().unwrap();
+        let stmts = Parser::new(tokens).parse().unwrap();
+        let mut resolver = Resolver::default();
+        resolver.resolve(&stmts)?;
+        Ok(resolver)
+    }
+
+    // The columns of the declarations of the uses of `name` on the first line,
+    // in the order of the uses
+    fn declarations(resolution: &Resolution, name: &str) -> Vec<u16> {
+        let mut uses: Vec<_> = resolution.uses.iter().collect();
+        uses.sort_by_key(|(pos, _)| pos.column());
+        uses.into_iter()
+            .map(|(_, &index)| &resolution.symbols[index])
+            .filter(|symbol| symbol.name.as_str() == name)
+            .map(|symbol| symbol.pos.as_ref().unwrap().column())
+            .collect()
+    }
+
+    #[test]
+    fn names_refer_to_their_nearest_declaration() {
+        let resolver = resolve("let x = 1; let x = x + 1; fn f(x: i32) -> i32 { x } f(x)").unwrap();
+        let resolution = resolver.resolution();
+        assert_eq!(declarations(resolution, "x"), [5, 32, 16]);
+        let (hiding, hidden) = *resolution.shadows.last().unwrap();
+        assert!(matches!(
+            resolution.symbols[hiding].kind,
+            SymbolKind::Parameter { .. }
+        ));
+        assert!(matches!(
+            resolution.symbols[hidden].kind,
+            SymbolKind::Variable { .. }
+        ));
+        // Functions are visible before they are declared, variables only after
+        assert!(resolve("f(); fn f() {}").is_ok());
+        assert!(matches!(
+            resolve("x; let x = 1;"),
+            Err(Error::UnknownIdent(_))
+        ));
+    }
+
+    #[test]
+    fn names_are_declared_once_in_a_scope() {
+        let duplicate = |source| match resolve(source) {
+            Err(Error::DuplicateDefinition(name, first)) => (name, first.column()),
+            _ => (String::new(), 0),
+        };
+        assert_eq!(duplicate("fn f(a: i32, a: i32) {}"), ("a".to_string(), 6));
+        assert_eq!(duplicate("fn f() {} fn f() {}"), ("f".to_string(), 4));
+        assert_eq!(
+            duplicate("struct P { x: i32, x: i32 }"),
+            ("x".to_string(), 12)
+        );
+        assert_eq!(duplicate("let (a, a) = (1, 2);"), ("a".to_string(), 6));
+        assert!(resolve("let a = 1; { let a = 2; }").is_ok());
+    }
+}
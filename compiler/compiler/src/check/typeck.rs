@@ -0,0 +1,2593 @@
+use super::types::{NumberType, Type};
+use crate::{
+    ast::{
+        expr::{Expr, Side},
+        pattern::{path_name, Pattern},
+        stmt::{FnDecl, Generic, Payload, Stmt, TraitDecl},
+        token::{Literal, Number, Operator, Unsuffixed},
+        types::TypeExpr,
+    },
+    error::*,
+    position::Position,
+    prelude::{self, Overload},
+};
+use ascii::AsciiString;
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+    rc::Rc,
+    slice,
+};
+
+// A function of an `impl` block, `receiver` is set for methods taking `self`
+// and `mutates` for the ones taking `mut self`. The type parameters are the
+// ones of the block followed by its own.
+#[derive(Clone)]
+struct Method {
+    generics: Vec<Position>,
+    params: Vec<Type>,
+    ret: Type,
+    receiver: bool,
+    mutates: bool,
+    // Where the method is declared
+    pos: Position,
+}
+
+// The resolved types of what a struct or an enum declares
+#[derive(Clone, Default)]
+struct Members {
+    // The fields of a struct, in the order they are declared
+    fields: Vec<Type>,
+    // The payload of each variant of an enum, struct variants in field order
+    variants: Vec<Vec<Type>>,
+    methods: HashMap<AsciiString, Method>,
+}
+
+// The methods of a trait, where `Self` is a type parameter declared at the trait
+#[derive(Clone)]
+struct Trait {
+    methods: HashMap<AsciiString, Method>,
+}
+
+// An `impl Trait for Type`, with the methods it gives the type. The ones the
+// trait provides are included, the type takes the place of `Self` in them.
+#[derive(Clone)]
+struct Impl {
+    trait_: Rc<TraitDecl>,
+    methods: HashMap<AsciiString, Method>,
+    pos: Position,
+}
+
+// The names declared in a block. Values and types share a namespace, so the
+// resolver keeps them from clashing.
+#[derive(Default)]
+struct Names {
+    values: HashMap<AsciiString, Type>,
+    // The type parameters of the generic functions among the values, which
+    // are instantiated wherever the function is named
+    generics: HashMap<AsciiString, Vec<Position>>,
+    // Structs and enums without their type arguments, type parameters and
+    // traits as `Type::Dyn`
+    types: HashMap<AsciiString, Type>,
+    // The values that are constants, which patterns naming them compare against
+    consts: HashSet<AsciiString>,
+    // Where the functions among the values are declared
+    functions: HashMap<AsciiString, Position>,
+}
+
+// What a type variable may become, unsuffixed numbers only become numbers
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Any,
+    Integer,
+    Float,
+}
+
+// An enclosing loop, with the type of the values its `break`s leave it with
+// and where the first of them is
+struct Loop {
+    label: Option<AsciiString>,
+    value: Option<(Type, Position)>,
+}
+
+#[derive(Clone)]
+enum Var {
+    Unbound(Kind),
+    Bound(Type),
+}
+
+// Checks the types of a program against its annotations. Local bindings,
+// closures and unsuffixed numbers are inferred from how they are used, the
+// parameters and return types of functions are `Type::Unknown` unless
+// annotated and left for the interpreter to check.
+pub struct TypeChecker {
+    // The names visible in each enclosing block, the globals first
+    scopes: Vec<Names>,
+    // The members of every struct and enum, by where they are declared
+    members: HashMap<Position, Members>,
+    // Every trait and the traits each type parameter is bounded by, by where
+    // they are declared
+    traits: HashMap<Position, Trait>,
+    bounds: HashMap<Position, Vec<Rc<TraitDecl>>>,
+    // The trait implementations of the program and the modules it imports,
+    // by where the type and the trait are declared
+    impls: HashMap<(Position, Position), Impl>,
+    // The types and traits imported from other modules, which can not be
+    // implemented for each other
+    imported: HashSet<Position>,
+    // Type arguments that must implement a trait, checked once they are inferred
+    obligations: Vec<(Type, Rc<TraitDecl>, Position)>,
+    // The return type of each enclosing function and where it is annotated
+    returns: Vec<(Type, Option<Position>)>,
+    // The loops enclosing the checked expression in its function
+    loops: Vec<Loop>,
+    // What each `Type::Var` is bound to so far
+    vars: Vec<Var>,
+    // The unsuffixed numbers of the program, given their value once the
+    // whole program is checked
+    literals: Vec<(Unsuffixed, Type, Position)>,
+    // Negated numbers whose type is not known yet, which must be signed
+    negated: Vec<(Type, Position)>,
+    // The number casts of the program, by where their `as` is once the types
+    // of the numbers they cast are inferred
+    numeric_casts: Vec<(Type, NumberType, Position)>,
+    casts: HashMap<Position, (NumberType, NumberType)>,
+    // The method calls of the last checked program changing their receiver
+    mutating: HashSet<Position>,
+    // The traits of the methods the last checked program calls on the
+    // types implementing them
+    called: HashSet<Position>,
+}
+
+// The prelude is declared like the items of an imported module
+impl Default for TypeChecker {
+    fn default() -> Self {
+        let mut checker = Self {
+            scopes: vec![Names::default()],
+            members: HashMap::new(),
+            traits: HashMap::new(),
+            bounds: HashMap::new(),
+            impls: HashMap::new(),
+            imported: HashSet::new(),
+            obligations: Vec::new(),
+            returns: Vec::new(),
+            loops: Vec::new(),
+            vars: Vec::new(),
+            literals: Vec::new(),
+            negated: Vec::new(),
+            numeric_casts: Vec::new(),
+            casts: HashMap::new(),
+            mutating: HashSet::new(),
+            called: HashSet::new(),
+        };
+        let stmts = prelude::stmts();
+        checker.declare(&stmts).expect("the prelude type checks");
+        checker
+            .imported
+            .extend(stmts.iter().filter_map(|stmt| match stmt {
+                Stmt::Enum { decl } => Some(decl.pos.clone()),
+                Stmt::Trait { decl } => Some(decl.pos.clone()),
+                _ => None,
+            }));
+        checker
+    }
+}
+
+impl TypeChecker {
+    // The globals are kept, so the REPL can check each line against the ones before it
+    pub fn check(&mut self, stmts: &[Stmt]) -> Result<()> {
+        self.mutating.clear();
+        self.called.clear();
+        self.casts.clear();
+        let result = self.stmts(stmts).and_then(|_| self.finish());
+        self.scopes.truncate(1);
+        self.returns.clear();
+        self.loops.clear();
+        self.literals.clear();
+        self.negated.clear();
+        self.numeric_casts.clear();
+        self.obligations.clear();
+        // The globals outlive the variables, what is still not inferred is unknown
+        let globals = mem::take(&mut self.scopes[0].values);
+        self.scopes[0].values = globals
+            .into_iter()
+            .map(|(name, ty)| (name, self.resolved(&ty)))
+            .collect();
+        self.vars.clear();
+        result
+    }
+
+    // Whether the method called at `pos` changes its receiver. Methods of
+    // values whose type is not known are assumed not to.
+    pub fn mutates(&self, pos: &Position) -> bool {
+        self.mutating.contains(pos)
+    }
+
+    // The number types the `as` at `pos` casts from and to, when both are known
+    pub fn cast(&self, pos: &Position) -> Option<(NumberType, NumberType)> {
+        self.casts.get(pos).copied()
+    }
+
+    // Whether the program calls a method of the trait named `name` on a type
+    // implementing it, which needs the trait in scope but does not name it
+    pub fn calls_trait(&self, name: &AsciiString) -> bool {
+        matches!(self.lookup_type(name), Some(Type::Dyn(decl)) if self.called.contains(&decl.pos))
+    }
+
+    // Numbers nothing constrains default to `i32` and `f64`, once every
+    // constraint is known the unsuffixed ones get their value
+    fn finish(&mut self) -> Result<()> {
+        for (number, ty, pos) in mem::take(&mut self.literals) {
+            let Type::Number(ty) = self.resolved(&ty) else {
+                unreachable!("unsuffixed numbers are bound to numbers")
+            };
+            if ty.is_unsigned() && number.digits.starts_with('-') {
+                return Error::InvalidOperand(String::from("-"), ty.name().to_string()).throw(&pos);
+            }
+            match Number::parse(&number.digits, ty.name()) {
+                Ok(value) => *number.number.borrow_mut() = Some(value),
+                Err(error) => return error.throw(&pos),
+            }
+        }
+        for (ty, pos) in mem::take(&mut self.negated) {
+            if let Type::Number(number) = self.resolved(&ty) {
+                if number.is_unsigned() {
+                    return Error::InvalidOperand(String::from("-"), number.name().to_string())
+                        .throw(&pos);
+                }
+            }
+        }
+        for (from, to, pos) in mem::take(&mut self.numeric_casts) {
+            if let Type::Number(from) = self.resolved(&from) {
+                self.casts.insert(pos, (from, to));
+            }
+        }
+        for (ty, trait_, pos) in mem::take(&mut self.obligations) {
+            if !self.implements(&ty, &trait_) {
+                return Error::UnsatisfiedBound(self.show(&ty), trait_.name.to_string())
+                    .throw(&pos);
+            }
+        }
+        Ok(())
+    }
+
+    // Makes an item of a checked module visible to the program importing it
+    pub fn import(&mut self, name: &AsciiString, module: &TypeChecker) {
+        let globals = &module.scopes[0];
+        if let Some(ty) = globals.values.get(name) {
+            let generics = globals.generics.get(name).cloned().unwrap_or_default();
+            self.define_generic(name, generics, ty.clone());
+            if globals.consts.contains(name) {
+                self.scopes[0].consts.insert(name.clone());
+            }
+            if let Some(pos) = globals.functions.get(name) {
+                self.scopes[0].functions.insert(name.clone(), pos.clone());
+            }
+        }
+        if let Some(ty) = globals.types.get(name) {
+            let declared = match ty {
+                Type::Dyn(decl) => Some(&decl.pos),
+                ty => declared_at(ty),
+            };
+            self.imported.extend(declared.cloned());
+            self.scopes[0].types.insert(name.clone(), ty.clone());
+        }
+    }
+
+    // Implementations hold wherever the trait and the type are known, so the
+    // ones of a module come along with any item imported from it. So do the
+    // fields and methods of its types, which values of them returned by the
+    // items have whether or not the type is named.
+    pub fn import_impls(&mut self, module: &TypeChecker) -> Result<()> {
+        for (key, implementation) in &module.impls {
+            if let Some(first) = self
+                .impls
+                .get(key)
+                .filter(|first| first.pos != implementation.pos)
+            {
+                let name = implementation.trait_.name.to_string();
+                let ty = module.type_name(&key.0);
+                return Error::ConflictingImpls(name, ty, first.pos.clone())
+                    .throw(&implementation.pos);
+            }
+            self.impls.insert(key.clone(), implementation.clone());
+        }
+        for (pos, members) in &module.members {
+            self.members.insert(pos.clone(), members.clone());
+        }
+        for (pos, trait_) in &module.traits {
+            self.traits.insert(pos.clone(), trait_.clone());
+        }
+        for (pos, bounds) in &module.bounds {
+            self.bounds.insert(pos.clone(), bounds.clone());
+        }
+        Ok(())
+    }
+
+    // The name of the struct or enum declared at `pos`
+    fn type_name(&self, pos: &Position) -> String {
+        let types = self.scopes.iter().flat_map(|names| names.types.values());
+        match types.into_iter().find(|ty| declared_at(ty) == Some(pos)) {
+            Some(ty) => ty.to_string(),
+            None => String::from("the type"),
+        }
+    }
+
+    fn scoped<T>(&mut self, check: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.scopes.push(Names::default());
+        let result = check(self);
+        self.scopes.pop();
+        result
+    }
+
+    fn names(&mut self) -> &mut Names {
+        self.scopes.last_mut().unwrap()
+    }
+
+    // Names a value in the innermost block, hiding any value of the same name
+    fn define(&mut self, name: &AsciiString, ty: Type) {
+        self.define_generic(name, Vec::new(), ty);
+    }
+
+    fn define_generic(&mut self, name: &AsciiString, generics: Vec<Position>, ty: Type) {
+        let names = self.names();
+        names.values.insert(name.clone(), ty);
+        names.generics.insert(name.clone(), generics);
+        names.consts.remove(name);
+        names.functions.remove(name);
+    }
+
+    // Where the function `name` refers to is declared, if it refers to one
+    fn function_decl(&self, name: &AsciiString) -> Option<Position> {
+        let names = self
+            .scopes
+            .iter()
+            .rev()
+            .find(|names| names.values.contains_key(name))?;
+        names.functions.get(name).cloned()
+    }
+
+    // The type of the constant `name` refers to, if it refers to one
+    fn constant(&self, name: &AsciiString) -> Option<Type> {
+        let names = self
+            .scopes
+            .iter()
+            .rev()
+            .find(|names| names.values.contains_key(name))?;
+        names
+            .consts
+            .contains(name)
+            .then(|| names.values[name].clone())
+    }
+
+    // Declares the type parameters of a generic declaration in the innermost block
+    fn generics(&mut self, generics: &[Generic]) -> Result<()> {
+        let mut declared: HashMap<&AsciiString, &Position> = HashMap::new();
+        for generic in generics {
+            if let Some(first) = declared.insert(&generic.name, &generic.pos) {
+                let name = generic.name.to_string();
+                return Error::DuplicateDefinition(name, first.clone()).throw(&generic.pos);
+            }
+            let bounds = generic
+                .bounds
+                .iter()
+                .map(|(name, pos)| self.trait_named(name, pos))
+                .collect::<Result<_>>()?;
+            self.bounds.insert(generic.pos.clone(), bounds);
+            let param = Type::Param(generic.name.clone(), generic.pos.clone());
+            self.names().types.insert(generic.name.clone(), param);
+        }
+        Ok(())
+    }
+
+    fn trait_named(&self, name: &AsciiString, pos: &Position) -> Result<Rc<TraitDecl>> {
+        match self.lookup_type(name) {
+            Some(Type::Dyn(decl)) => Ok(decl),
+            _ => Error::UnknownTrait(name.to_string()).throw(pos),
+        }
+    }
+
+    // `Self` inside of a trait, a type parameter bounded by the trait
+    fn self_param(&mut self, decl: &TraitDecl) -> Type {
+        let param = Type::Param(self_name(), decl.pos.clone());
+        let this = self.lookup_type(&decl.name).unwrap_or(Type::Unknown);
+        if let Type::Dyn(this) = this {
+            self.bounds.insert(decl.pos.clone(), vec![this]);
+        }
+        self.names().types.insert(self_name(), param.clone());
+        param
+    }
+
+    // Whether a value of type `ty` is known to implement the trait. Types
+    // that are not known yet are checked by the interpreter.
+    fn implements(&self, ty: &Type, trait_: &TraitDecl) -> bool {
+        match self.shallow(ty) {
+            Type::Param(_, pos) => self
+                .bounds
+                .get(&pos)
+                .is_some_and(|bounds| bounds.iter().any(|bound| bound.pos == trait_.pos)),
+            Type::Dyn(decl) => decl.pos == trait_.pos,
+            ty @ (Type::Struct(..) | Type::Enum(..)) => {
+                let key = (declared_at(&ty).unwrap().clone(), trait_.pos.clone());
+                self.impls.contains_key(&key)
+            }
+            ty => self.unknown(&ty) || self.builtin_implements(&ty, trait_),
+        }
+    }
+
+    // The builtin types implement the standard traits of the operators the
+    // interpreter applies to them
+    fn builtin_implements(&self, ty: &Type, trait_: &TraitDecl) -> bool {
+        if !prelude::is_standard(trait_) {
+            return false;
+        }
+        match (ty, trait_.name.as_str()) {
+            (Type::Number(number), "Neg") => !number.is_unsigned(),
+            (_, "Add" | "Sub" | "Mul" | "Div" | "Rem" | "Neg" | "Eq" | "Ord")
+                if self.numeric(ty) =>
+            {
+                true
+            }
+            (Type::String, "Add" | "Eq" | "Ord") | (Type::Char, "Eq" | "Ord") => true,
+            (Type::Bool, "Eq") => true,
+            _ => false,
+        }
+    }
+
+    // Whether the trait can be named where its methods are called
+    fn in_scope(&self, trait_: &TraitDecl) -> bool {
+        matches!(self.lookup_type(&trait_.name), Some(Type::Dyn(decl)) if decl.pos == trait_.pos)
+    }
+
+    // The type of a value and the type parameters it is generic over
+    fn scheme(&self, name: &AsciiString) -> Option<(Type, Vec<Position>)> {
+        self.scopes.iter().rev().find_map(|names| {
+            let ty = names.values.get(name)?;
+            let generics = names.generics.get(name).cloned().unwrap_or_default();
+            Some((ty.clone(), generics))
+        })
+    }
+
+    // Each use of a generic value gets its own type arguments
+    fn lookup(&mut self, name: &AsciiString, pos: &Position) -> Type {
+        match self.scheme(name) {
+            Some((ty, generics)) => self.instantiate(&ty, &generics, pos),
+            None => Type::Unknown,
+        }
+    }
+
+    fn instantiate(&mut self, ty: &Type, generics: &[Position], pos: &Position) -> Type {
+        let args = self.fresh_args(generics.len());
+        self.apply(ty, generics, &args, pos)
+    }
+
+    // Gives the type parameters their arguments, which must implement the
+    // traits the parameters are bounded by
+    fn apply(&mut self, ty: &Type, generics: &[Position], args: &[Type], pos: &Position) -> Type {
+        for (generic, arg) in generics.iter().zip(args) {
+            for bound in self.bounds.get(generic).into_iter().flatten() {
+                let obligation = (arg.clone(), Rc::clone(bound), pos.clone());
+                self.obligations.push(obligation);
+            }
+        }
+        substitute(ty, generics, args)
+    }
+
+    fn fresh_args(&mut self, count: usize) -> Vec<Type> {
+        (0..count).map(|_| self.fresh(Kind::Any)).collect()
+    }
+
+    // A struct or an enum of types yet to be inferred
+    fn instance(&mut self, ty: Type) -> Type {
+        let args = self.fresh_args(declared_generics(&ty).len());
+        with_args(ty, args)
+    }
+
+    // The types of the fields of a struct, with its type arguments in place
+    fn fields(&self, ty: &Type) -> Option<Vec<Type>> {
+        let Type::Struct(decl, args) = ty else {
+            return None;
+        };
+        let generics = generic_positions(&decl.generics);
+        let fields = self.members(ty)?.fields.iter();
+        Some(
+            fields
+                .map(|field| substitute(field, &generics, args))
+                .collect(),
+        )
+    }
+
+    // The types a variant of an enum carries, with its type arguments in place
+    fn payload(&self, ty: &Type, index: usize) -> Option<Vec<Type>> {
+        let Type::Enum(decl, args) = ty else {
+            return None;
+        };
+        let generics = generic_positions(&decl.generics);
+        let payload = self.members(ty)?.variants.get(index)?.iter();
+        Some(payload.map(|ty| substitute(ty, &generics, args)).collect())
+    }
+
+    fn lookup_type(&self, name: &AsciiString) -> Option<Type> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|names| names.types.get(name).cloned())
+    }
+
+    fn members(&self, ty: &Type) -> Option<&Members> {
+        self.members.get(declared_at(ty)?)
+    }
+
+    fn fresh(&mut self, kind: Kind) -> Type {
+        self.vars.push(Var::Unbound(kind));
+        Type::Var(self.vars.len() - 1)
+    }
+
+    // The type with its outermost variable looked up, nested ones are kept
+    fn shallow(&self, ty: &Type) -> Type {
+        match ty {
+            Type::Var(var) => match &self.vars[*var] {
+                Var::Bound(ty) => self.shallow(ty),
+                Var::Unbound(_) => ty.clone(),
+            },
+            ty => ty.clone(),
+        }
+    }
+
+    // What an unbound variable may become, `None` for any other type
+    fn kind(&self, ty: &Type) -> Option<Kind> {
+        match self.shallow(ty) {
+            Type::Var(var) => match self.vars[var] {
+                Var::Unbound(kind) => Some(kind),
+                Var::Bound(_) => None,
+            },
+            _ => None,
+        }
+    }
+
+    // Numbers and unsuffixed numbers of a type not inferred yet
+    fn numeric(&self, ty: &Type) -> bool {
+        match self.kind(ty) {
+            Some(kind) => kind != Kind::Any,
+            None => matches!(self.shallow(ty), Type::Number(_)),
+        }
+    }
+
+    fn integer(&self, ty: &Type) -> bool {
+        match self.shallow(ty) {
+            Type::Number(number) => !number.is_fractional(),
+            ty => self.kind(&ty) == Some(Kind::Integer),
+        }
+    }
+
+    // Whether the checks of a type are left to the interpreter
+    fn unknown(&self, ty: &Type) -> bool {
+        matches!(self.shallow(ty), Type::Unknown | Type::Never) || self.kind(ty) == Some(Kind::Any)
+    }
+
+    // The type with every variable looked up. Numbers nothing constrains have
+    // their default type, anything else not inferred is unknown.
+    fn resolved(&self, ty: &Type) -> Type {
+        match ty {
+            Type::Var(var) => match &self.vars[*var] {
+                Var::Bound(ty) => self.resolved(ty),
+                Var::Unbound(Kind::Any) => Type::Unknown,
+                Var::Unbound(Kind::Integer) => Type::Number(NumberType::I32),
+                Var::Unbound(Kind::Float) => Type::Number(NumberType::F64),
+            },
+            ty => ty.map(|ty| self.resolved(ty)),
+        }
+    }
+
+    // How a type is shown in errors
+    fn show(&self, ty: &Type) -> String {
+        self.resolved(ty).to_string()
+    }
+
+    fn occurs(&self, var: usize, ty: &Type) -> bool {
+        match self.shallow(ty) {
+            Type::Var(other) => other == var,
+            Type::Array(inner, _) | Type::Range(inner) | Type::Optional(inner) => {
+                self.occurs(var, &inner)
+            }
+            Type::Map(key, value) => self.occurs(var, &key) || self.occurs(var, &value),
+            Type::Tuple(elements) | Type::Struct(_, elements) | Type::Enum(_, elements) => {
+                elements.iter().any(|ty| self.occurs(var, ty))
+            }
+            Type::Function(params, ret) => {
+                params.iter().any(|ty| self.occurs(var, ty)) || self.occurs(var, &ret)
+            }
+            _ => false,
+        }
+    }
+
+    // Whether a value of type `found` can be used where `expected` is, binding
+    // the variables of either so it can. Unknown types are accepted both ways,
+    // the runtime checks them.
+    fn unify(&mut self, expected: &Type, found: &Type) -> bool {
+        let (expected, found) = (self.shallow(expected), self.shallow(found));
+        match (&expected, &found) {
+            (Type::Unknown | Type::Never, _) | (_, Type::Unknown | Type::Never) => true,
+            (Type::Var(a), Type::Var(b)) if a == b => true,
+            // An unsuffixed number is of the type inside of the optional
+            (Type::Optional(inner), Type::Var(_)) if self.kind(&found) != Some(Kind::Any) => {
+                self.unify(inner, &found)
+            }
+            (Type::Var(var), ty) | (ty, Type::Var(var)) => self.bind_var(*var, ty),
+            (Type::Optional(_) | Type::Null, Type::Null) => true,
+            (Type::Optional(expected), Type::Optional(found)) => self.unify(expected, found),
+            (Type::Optional(expected), found) => self.unify(expected, found),
+            (Type::Unit, Type::Unit)
+            | (Type::Bool, Type::Bool)
+            | (Type::Char, Type::Char)
+            | (Type::String, Type::String) => true,
+            (Type::Number(expected), Type::Number(found)) => expected == found,
+            (Type::Array(expected, _), Type::Array(found, _))
+            | (Type::Range(expected), Type::Range(found)) => self.unify(expected, found),
+            (Type::Map(key, value), Type::Map(found_key, found_value)) => {
+                self.unify(key, found_key) && self.unify(value, found_value)
+            }
+            (Type::Tuple(expected), Type::Tuple(found)) => {
+                expected.len() == found.len()
+                    && expected.iter().zip(found).all(|(e, f)| self.unify(e, f))
+            }
+            // Parameters are contravariant
+            (Type::Function(params, ret), Type::Function(found_params, found_ret)) => {
+                params.len() == found_params.len()
+                    && params
+                        .iter()
+                        .zip(found_params)
+                        .all(|(p, f)| self.unify(f, p))
+                    && self.unify(ret, found_ret)
+            }
+            (Type::Struct(expected, args), Type::Struct(found, found_args)) => {
+                Rc::ptr_eq(expected, found)
+                    && args.iter().zip(found_args).all(|(e, f)| self.unify(e, f))
+            }
+            (Type::Enum(expected, args), Type::Enum(found, found_args)) => {
+                Rc::ptr_eq(expected, found)
+                    && args.iter().zip(found_args).all(|(e, f)| self.unify(e, f))
+            }
+            (Type::Param(_, expected), Type::Param(_, found)) => expected == found,
+            (Type::Dyn(expected), Type::Dyn(found)) => expected.pos == found.pos,
+            // A value of a type implementing the trait is a trait object
+            (Type::Dyn(trait_), found) => self.implements(found, trait_),
+            _ => false,
+        }
+    }
+
+    fn bind_var(&mut self, var: usize, ty: &Type) -> bool {
+        let Some(kind) = self.kind(&Type::Var(var)) else {
+            return false;
+        };
+        let fits = match ty {
+            Type::Var(other) => match (kind, self.kind(ty)) {
+                (Kind::Any, _) => true,
+                // The variable taking any type takes the kind of the other
+                (_, Some(Kind::Any)) => {
+                    self.vars[*other] = Var::Bound(Type::Var(var));
+                    return true;
+                }
+                (kind, other) => Some(kind) == other,
+            },
+            Type::Number(number) => match kind {
+                Kind::Any => true,
+                Kind::Integer => !number.is_float(),
+                Kind::Float => number.is_fractional(),
+            },
+            // `?` makes the type of a binding optional
+            Type::Null if kind == Kind::Any => {
+                let inner = self.fresh(Kind::Any);
+                self.vars[var] = Var::Bound(Type::Optional(Box::new(inner)));
+                return true;
+            }
+            ty => kind == Kind::Any && !self.occurs(var, ty),
+        };
+        if fits {
+            self.vars[var] = Var::Bound(ty.clone());
+        }
+        fits
+    }
+
+    // The type of a value that is of either type, if they can be mixed, like
+    // the branches of an `if` or the elements of an array
+    fn common(&mut self, a: &Type, b: &Type) -> Option<Type> {
+        let (a, b) = (self.shallow(a), self.shallow(b));
+        match (&a, &b) {
+            (Type::Unknown | Type::Never, _) => Some(b),
+            (_, Type::Unknown | Type::Never) | (Type::Null, Type::Null) => Some(a),
+            (Type::Null, ty) | (ty, Type::Null) => Some(Type::optional(ty.clone())),
+            (_, Type::Optional(_)) => self.unify(&b, &a).then_some(b),
+            _ => self.unify(&a, &b).then_some(a),
+        }
+    }
+
+    // Reports a value of type `found` where one of type `expected` is required,
+    // `declared` is where `expected` is written
+    fn expect(
+        &mut self,
+        expected: &Type,
+        found: &Type,
+        pos: &Position,
+        declared: Option<&Position>,
+    ) -> Result<()> {
+        if self.unify(expected, found) {
+            return Ok(());
+        }
+        let (expected, shown) = (self.show(expected), self.show(found));
+        match self.shallow(found) {
+            Type::Null => Error::NullAssignment(expected).throw(pos),
+            _ => Error::MismatchedTypes(expected, shown, declared.cloned()).throw(pos),
+        }
+    }
+
+    fn arguments_match(
+        &mut self,
+        name: &str,
+        params: &[Type],
+        arguments: &[Expr],
+        found: &[Type],
+        pos: &Position,
+        declared: Option<&Position>,
+    ) -> Result<()> {
+        if params.len() != arguments.len() {
+            let declared = declared.cloned();
+            return Error::ArityMismatch(name.to_owned(), params.len(), arguments.len(), declared)
+                .throw(pos);
+        }
+        for ((param, argument), found) in params.iter().zip(arguments).zip(found) {
+            self.expect(param, found, value_pos(argument), None)?;
+        }
+        Ok(())
+    }
+
+    // The type an annotation names
+    fn resolve(&self, ty: &TypeExpr) -> Result<Type> {
+        Ok(match ty {
+            TypeExpr::Named { name, args, pos } => {
+                let declared = self.lookup_type(name);
+                let expected = match name.as_str() {
+                    "Map" => 2,
+                    "Range" => 1,
+                    _ => declared
+                        .as_ref()
+                        .map_or(0, |ty| declared_generics(ty).len()),
+                };
+                if args.len() != expected {
+                    return Error::TypeArguments(name.to_string(), expected, args.len()).throw(pos);
+                }
+                let mut args = self.resolve_all(args)?;
+                match name.as_str() {
+                    "bool" => Type::Bool,
+                    "char" => Type::Char,
+                    "String" => Type::String,
+                    "Map" => {
+                        let value = args.pop().unwrap();
+                        Type::Map(Box::new(args.pop().unwrap()), Box::new(value))
+                    }
+                    "Range" => Type::Range(Box::new(args.pop().unwrap())),
+                    _ => match NumberType::from_name(name.as_str()) {
+                        Some(number) => Type::Number(number),
+                        None => match declared {
+                            // Trait objects are written `dyn Trait`
+                            Some(Type::Dyn(_)) | None => {
+                                return Error::UnknownType(name.to_string()).throw(pos)
+                            }
+                            Some(ty) => with_args(ty, args),
+                        },
+                    },
+                }
+            }
+            TypeExpr::Tuple { elements, .. } if elements.is_empty() => Type::Unit,
+            TypeExpr::Tuple { elements, .. } => Type::Tuple(self.resolve_all(elements)?),
+            TypeExpr::Optional { inner, .. } => Type::optional(self.resolve(inner)?),
+            TypeExpr::Array {
+                element, length, ..
+            } => Type::Array(Box::new(self.resolve(element)?), length.is_some()),
+            TypeExpr::Function { params, ret, .. } => {
+                let ret = match ret {
+                    Some(ret) => self.resolve(ret)?,
+                    None => Type::Unit,
+                };
+                Type::Function(self.resolve_all(params)?, Box::new(ret))
+            }
+            TypeExpr::Dyn { name, pos } => Type::Dyn(self.trait_named(name, pos)?),
+        })
+    }
+
+    fn resolve_all(&self, types: &[TypeExpr]) -> Result<Vec<Type>> {
+        types.iter().map(|ty| self.resolve(ty)).collect()
+    }
+
+    // An unannotated part of a signature is unknown
+    fn annotated(&self, ty: &Option<TypeExpr>) -> Result<Type> {
+        match ty {
+            Some(ty) => self.resolve(ty),
+            None => Ok(Type::Unknown),
+        }
+    }
+
+    // The parameter and return types of a function, `self` is of the `impl` type
+    fn signature(&self, decl: &FnDecl, receiver: Option<&Type>) -> Result<(Vec<Type>, Type)> {
+        let mut params = Vec::with_capacity(decl.params.len());
+        for param in &decl.params {
+            params.push(match receiver {
+                Some(receiver) if param.name == "self" => receiver.clone(),
+                _ => self.annotated(&param.ty)?,
+            });
+        }
+        Ok((params, self.annotated(&decl.ret)?))
+    }
+
+    // Types are declared first, as the signatures of the block can name them
+    fn declare(&mut self, stmts: &[Stmt]) -> Result<()> {
+        for stmt in stmts {
+            let (name, ty) = match stmt {
+                Stmt::Struct { decl } => (&decl.name, Type::Struct(Rc::clone(decl), Vec::new())),
+                Stmt::Enum { decl } => (&decl.name, Type::Enum(Rc::clone(decl), Vec::new())),
+                Stmt::Trait { decl } => (&decl.name, Type::Dyn(Rc::clone(decl))),
+                _ => continue,
+            };
+            self.names().types.insert(name.clone(), ty);
+        }
+        for stmt in stmts {
+            match stmt {
+                Stmt::Struct { decl } => {
+                    let fields = self.scoped(|checker| {
+                        checker.generics(&decl.generics)?;
+                        let fields = decl.fields.iter().map(|field| &field.ty);
+                        fields.map(|ty| checker.resolve(ty)).collect()
+                    })?;
+                    let members = Members {
+                        fields,
+                        ..Members::default()
+                    };
+                    self.members.insert(decl.pos.clone(), members);
+                }
+                Stmt::Enum { decl } => {
+                    let variants = self.scoped(|checker| {
+                        checker.generics(&decl.generics)?;
+                        let mut variants = Vec::with_capacity(decl.variants.len());
+                        for variant in &decl.variants {
+                            variants.push(match &variant.payload {
+                                Payload::Unit => Vec::new(),
+                                Payload::Tuple(types) => checker.resolve_all(types)?,
+                                Payload::Struct(fields) => fields
+                                    .iter()
+                                    .map(|field| checker.resolve(&field.ty))
+                                    .collect::<Result<_>>()?,
+                            });
+                        }
+                        Ok(variants)
+                    })?;
+                    let members = Members {
+                        variants,
+                        ..Members::default()
+                    };
+                    self.members.insert(decl.pos.clone(), members);
+                }
+                Stmt::Function { decl } => {
+                    let ty = self.scoped(|checker| {
+                        checker.generics(&decl.generics)?;
+                        let (params, ret) = checker.signature(decl, None)?;
+                        Ok(Type::Function(params, Box::new(ret)))
+                    })?;
+                    self.define_generic(&decl.name, generic_positions(&decl.generics), ty);
+                    self.names()
+                        .functions
+                        .insert(decl.name.clone(), decl.pos.clone());
+                }
+                Stmt::Const { decl } => {
+                    let ty = self.resolve(&decl.ty)?;
+                    self.define(&decl.name, ty);
+                    self.names().consts.insert(decl.name.clone());
+                }
+                Stmt::Trait { decl } => {
+                    let methods = self.scoped(|checker| {
+                        let this = checker.self_param(decl);
+                        let mut methods = HashMap::new();
+                        for method in &decl.methods {
+                            let method = checker.method_signature(&method.decl, &[], &this)?;
+                            methods.insert(method.0, method.1);
+                        }
+                        Ok(methods)
+                    })?;
+                    self.traits.insert(decl.pos.clone(), Trait { methods });
+                }
+                _ => {}
+            }
+        }
+        // After the members, so the methods are not lost when a type is declared
+        for stmt in stmts {
+            let Stmt::Impl {
+                generics,
+                trait_,
+                ty,
+                args,
+                methods,
+                pos,
+            } = stmt
+            else {
+                continue;
+            };
+            let declared = self.scoped(|checker| {
+                checker.generics(generics)?;
+                let Some(receiver) = checker.receiver(ty, args, pos)? else {
+                    return Ok(None);
+                };
+                checker.names().types.insert(self_name(), receiver.clone());
+                let generics = generic_positions(generics);
+                let mut declared = HashMap::new();
+                for decl in methods {
+                    let (name, method) = checker.method_signature(decl, &generics, &receiver)?;
+                    declared.insert(name, method);
+                }
+                Ok(Some((receiver, generics, declared)))
+            })?;
+            let Some((receiver, generics, declared)) = declared else {
+                continue;
+            };
+            let at = declared_at(&receiver).unwrap().clone();
+            match trait_ {
+                Some((name, trait_pos)) => {
+                    let trait_ = self.trait_named(name, trait_pos)?;
+                    let methods =
+                        self.implementation(&trait_, &receiver, &generics, declared, methods, pos)?;
+                    let key = (at, trait_.pos.clone());
+                    if let Some(first) = self.impls.get(&key) {
+                        let (name, ty) = (trait_.name.to_string(), ty.to_string());
+                        return Error::ConflictingImpls(name, ty, first.pos.clone()).throw(pos);
+                    }
+                    if self.imported.contains(&key.0) && self.imported.contains(&key.1) {
+                        return Error::OrphanImpl(trait_.name.to_string(), ty.to_string())
+                            .throw(pos);
+                    }
+                    let implementation = Impl {
+                        trait_,
+                        methods,
+                        pos: pos.clone(),
+                    };
+                    self.impls.insert(key, implementation);
+                }
+                None => self.members.entry(at).or_default().methods.extend(declared),
+            }
+        }
+        Ok(())
+    }
+
+    // The type of a function of an `impl` block or a trait, where `receiver`
+    // is the type of `self` and `generics` the type parameters of the block
+    fn method_signature(
+        &mut self,
+        decl: &FnDecl,
+        generics: &[Position],
+        receiver: &Type,
+    ) -> Result<(AsciiString, Method)> {
+        self.scoped(|checker| {
+            checker.generics(&decl.generics)?;
+            let (params, ret) = checker.signature(decl, Some(receiver))?;
+            let mut generics = generics.to_vec();
+            generics.extend(generic_positions(&decl.generics));
+            let method = Method {
+                generics,
+                params,
+                ret,
+                receiver: decl.is_method(),
+                mutates: decl.is_method() && decl.params[0].mutable,
+                pos: decl.pos.clone(),
+            };
+            Ok((decl.name.clone(), method))
+        })
+    }
+
+    // Checks the methods of an `impl Trait for Type` against the trait and
+    // adds the ones the trait provides
+    fn implementation(
+        &mut self,
+        trait_: &TraitDecl,
+        receiver: &Type,
+        generics: &[Position],
+        mut declared: HashMap<AsciiString, Method>,
+        methods: &[Rc<FnDecl>],
+        pos: &Position,
+    ) -> Result<HashMap<AsciiString, Method>> {
+        let required = self.traits[&trait_.pos].methods.clone();
+        let this = [trait_.pos.clone()];
+        let mut missing = Vec::new();
+        for method in &trait_.methods {
+            let name = &method.decl.name;
+            // `Self` is the type, and the type parameters of the method are its own
+            let expected = &required[name];
+            let own = &expected.generics;
+            let expected_type = substitute(
+                &Type::Function(expected.params.clone(), Box::new(expected.ret.clone())),
+                &this,
+                slice::from_ref(receiver),
+            );
+            match declared.get(name) {
+                Some(found) => {
+                    let decl = methods.iter().find(|decl| &decl.name == name).unwrap();
+                    let params = generic_params(&decl.generics);
+                    let found_type = substitute(
+                        &Type::Function(found.params.clone(), Box::new(found.ret.clone())),
+                        &found.generics[generics.len()..],
+                        &params,
+                    );
+                    let expected_type = substitute(&expected_type, own, &params);
+                    if own.len() != params.len()
+                        || found.receiver != expected.receiver
+                        || !self.unify(&expected_type, &found_type)
+                    {
+                        let (expected, found) = (self.show(&expected_type), self.show(&found_type));
+                        let declared = Some(method.decl.pos.clone());
+                        return Error::MismatchedTypes(expected, found, declared).throw(&decl.pos);
+                    }
+                }
+                None if method.provided => {
+                    let Type::Function(params, ret) = expected_type else {
+                        unreachable!()
+                    };
+                    let mut all = generics.to_vec();
+                    all.extend(own.iter().cloned());
+                    let method = Method {
+                        generics: all,
+                        params,
+                        ret: *ret,
+                        receiver: expected.receiver,
+                        mutates: expected.mutates,
+                        pos: expected.pos.clone(),
+                    };
+                    declared.insert(name.clone(), method);
+                }
+                None => missing.push(name.to_string()),
+            }
+        }
+        if let Some(decl) = methods
+            .iter()
+            .find(|decl| trait_.method(&decl.name).is_none())
+        {
+            let name = trait_.name.to_string();
+            return Error::NotTraitMethod(decl.name.to_string(), name).throw(&decl.pos);
+        }
+        if !missing.is_empty() {
+            let (name, ty) = (trait_.name.to_string(), self.show(receiver));
+            return Error::MissingMethods(missing, name, ty).throw(pos);
+        }
+        Ok(declared)
+    }
+
+    // The type an `impl` block adds methods to, the resolver reports unknown types
+    fn receiver(
+        &self,
+        name: &AsciiString,
+        args: &[TypeExpr],
+        pos: &Position,
+    ) -> Result<Option<Type>> {
+        let Some(ty) = self
+            .lookup_type(name)
+            .filter(|ty| declared_at(ty).is_some())
+        else {
+            return Ok(None);
+        };
+        let expected = declared_generics(&ty).len();
+        if args.len() != expected {
+            return Error::TypeArguments(name.to_string(), expected, args.len()).throw(pos);
+        }
+        Ok(Some(with_args(ty, self.resolve_all(args)?)))
+    }
+
+    // A block diverges when one of its statements does
+    fn stmts(&mut self, stmts: &[Stmt]) -> Result<Type> {
+        self.declare(stmts)?;
+        let mut ty = Type::Unit;
+        for stmt in stmts {
+            if let Type::Never = self.stmt(stmt)? {
+                ty = Type::Never;
+            }
+        }
+        Ok(ty)
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> Result<Type> {
+        match stmt {
+            Stmt::Expr { expresion } => return self.expr(expresion),
+            Stmt::Function { decl } => {
+                self.function(decl, None)?;
+            }
+            Stmt::Const { decl } => {
+                let expected = self.resolve(&decl.ty)?;
+                let found = self.expr(&decl.value)?;
+                let declared = Some(decl.ty.pos());
+                self.expect(&expected, &found, value_pos(&decl.value), declared)?;
+            }
+            Stmt::Impl {
+                generics,
+                ty,
+                args,
+                methods,
+                pos,
+                ..
+            } => self.scoped(|checker| {
+                checker.generics(generics)?;
+                let receiver = checker.receiver(ty, args, pos)?.unwrap_or(Type::Unknown);
+                checker.names().types.insert(self_name(), receiver.clone());
+                for method in methods {
+                    checker.function(method, Some(&receiver))?;
+                }
+                Ok(())
+            })?,
+            // The methods a trait provides are checked for any type implementing it
+            Stmt::Trait { decl } => self.scoped(|checker| {
+                let this = checker.self_param(decl);
+                for method in decl.methods.iter().filter(|method| method.provided) {
+                    checker.function(&method.decl, Some(&this))?;
+                }
+                Ok(())
+            })?,
+            // `let x: T;` is assigned later, which is checked against `T`
+            Stmt::Let {
+                pattern, ty, value, ..
+            } => {
+                let found = match value {
+                    Some(value) => Some((self.expr(value)?, value)),
+                    None => None,
+                };
+                let ty = match (ty, found) {
+                    (Some(annotation), found) => {
+                        let expected = self.resolve(annotation)?;
+                        if let Some((found, value)) = found {
+                            let declared = Some(annotation.pos());
+                            self.expect(&expected, &found, value_pos(value), declared)?;
+                        }
+                        expected
+                    }
+                    // A binding of `?` is an optional of what is assigned to it later
+                    (None, Some((found, _))) => match self.shallow(&found) {
+                        Type::Null => Type::Optional(Box::new(self.fresh(Kind::Any))),
+                        _ => found,
+                    },
+                    (None, None) => self.fresh(Kind::Any),
+                };
+                self.bind(pattern, &ty)?;
+            }
+            Stmt::Struct { .. } | Stmt::Enum { .. } | Stmt::Use { .. } => {}
+        }
+        Ok(Type::Unit)
+    }
+
+    // Inside of a generic function its type parameters are types of their own
+    fn function(&mut self, decl: &FnDecl, receiver: Option<&Type>) -> Result<Type> {
+        self.scoped(|checker| {
+            checker.generics(&decl.generics)?;
+            let (params, ret) = checker.signature(decl, receiver)?;
+            checker.body(decl, params, ret)
+        })
+    }
+
+    // Unlike functions, closures are typed by how they are used
+    fn closure(&mut self, decl: &FnDecl) -> Result<Type> {
+        let mut params = Vec::with_capacity(decl.params.len());
+        for param in &decl.params {
+            params.push(match &param.ty {
+                Some(ty) => self.resolve(ty)?,
+                None => self.fresh(Kind::Any),
+            });
+        }
+        let ret = match &decl.ret {
+            Some(ret) => self.resolve(ret)?,
+            None => self.fresh(Kind::Any),
+        };
+        self.body(decl, params, ret)
+    }
+
+    fn body(&mut self, decl: &FnDecl, params: Vec<Type>, ret: Type) -> Result<Type> {
+        let declared = decl.ret.as_ref().map(|ret| ret.pos().clone());
+        self.returns.push((ret.clone(), declared.clone()));
+        let loops = mem::take(&mut self.loops);
+        let body = self.scoped(|checker| {
+            for (param, ty) in decl.params.iter().zip(&params) {
+                checker.define(&param.name, ty.clone());
+            }
+            checker.expr(&decl.body)
+        });
+        self.loops = loops;
+        self.returns.pop();
+        self.expect(&ret, &body?, value_pos(&decl.body), declared.as_ref())?;
+        Ok(Type::Function(params, Box::new(ret)))
+    }
+
+    // Declares the bindings of a pattern matching values of type `ty`
+    fn bind(&mut self, pattern: &Pattern, ty: &Type) -> Result<()> {
+        let ty = &self.shallow(ty);
+        match (pattern, ty) {
+            // Null matches none of the shapes, they match the inner value
+            (
+                Pattern::Tuple { .. } | Pattern::Struct { .. } | Pattern::Variant { .. },
+                Type::Optional(inner),
+            ) => return self.bind(pattern, inner),
+            (Pattern::Struct { path, pos, .. } | Pattern::Variant { path, pos, .. }, _) => {
+                let declared = self.lookup_type(&path[0]).unwrap_or(Type::Unknown);
+                let found = self.instance(declared);
+                if !self.unify(ty, &found) {
+                    let (expected, found) = (self.show(ty), self.show(&found));
+                    return Error::MismatchedPatterns(expected, found).throw(pos);
+                }
+            }
+            (Pattern::Tuple { elements, pos }, _) if !self.unknown(ty) => {
+                let found = Type::Tuple(vec![Type::Unknown; elements.len()]);
+                if !matches!(ty, Type::Tuple(types) if types.len() == elements.len()) {
+                    let (expected, found) = (self.show(ty), self.show(&found));
+                    return Error::MismatchedPatterns(expected, found).throw(pos);
+                }
+            }
+            _ => {}
+        }
+        let ty = &self.shallow(ty);
+        let (patterns, types): (Vec<&Pattern>, Vec<Type>) = match (pattern, ty) {
+            (
+                Pattern::Binding {
+                    name, mutable, pos, ..
+                },
+                ty,
+            ) => {
+                match self.constant(name).filter(|_| !mutable) {
+                    Some(found) if !self.unify(ty, &found) => {
+                        let (expected, found) = (self.show(ty), self.show(&found));
+                        return Error::MismatchedPatterns(expected, found).throw(pos);
+                    }
+                    Some(_) => {}
+                    None => self.define(name, ty.clone()),
+                }
+                return Ok(());
+            }
+            (Pattern::Tuple { elements, .. }, Type::Tuple(types))
+                if elements.len() == types.len() =>
+            {
+                (elements.iter().collect(), types.clone())
+            }
+            (Pattern::Tuple { elements, .. }, Type::Var(_)) if self.kind(ty) == Some(Kind::Any) => {
+                let types = elements
+                    .iter()
+                    .map(|_| self.fresh(Kind::Any))
+                    .collect::<Vec<_>>();
+                self.unify(ty, &Type::Tuple(types.clone()));
+                (elements.iter().collect(), types)
+            }
+            (Pattern::Struct { path, fields, .. }, Type::Struct(decl, _)) if path.len() == 1 => {
+                let members = self.fields(ty).unwrap_or_default();
+                fields
+                    .iter()
+                    .map(|(name, pattern)| {
+                        let index = decl.fields.iter().position(|f| &f.name == name);
+                        let ty = index.and_then(|index| members.get(index));
+                        (pattern, ty.cloned().unwrap_or(Type::Unknown))
+                    })
+                    .unzip()
+            }
+            (Pattern::Struct { path, fields, .. }, Type::Enum(decl, _)) if path.len() == 2 => {
+                let variant = decl.variant(&path[1]);
+                let payload = variant.and_then(|(index, _)| self.payload(ty, index));
+                let names = match variant.map(|(_, variant)| &variant.payload) {
+                    Some(Payload::Struct(declared)) => declared.as_slice(),
+                    _ => &[],
+                };
+                fields
+                    .iter()
+                    .map(|(name, pattern)| {
+                        let index = names.iter().position(|f| &f.name == name);
+                        let ty = index.and_then(|index| payload.as_ref()?.get(index).cloned());
+                        (pattern, ty.unwrap_or(Type::Unknown))
+                    })
+                    .unzip()
+            }
+            (Pattern::Variant { path, elements, .. }, Type::Enum(decl, _)) if path.len() == 2 => {
+                let payload = decl
+                    .variant(&path[1])
+                    .and_then(|(index, _)| self.payload(ty, index))
+                    .filter(|payload| payload.len() == elements.len());
+                match payload {
+                    Some(payload) => (elements.iter().collect(), payload),
+                    None => (
+                        elements.iter().collect(),
+                        vec![Type::Unknown; elements.len()],
+                    ),
+                }
+            }
+            (Pattern::Tuple { elements, .. } | Pattern::Variant { elements, .. }, _) => (
+                elements.iter().collect(),
+                vec![Type::Unknown; elements.len()],
+            ),
+            (Pattern::Struct { fields, .. }, _) => fields
+                .iter()
+                .map(|(_, pattern)| (pattern, Type::Unknown))
+                .unzip(),
+            (Pattern::Literal { value, pos }, _) => return self.pattern_literal(value, ty, pos),
+            (
+                Pattern::Range {
+                    start, end, pos, ..
+                },
+                _,
+            ) => {
+                for value in [start, end].into_iter().flatten() {
+                    self.pattern_literal(value, ty, pos)?;
+                }
+                return Ok(());
+            }
+            (Pattern::Path { .. } | Pattern::Wildcard { .. }, _) => return Ok(()),
+        };
+        for (pattern, ty) in patterns.into_iter().zip(&types) {
+            self.bind(pattern, ty)?;
+        }
+        Ok(())
+    }
+
+    // The literals of patterns are of the type of the matched value
+    fn pattern_literal(&mut self, value: &Literal, ty: &Type, pos: &Position) -> Result<()> {
+        let found = self.literal(value, pos);
+        if self.unify(ty, &found) {
+            return Ok(());
+        }
+        Error::MismatchedPatterns(self.show(ty), self.show(&found)).throw(pos)
+    }
+
+    fn literal(&mut self, value: &Literal, pos: &Position) -> Type {
+        match value {
+            Literal::Null => Type::Null,
+            Literal::Char(_) => Type::Char,
+            Literal::Boolean(_) => Type::Bool,
+            Literal::Number(number) => Type::Number(NumberType::of(number)),
+            Literal::Unsuffixed(number) => {
+                let kind = if number.is_float() {
+                    Kind::Float
+                } else {
+                    Kind::Integer
+                };
+                let ty = self.fresh(kind);
+                self.literals
+                    .push((number.clone(), ty.clone(), pos.clone()));
+                ty
+            }
+            Literal::String(_) => Type::String,
+        }
+    }
+
+    fn exprs(&mut self, exprs: &[Expr]) -> Result<Vec<Type>> {
+        exprs.iter().map(|expr| self.expr(expr)).collect()
+    }
+
+    // Conditions must be `bool`, there is no truthiness
+    fn condition(&mut self, condition: &Expr) -> Result<()> {
+        let ty = self.expr(condition)?;
+        if self.unify(&Type::Bool, &ty) {
+            return Ok(());
+        }
+        Error::InvalidCondition(self.show(&ty)).throw(value_pos(condition))
+    }
+
+    // The elements of a collection share a type, which the first known one decides
+    fn element(&mut self, expected: Type, element: &Expr) -> Result<Type> {
+        let found = self.expr(element)?;
+        match self.common(&expected, &found) {
+            Some(ty) => Ok(ty),
+            None => Error::MismatchedTypes(self.show(&expected), self.show(&found), None)
+                .throw(value_pos(element)),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<Type> {
+        Ok(match expr {
+            Expr::Array { elements, .. } => {
+                let mut ty = self.fresh(Kind::Any);
+                for element in elements {
+                    ty = self.element(ty, element)?;
+                }
+                Type::Array(Box::new(ty), false)
+            }
+            Expr::Assign { target, value, .. } => {
+                let expected = self.expr(target)?;
+                let found = self.expr(value)?;
+                self.expect(&expected, &found, value_pos(value), None)?;
+                found
+            }
+            Expr::Binary {
+                left,
+                operator,
+                right,
+                pos,
+            } => {
+                let left_type = self.expr(left)?;
+                let right_type = self.expr(right)?;
+                let overloaded = match prelude::binary(*operator) {
+                    Some(overload) => self.overloaded(
+                        &operator.to_string(),
+                        &left_type,
+                        overload,
+                        slice::from_ref(right),
+                        slice::from_ref(&right_type),
+                        pos,
+                    )?,
+                    None => None,
+                };
+                match overloaded {
+                    Some(ty) => ty,
+                    None => self.binary(&left_type, *operator, &right_type, pos)?,
+                }
+            }
+            Expr::Block { stmts, tail, .. } => self.scoped(|checker| {
+                let ty = checker.stmts(stmts)?;
+                match tail {
+                    Some(tail) => checker.expr(tail),
+                    None => Ok(ty),
+                }
+            })?,
+            Expr::Break { label, value, pos } => {
+                let found = match value {
+                    Some(value) => self.expr(value)?,
+                    None => Type::Unit,
+                };
+                let pos = value.as_deref().map_or(pos, value_pos);
+                // Unlabeled breaks leave the innermost loop
+                let target = self
+                    .loops
+                    .iter()
+                    .rposition(|target| label.is_none() || &target.label == label);
+                if let Some(target) = target {
+                    let value = match self.loops[target].value.clone() {
+                        None => (found, pos.clone()),
+                        Some((expected, first)) => match self.common(&expected, &found) {
+                            Some(ty) => (ty, first),
+                            None => Error::MismatchedTypes(
+                                self.show(&expected),
+                                self.show(&found),
+                                Some(first),
+                            )
+                            .throw(pos)?,
+                        },
+                    };
+                    self.loops[target].value = Some(value);
+                }
+                Type::Never
+            }
+            Expr::Call {
+                callee,
+                arguments,
+                pos,
+            } => {
+                let callee_type = self.expr(callee)?;
+                let found = self.exprs(arguments)?;
+                match self.shallow(&callee_type) {
+                    Type::Function(params, ret) => {
+                        let name = callee_name(callee);
+                        let declared = match &**callee {
+                            Expr::Variable { name, .. } => self.function_decl(name),
+                            _ => None,
+                        };
+                        let declared = declared.as_ref();
+                        self.arguments_match(&name, &params, arguments, &found, pos, declared)?;
+                        *ret
+                    }
+                    Type::Unknown | Type::Never | Type::Var(_) => Type::Unknown,
+                    ty => Error::NotCallable(self.show(&ty)).throw(pos)?,
+                }
+            }
+            Expr::Closure { decl, .. } => self.closure(decl)?,
+            Expr::Continue { .. } => Type::Never,
+            Expr::Field {
+                object,
+                name,
+                safe,
+                pos,
+            } => {
+                let object = self.expr(object)?;
+                let (ty, optional) = unwrap_safe(self.shallow(&object), *safe);
+                let field = match &ty {
+                    Type::Struct(decl, _) => {
+                        let index = decl.fields.iter().position(|field| &field.name == name);
+                        let fields = self.fields(&ty);
+                        match index.and_then(|index| fields.as_ref()?.get(index).cloned()) {
+                            Some(field) => field,
+                            None if index.is_some() => Type::Unknown,
+                            None => {
+                                Error::UnknownField(name.to_string(), ty.to_string()).throw(pos)?
+                            }
+                        }
+                    }
+                    Type::Unknown | Type::Never | Type::Var(_) => Type::Unknown,
+                    ty => Error::UnknownField(name.to_string(), self.show(ty)).throw(pos)?,
+                };
+                wrap_safe(field, optional)
+            }
+            Expr::Group { expresion } => self.expr(expresion)?,
+            // Numbers cast to each other, `bool` and `char` to integers and
+            // `u8` to `char`
+            Expr::Cast { expresion, ty, pos } => {
+                let from = self.expr(expresion)?;
+                let to = self.resolve(ty)?;
+                let castable = match &to {
+                    Type::Number(number) => {
+                        self.numeric(&from)
+                            || self.unknown(&from)
+                            || !number.is_float()
+                                && matches!(self.shallow(&from), Type::Bool | Type::Char)
+                    }
+                    Type::Char => self.unify(&Type::Number(NumberType::U8), &from),
+                    _ => false,
+                };
+                if !castable {
+                    return Error::InvalidCast(self.show(&from), self.show(&to)).throw(pos);
+                }
+                if let Type::Number(number) = to {
+                    self.numeric_casts.push((from, number, pos.clone()));
+                }
+                to
+            }
+            // `max::<u8>` or `Pair::<i32, bool> { .. }`
+            Expr::TypeArgs {
+                expresion,
+                args,
+                pos,
+            } => {
+                let args = self.resolve_all(args)?;
+                let (ty, generics) = match &**expresion {
+                    Expr::Struct { path, fields, .. } => {
+                        return self.struct_literal(path, fields, Some((&args, pos)))
+                    }
+                    Expr::Variable { name, .. } => {
+                        self.scheme(name).unwrap_or((Type::Unknown, Vec::new()))
+                    }
+                    Expr::Path { segments, pos } => self.path_scheme(segments, pos)?,
+                    expr => (self.expr(expr)?, Vec::new()),
+                };
+                if !matches!(ty, Type::Unknown) && generics.len() != args.len() {
+                    let name = callee_name(expresion);
+                    return Error::TypeArguments(name, generics.len(), args.len()).throw(pos);
+                }
+                self.apply(&ty, &generics, &args, pos)
+            }
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                pos,
+            } => {
+                self.condition(condition)?;
+                let then_type = self.expr(then_branch)?;
+                let else_type = match else_branch {
+                    Some(branch) => self.expr(branch)?,
+                    None => Type::Unit,
+                };
+                match self.common(&then_type, &else_type) {
+                    Some(ty) => ty,
+                    None => {
+                        let else_pos = else_branch.as_deref().map_or(pos, value_pos);
+                        Error::IncompatibleBranches(
+                            self.show(&then_type),
+                            self.show(&else_type),
+                            value_pos(then_branch).clone(),
+                        )
+                        .throw(else_pos)?
+                    }
+                }
+            }
+            Expr::Index { object, index, pos } => {
+                let object = self.expr(object)?;
+                let index_type = self.expr(index)?;
+                let arguments = slice::from_ref(&**index);
+                let found = slice::from_ref(&index_type);
+                match self.overloaded("[]", &object, prelude::INDEX, arguments, found, pos)? {
+                    Some(ty) => ty,
+                    None => self.index_type(&object, &index_type, pos)?,
+                }
+            }
+            Expr::Literal { value, pos } => self.literal(value, pos),
+            Expr::Logical {
+                left,
+                operator: Operator::Coalesce,
+                right,
+                ..
+            } => {
+                let left = self.expr(left)?;
+                let right_type = self.expr(right)?;
+                match self.shallow(&left) {
+                    Type::Optional(inner) => match self.common(&inner, &right_type) {
+                        Some(ty) => ty,
+                        None => {
+                            let (expected, found) = (self.show(&inner), self.show(&right_type));
+                            Error::MismatchedTypes(expected, found, None).throw(value_pos(right))?
+                        }
+                    },
+                    Type::Null => right_type,
+                    Type::Unknown | Type::Never | Type::Var(_) => Type::Unknown,
+                    // The right side never runs
+                    left => left,
+                }
+            }
+            Expr::Logical {
+                left,
+                operator,
+                right,
+                pos,
+            } => {
+                for operand in [left, right] {
+                    let ty = self.expr(operand)?;
+                    if !self.unify(&Type::Bool, &ty) {
+                        Error::InvalidOperand(operator.to_string(), self.show(&ty)).throw(pos)?
+                    }
+                }
+                Type::Bool
+            }
+            // A loop no `break` leaves never ends
+            Expr::Loop { label, body, .. } => {
+                self.loops.push(Loop {
+                    label: label.clone(),
+                    value: None,
+                });
+                let result = self.expr(body);
+                let value = self.loops.pop().unwrap().value;
+                result?;
+                value.map_or(Type::Never, |(ty, _)| ty)
+            }
+            Expr::LoopIn {
+                label,
+                pattern,
+                iterable,
+                body,
+                pos,
+            } => {
+                let ty = self.expr(iterable)?;
+                let item = match self.item_type(&ty) {
+                    Some(item) => Some(item),
+                    None => self.next_item(&ty, iterable.pos())?,
+                };
+                let Some(item) = item else {
+                    return Error::NotIterable(self.show(&ty)).throw(iterable.pos());
+                };
+                // It ends once the items run out, without a value
+                self.loops.push(Loop {
+                    label: label.clone(),
+                    value: Some((Type::Unit, pos.clone())),
+                });
+                let result = self.scoped(|checker| {
+                    checker.bind(pattern, &item)?;
+                    checker.expr(body)
+                });
+                self.loops.pop();
+                result?;
+                Type::Unit
+            }
+            Expr::Map { entries, .. } => {
+                let (mut key, mut value) = (self.fresh(Kind::Any), self.fresh(Kind::Any));
+                for (k, v) in entries {
+                    key = self.element(key, k)?;
+                    value = self.element(value, v)?;
+                }
+                Type::Map(Box::new(key), Box::new(value))
+            }
+            Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                let ty = self.expr(scrutinee)?;
+                let mut result: Option<(Type, &Position)> = None;
+                for arm in arms {
+                    let body = self.scoped(|checker| {
+                        checker.bind(&arm.pattern, &ty)?;
+                        if let Some(guard) = &arm.guard {
+                            checker.condition(guard)?;
+                        }
+                        checker.expr(&arm.body)
+                    })?;
+                    let pos = value_pos(&arm.body);
+                    result = Some(match result {
+                        None => (body, pos),
+                        Some((expected, first)) => match self.common(&expected, &body) {
+                            Some(ty) => (ty, first),
+                            None => Error::MismatchedTypes(
+                                self.show(&expected),
+                                self.show(&body),
+                                Some(first.clone()),
+                            )
+                            .throw(pos)?,
+                        },
+                    });
+                }
+                // Without arms no value is produced
+                result.map_or(Type::Never, |(ty, _)| ty)
+            }
+            Expr::Method {
+                receiver,
+                name,
+                arguments,
+                safe,
+                pos,
+            } => {
+                let receiver = self.expr(receiver)?;
+                let (receiver, optional) = unwrap_safe(self.shallow(&receiver), *safe);
+                let found = self.exprs(arguments)?;
+                let ret = self.method(&receiver, name, arguments, &found, pos)?;
+                wrap_safe(ret, optional)
+            }
+            Expr::Path { segments, pos } => {
+                let (ty, generics) = self.path_scheme(segments, pos)?;
+                self.instantiate(&ty, &generics, pos)
+            }
+            Expr::Range {
+                start,
+                end,
+                inclusive,
+                pos,
+            } => {
+                let operator = if *inclusive { "..=" } else { ".." };
+                // Both bounds are integers of the same type
+                let bound = self.fresh(Kind::Integer);
+                let mut first: Option<Type> = None;
+                for value in [start, end].into_iter().flatten() {
+                    let ty = self.expr(value)?;
+                    // Integers can be decimals, but decimals do not count
+                    let decimal = matches!(self.shallow(&ty), Type::Number(NumberType::Decimal));
+                    if !decimal && self.unify(&bound, &ty) {
+                        first.get_or_insert(ty);
+                        continue;
+                    }
+                    match &first {
+                        Some(first) if self.integer(&ty) => Error::InvalidOperation(
+                            operator.to_string(),
+                            self.show(first),
+                            self.show(&ty),
+                        )
+                        .throw(pos)?,
+                        _ => Error::InvalidOperand(operator.to_string(), self.show(&ty))
+                            .throw(pos)?,
+                    }
+                }
+                match first {
+                    Some(_) => Type::Range(Box::new(bound)),
+                    None => Type::Range(Box::new(Type::Unknown)),
+                }
+            }
+            Expr::Return { value, pos } => {
+                let found = match value {
+                    Some(value) => self.expr(value)?,
+                    None => Type::Unit,
+                };
+                if let Some((expected, declared)) = self.returns.last().cloned() {
+                    let pos = value.as_deref().map_or(pos, value_pos);
+                    self.expect(&expected, &found, pos, declared.as_ref())?;
+                }
+                Type::Never
+            }
+            Expr::Struct { path, fields, .. } => self.struct_literal(path, fields, None)?,
+            Expr::Tuple { elements, .. } if elements.is_empty() => Type::Unit,
+            Expr::Tuple { elements, .. } => Type::Tuple(self.exprs(elements)?),
+            Expr::TupleIndex {
+                object,
+                index,
+                safe,
+                pos,
+            } => {
+                let object = self.expr(object)?;
+                let (ty, optional) = unwrap_safe(self.shallow(&object), *safe);
+                let element = match &ty {
+                    Type::Tuple(elements) => match elements.get(*index) {
+                        Some(element) => element.clone(),
+                        None => {
+                            Error::IndexOutOfBounds(index.to_string(), elements.len()).throw(pos)?
+                        }
+                    },
+                    Type::Unknown | Type::Never | Type::Var(_) => Type::Unknown,
+                    ty => Error::UnknownField(index.to_string(), self.show(ty)).throw(pos)?,
+                };
+                wrap_safe(element, optional)
+            }
+            Expr::Unary {
+                operator,
+                expresion: Side::Left(operand) | Side::Right(operand),
+                pos,
+            } => {
+                let ty = self.expr(operand)?;
+                if *operator == Operator::Minus {
+                    if let Some(ty) = self.overloaded("-", &ty, prelude::NEG, &[], &[], pos)? {
+                        return Ok(ty);
+                    }
+                }
+                let numeric = self.numeric(&ty);
+                match (operator, self.shallow(&ty)) {
+                    (Operator::Exclamation, _) if self.unify(&Type::Bool, &ty) => Type::Bool,
+                    _ if self.unknown(&ty) => Type::Unknown,
+                    (Operator::Minus, Type::Number(number)) if !number.is_unsigned() => ty,
+                    // Checked once the type of the number is inferred
+                    (Operator::Minus, Type::Var(_)) if numeric => {
+                        self.negated.push((ty.clone(), pos.clone()));
+                        ty
+                    }
+                    (Operator::Increment | Operator::Decrement, _) if numeric => ty,
+                    _ => Error::InvalidOperand(operator.to_string(), self.show(&ty)).throw(pos)?,
+                }
+            }
+            Expr::Variable { name, pos } => self.lookup(name, pos),
+        })
+    }
+
+    // `Shape::Circle` or `Point::new` and the type parameters they are generic
+    // over, anything else is reported by the interpreter
+    fn path_scheme(
+        &mut self,
+        segments: &[AsciiString],
+        pos: &Position,
+    ) -> Result<(Type, Vec<Position>)> {
+        let unknown = || Ok((Type::Unknown, Vec::new()));
+        let [ty, name] = segments else {
+            return unknown();
+        };
+        let Some(ty) = self.lookup_type(ty) else {
+            return unknown();
+        };
+        let Some(members) = self.members(&ty) else {
+            return unknown();
+        };
+        if let Type::Enum(decl, _) = &ty {
+            if let Some((index, variant)) = decl.variant(name) {
+                let generics = generic_positions(&decl.generics);
+                let ty = with_args(ty.clone(), generic_params(&decl.generics));
+                return match variant.payload {
+                    Payload::Unit => Ok((ty, generics)),
+                    Payload::Tuple(_) => {
+                        let payload = members.variants[index].clone();
+                        Ok((Type::Function(payload, Box::new(ty)), generics))
+                    }
+                    Payload::Struct(_) => unknown(),
+                };
+            }
+        }
+        match self.find_method(&ty, name, pos)? {
+            Some(method) => Ok((
+                Type::Function(method.params, Box::new(method.ret)),
+                method.generics,
+            )),
+            None => unknown(),
+        }
+    }
+
+    // `Point { .. }` or `Shape::Rect { .. }`, unknown and missing fields are
+    // reported by the interpreter
+    fn struct_literal(
+        &mut self,
+        path: &[AsciiString],
+        fields: &[(AsciiString, Expr)],
+        explicit: Option<(&[Type], &Position)>,
+    ) -> Result<Type> {
+        let declared = self.lookup_type(&path[0]).unwrap_or(Type::Unknown);
+        let ty = match explicit {
+            Some((args, pos)) if declared_at(&declared).is_some() => {
+                let expected = declared_generics(&declared).len();
+                if args.len() != expected {
+                    let name = path_name(path);
+                    return Error::TypeArguments(name, expected, args.len()).throw(pos);
+                }
+                with_args(declared, args.to_vec())
+            }
+            _ => self.instance(declared),
+        };
+        let declared = match (&ty, path) {
+            (Type::Struct(decl, _), [_]) => self
+                .fields(&ty)
+                .map(|types| (decl.fields.as_slice(), types)),
+            (Type::Enum(decl, _), [_, variant]) => match decl.variant(variant) {
+                Some((index, variant)) => match &variant.payload {
+                    Payload::Struct(declared) => self
+                        .payload(&ty, index)
+                        .map(|types| (declared.as_slice(), types)),
+                    _ => None,
+                },
+                None => None,
+            },
+            _ => None,
+        };
+        let Some((declared, types)) = declared else {
+            for (_, value) in fields {
+                self.expr(value)?;
+            }
+            return Ok(Type::Unknown);
+        };
+        for (name, value) in fields {
+            let found = self.expr(value)?;
+            if let Some(index) = declared.iter().position(|field| &field.name == name) {
+                let annotation = Some(declared[index].ty.pos());
+                self.expect(&types[index], &found, value_pos(value), annotation)?;
+            }
+        }
+        Ok(ty)
+    }
+
+    // Methods of structs and enums come from their `impl` blocks, the ones of
+    // collections and strings are builtin. Implementations are known by where
+    // the type is declared, whether or not the program names it.
+    fn method(
+        &mut self,
+        receiver: &Type,
+        name: &AsciiString,
+        arguments: &[Expr],
+        found: &[Type],
+        pos: &Position,
+    ) -> Result<Type> {
+        if declared_at(receiver).is_some() || matches!(receiver, Type::Param(..) | Type::Dyn(_)) {
+            return match self.find_method(receiver, name, pos)? {
+                Some(method) if method.receiver => {
+                    if method.mutates {
+                        self.mutating.insert(pos.clone());
+                    }
+                    self.call_method(method, receiver, name, arguments, found, pos)
+                }
+                _ => Error::UnknownMethod(name.to_string(), self.show(receiver)).throw(pos),
+            };
+        }
+        let Some((params, ret)) = self.builtin_method(receiver, name.as_str()) else {
+            return match receiver {
+                Type::Unknown | Type::Never | Type::Var(_) => Ok(Type::Unknown),
+                _ => Error::UnknownMethod(name.to_string(), self.show(receiver)).throw(pos),
+            };
+        };
+        if matches!(name.as_str(), "push" | "pop" | "insert" | "remove") {
+            if let Type::Array(_, true) = receiver {
+                return Error::FixedLength(name.to_string()).throw(pos);
+            }
+            self.mutating.insert(pos.clone());
+        }
+        self.arguments_match(name.as_str(), &params, arguments, found, pos, None)?;
+        Ok(ret)
+    }
+
+    fn call_method(
+        &mut self,
+        method: Method,
+        receiver: &Type,
+        name: &AsciiString,
+        arguments: &[Expr],
+        found: &[Type],
+        pos: &Position,
+    ) -> Result<Type> {
+        let ty = Type::Function(method.params, Box::new(method.ret));
+        let Type::Function(params, ret) = self.instantiate(&ty, &method.generics, pos) else {
+            unreachable!("substituting keeps the function")
+        };
+        // Methods of an `impl` for some of the type arguments only
+        if !self.unify(&params[0], receiver) {
+            return Error::UnknownMethod(name.to_string(), self.show(receiver)).throw(pos);
+        }
+        let declared = Some(&method.pos);
+        self.arguments_match(name.as_str(), &params[1..], arguments, found, pos, declared)?;
+        Ok(*ret)
+    }
+
+    // Operators on structs, enums and type parameters call the method of the
+    // standard trait their type implements. Comparisons are `bool` whatever
+    // `eq` and `cmp` return, `None` leaves the operator to the builtin types.
+    // Structs, enums and type parameters without `Eq` are compared like other
+    // values, type parameters need a bound for any other operator.
+    fn overloaded(
+        &mut self,
+        operator: &str,
+        operand: &Type,
+        (trait_, name): Overload,
+        arguments: &[Expr],
+        found: &[Type],
+        pos: &Position,
+    ) -> Result<Option<Type>> {
+        let operand = self.shallow(operand);
+        if !matches!(
+            operand,
+            Type::Struct(..) | Type::Enum(..) | Type::Param(..) | Type::Dyn(_)
+        ) {
+            return Ok(None);
+        }
+        let decl = prelude::trait_decl(trait_);
+        if !self.implements(&operand, &decl) {
+            return match operand {
+                Type::Struct(..) | Type::Enum(..) if trait_ != "Eq" => {
+                    let ty = self.show(&operand);
+                    Error::OperatorNotImplemented(operator.to_string(), ty, trait_.to_string())
+                        .throw(pos)
+                }
+                Type::Param(..) if trait_ != "Eq" => {
+                    let ty = self.show(&operand);
+                    Error::UnboundedOperator(operator.to_string(), ty, trait_.to_string())
+                        .throw(pos)
+                }
+                _ => Ok(None),
+            };
+        }
+        let name = AsciiString::from_ascii(name).unwrap();
+        let method = match declared_at(&operand) {
+            Some(at) => {
+                let key = (at.clone(), decl.pos.clone());
+                self.impls[&key].methods.get(&name).cloned()
+            }
+            None => self.bound_method(&decl, &operand, &name),
+        };
+        let method = method.expect("the trait declares the method");
+        let ty = self.call_method(method, &operand, &name, arguments, found, pos)?;
+        Ok(Some(match trait_ {
+            "Eq" | "Ord" => Type::Bool,
+            _ => ty,
+        }))
+    }
+
+    // A method of a type from its `impl` blocks or the traits it implements.
+    // Type parameters and trait objects have the methods of their traits.
+    fn find_method(
+        &mut self,
+        receiver: &Type,
+        name: &AsciiString,
+        pos: &Position,
+    ) -> Result<Option<Method>> {
+        let bounds = match receiver {
+            Type::Param(_, param) => self.bounds.get(param).cloned().unwrap_or_default(),
+            Type::Dyn(decl) => vec![Rc::clone(decl)],
+            _ => Vec::new(),
+        };
+        if !bounds.is_empty() {
+            let method = bounds
+                .iter()
+                .find_map(|bound| self.bound_method(bound, receiver, name));
+            return Ok(method);
+        }
+        if let Some(method) = self.members(receiver).and_then(|m| m.methods.get(name)) {
+            return Ok(Some(method.clone()));
+        }
+        let Some(at) = declared_at(receiver) else {
+            return Ok(None);
+        };
+        // A method of a trait that is not in scope can not be called
+        let mut hidden = None;
+        for ((ty, _), implementation) in &self.impls {
+            let Some(method) = implementation.methods.get(name).filter(|_| ty == at) else {
+                continue;
+            };
+            if self.in_scope(&implementation.trait_) {
+                let method = method.clone();
+                self.called.insert(implementation.trait_.pos.clone());
+                return Ok(Some(method));
+            }
+            hidden = Some(&implementation.trait_);
+        }
+        match hidden {
+            Some(trait_) => Error::TraitNotImported(
+                name.to_string(),
+                self.show(receiver),
+                trait_.name.to_string(),
+            )
+            .throw(pos),
+            None => Ok(None),
+        }
+    }
+
+    // A method of a trait bounding `receiver`, which takes the place of `Self`
+    fn bound_method(
+        &self,
+        bound: &TraitDecl,
+        receiver: &Type,
+        name: &AsciiString,
+    ) -> Option<Method> {
+        let method = self.traits.get(&bound.pos)?.methods.get(name)?;
+        let this = [bound.pos.clone()];
+        let receiver = slice::from_ref(receiver);
+        Some(Method {
+            generics: method.generics.clone(),
+            params: method
+                .params
+                .iter()
+                .map(|ty| substitute(ty, &this, receiver))
+                .collect(),
+            ret: substitute(&method.ret, &this, receiver),
+            receiver: method.receiver,
+            mutates: method.mutates,
+            pos: method.pos.clone(),
+        })
+    }
+
+    // The items a `loop .. in` gets out of a value of type `ty`, if it is iterable
+    fn item_type(&self, ty: &Type) -> Option<Type> {
+        Some(match self.shallow(ty) {
+            Type::Array(element, _) | Type::Range(element) => *element,
+            Type::String => Type::Char,
+            // Maps yield `(key, value)` tuples
+            Type::Map(key, value) => Type::Tuple(vec![*key, *value]),
+            // Functions are called until they return `?`
+            Type::Function(_, ret) => match self.shallow(&ret) {
+                Type::Optional(item) => *item,
+                _ => Type::Unknown,
+            },
+            Type::Unknown | Type::Never => Type::Unknown,
+            ty if self.unknown(&ty) => Type::Unknown,
+            _ => return None,
+        })
+    }
+
+    // The items of a struct or an enum with a `next(mut self) -> T?` method,
+    // which a `loop .. in` calls until it returns `?`
+    fn next_item(&mut self, ty: &Type, pos: &Position) -> Result<Option<Type>> {
+        let ty = self.shallow(ty);
+        if declared_at(&ty).is_none() {
+            return Ok(None);
+        }
+        let name = AsciiString::from_ascii("next").unwrap();
+        let method = self.find_method(&ty, &name, pos)?;
+        let Some(method) = method.filter(|method| method.receiver && method.params.len() == 1)
+        else {
+            return Ok(None);
+        };
+        let ret = self.call_method(method, &ty, &name, &[], &[], pos)?;
+        Ok(match self.shallow(&ret) {
+            Type::Optional(item) => Some(*item),
+            ret if self.unknown(&ret) => Some(Type::Unknown),
+            _ => None,
+        })
+    }
+
+    // The parameters and the return type of a builtin method, without the
+    // receiver. What `map` and `fold` produce is inferred from their arguments.
+    fn builtin_method(&mut self, receiver: &Type, name: &str) -> Option<(Vec<Type>, Type)> {
+        let usize = Type::Number(NumberType::USize);
+        Some(match (receiver, name) {
+            (Type::Array(element, _), "push") => (vec![(**element).clone()], Type::Unit),
+            (Type::Array(element, _), "pop") => (vec![], Type::optional((**element).clone())),
+            (Type::Array(..) | Type::Map(..) | Type::String, "len") => (vec![], usize),
+            (Type::Map(key, value), "get" | "remove") => {
+                (vec![(**key).clone()], Type::optional((**value).clone()))
+            }
+            (Type::Map(key, value), "insert") => (
+                vec![(**key).clone(), (**value).clone()],
+                Type::optional((**value).clone()),
+            ),
+            (Type::Map(key, _), "contains") => (vec![(**key).clone()], Type::Bool),
+            (Type::Map(key, _), "keys") => (vec![], Type::Array(key.clone(), false)),
+            (Type::Map(_, value), "values") => (vec![], Type::Array(value.clone(), false)),
+            // The methods of the standard traits the type implements, which
+            // take and give values of its type like the operators calling them
+            (_, "add" | "sub" | "mul" | "div" | "rem" | "neg" | "eq" | "cmp") => {
+                let trait_ = match name {
+                    "add" => "Add",
+                    "sub" => "Sub",
+                    "mul" => "Mul",
+                    "div" => "Div",
+                    "rem" => "Rem",
+                    "neg" => "Neg",
+                    "eq" => "Eq",
+                    _ => "Ord",
+                };
+                if !self.builtin_implements(receiver, &prelude::trait_decl(trait_)) {
+                    return None;
+                }
+                let operand = receiver.clone();
+                match name {
+                    "neg" => (vec![], operand),
+                    "eq" => (vec![operand], Type::Bool),
+                    "cmp" => {
+                        let ordering = Type::Enum(prelude::enum_decl("Ordering"), Vec::new());
+                        (vec![operand], ordering)
+                    }
+                    _ => (vec![operand.clone()], operand),
+                }
+            }
+            (Type::Array(..) | Type::Map(..) | Type::String | Type::Range(_), _) => {
+                let item = self.item_type(receiver)?;
+                let function = |params, ret| Type::Function(params, Box::new(ret));
+                match name {
+                    "map" => {
+                        let mapped = self.fresh(Kind::Any);
+                        (
+                            vec![function(vec![item], mapped.clone())],
+                            Type::Array(Box::new(mapped), false),
+                        )
+                    }
+                    "filter" => (
+                        vec![function(vec![item.clone()], Type::Bool)],
+                        Type::Array(Box::new(item), false),
+                    ),
+                    "fold" => {
+                        let folded = self.fresh(Kind::Any);
+                        (
+                            vec![
+                                folded.clone(),
+                                function(vec![folded.clone(), item], folded.clone()),
+                            ],
+                            folded,
+                        )
+                    }
+                    _ => return None,
+                }
+            }
+            // `n.to_u8()` is the number as a `u8` or `?` when it does not fit
+            (_, name) if self.numeric(receiver) => {
+                let to = NumberType::from_name(name.strip_prefix("to_")?)?;
+                (vec![], Type::optional(Type::Number(to)))
+            }
+            _ => return None,
+        })
+    }
+
+    // The type of `left operator right`, mirroring what the interpreter allows
+    fn binary(
+        &mut self,
+        left: &Type,
+        operator: Operator,
+        right: &Type,
+        pos: &Position,
+    ) -> Result<Type> {
+        let arithmetic = matches!(
+            operator,
+            Operator::Plus
+                | Operator::Minus
+                | Operator::Asterisk
+                | Operator::Slash
+                | Operator::Percent
+        );
+        let equality = matches!(operator, Operator::EqualTo | Operator::NotEqualTo);
+        // Numbers only mix with numbers of their type, so an unsuffixed number
+        // takes the type of the other side
+        let null =
+            matches!(self.shallow(left), Type::Null) || matches!(self.shallow(right), Type::Null);
+        let same = !null && self.unify(left, right);
+        let (left, right) = (self.shallow(left), self.shallow(right));
+        // One unknown side leaves the checks to the interpreter
+        if self.unknown(&left) || self.unknown(&right) {
+            let known = if self.unknown(&left) { &right } else { &left };
+            return Ok(match known {
+                _ if !arithmetic => Type::Bool,
+                Type::String => Type::String,
+                known if self.numeric(known) => known.clone(),
+                _ => Type::Unknown,
+            });
+        }
+        let invalid = |checker: &Self| {
+            let (left, right) = (checker.show(&left), checker.show(&right));
+            Error::InvalidOperation(operator.to_string(), left, right).throw(pos)
+        };
+        let numbers = self.numeric(&left) && self.numeric(&right);
+        match (&left, &right) {
+            _ if numbers && !same => invalid(self),
+            _ if numbers && arithmetic => Ok(left.clone()),
+            (Type::String, Type::String) if operator == Operator::Plus => Ok(Type::String),
+            _ if arithmetic => invalid(self),
+            (Type::String, Type::String) | (Type::Char, Type::Char) => Ok(Type::Bool),
+            _ if numbers => Ok(Type::Bool),
+            // Only values that can be mixed are compared, `?` with optionals
+            _ if equality && self.common(&left, &right).is_some() => Ok(Type::Bool),
+            _ => invalid(self),
+        }
+    }
+
+    fn index_type(&mut self, object: &Type, index: &Type, pos: &Position) -> Result<Type> {
+        let object = self.shallow(object);
+        let integer = self.fresh(Kind::Integer);
+        Ok(match (&object, self.shallow(index)) {
+            (Type::Map(key, value), _) => {
+                self.expect(key, index, pos, None)?;
+                (**value).clone()
+            }
+            _ if self.unknown(&object) => Type::Unknown,
+            // A slice of an array has a length of its own
+            (Type::Array(element, _), Type::Range(_)) => Type::Array(element.clone(), false),
+            (Type::String, Type::Range(_)) => object.clone(),
+            (Type::Array(..) | Type::String, _) if self.unknown(index) => Type::Unknown,
+            (Type::Array(element, _), _) if self.unify(&integer, index) => (**element).clone(),
+            (Type::String, _) if self.unify(&integer, index) => Type::Char,
+            _ => {
+                return Error::InvalidOperation(
+                    String::from("[]"),
+                    self.show(&object),
+                    self.show(index),
+                )
+                .throw(pos)
+            }
+        })
+    }
+}
+
+// Where the value of an expression comes from, the tail of a block
+fn value_pos(expr: &Expr) -> &Position {
+    match expr {
+        Expr::Block {
+            tail: Some(tail), ..
+        } => value_pos(tail),
+        Expr::Group { expresion } => value_pos(expresion),
+        expr => expr.pos(),
+    }
+}
+
+fn callee_name(callee: &Expr) -> String {
+    match callee {
+        Expr::Variable { name, .. } => name.to_string(),
+        Expr::Path { segments, .. } => path_name(segments),
+        Expr::Field { name, .. } => name.to_string(),
+        Expr::Group { expresion } | Expr::TypeArgs { expresion, .. } => callee_name(expresion),
+        Expr::Closure { decl, .. } => decl.name.to_string(),
+        _ => String::from("function"),
+    }
+}
+
+fn declared_at(ty: &Type) -> Option<&Position> {
+    match ty {
+        Type::Struct(decl, _) => Some(&decl.pos),
+        Type::Enum(decl, _) => Some(&decl.pos),
+        _ => None,
+    }
+}
+
+// The type parameters a struct or an enum declares
+fn declared_generics(ty: &Type) -> &[Generic] {
+    match ty {
+        Type::Struct(decl, _) => &decl.generics,
+        Type::Enum(decl, _) => &decl.generics,
+        _ => &[],
+    }
+}
+
+// A struct or an enum given its type arguments
+fn with_args(ty: Type, args: Vec<Type>) -> Type {
+    match ty {
+        Type::Struct(decl, _) => Type::Struct(decl, args),
+        Type::Enum(decl, _) => Type::Enum(decl, args),
+        ty => ty,
+    }
+}
+
+fn self_name() -> AsciiString {
+    AsciiString::from_ascii("Self").unwrap()
+}
+
+fn generic_positions(generics: &[Generic]) -> Vec<Position> {
+    generics.iter().map(|generic| generic.pos.clone()).collect()
+}
+
+// A generic declaration inside of itself, given its own type parameters
+fn generic_params(generics: &[Generic]) -> Vec<Type> {
+    generics
+        .iter()
+        .map(|generic| Type::Param(generic.name.clone(), generic.pos.clone()))
+        .collect()
+}
+
+// Replaces the type parameters declared at `generics` with `args`
+fn substitute(ty: &Type, generics: &[Position], args: &[Type]) -> Type {
+    match ty {
+        Type::Param(_, pos) => match generics.iter().position(|generic| generic == pos) {
+            Some(index) => args.get(index).cloned().unwrap_or(Type::Unknown),
+            None => ty.clone(),
+        },
+        ty => ty.map(|ty| substitute(ty, generics, args)),
+    }
+}
+
+// `?.` looks inside optionals and is `?` itself when they are
+fn unwrap_safe(ty: Type, safe: bool) -> (Type, bool) {
+    match ty {
+        Type::Optional(inner) if safe => (*inner, true),
+        Type::Null if safe => (Type::Unknown, true),
+        ty => (ty, false),
+    }
+}
+
+fn wrap_safe(ty: Type, optional: bool) -> Type {
+    if optional {
+        Type::optional(ty)
+    } else {
+        ty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::Error, io::run};
+
+    fn mismatched(source: &str) -> bool {
+        matches!(run(source), Err(Error::MismatchedPatterns(..)))
+    }
+
+    #[test]
+    fn annotations_are_checked_before_the_program_runs() {
+        let mismatched = |source| matches!(run(source), Err(Error::MismatchedTypes(..)));
+        assert!(mismatched("let x: i32 = \"a\";"));
+        assert!(mismatched("fn f(a: i32) -> String { a }"));
+        assert!(mismatched("f(1); fn f(a: String) {}"));
+        // The program would never get to the `let`
+        assert!(mismatched("fn f() { loop {} } f(); let x: i32 = \"a\";"));
+        assert!(matches!(run("if 1 {}"), Err(Error::InvalidCondition(_))));
+        assert!(matches!(run("true + 1"), Err(Error::InvalidOperation(..))));
+        let invalid = |source| matches!(run(source), Err(Error::InvalidOperand(..)));
+        assert!(invalid("-\"a\"") && invalid("!1"));
+        assert!(matches!(
+            run("let x: u8 = 300;"),
+            Err(Error::NumOverFlow(..))
+        ));
+        assert!(matches!(run("let x: Foo = 1;"), Err(Error::UnknownType(_))));
+        let source = "let a: [i32] = [1]; let m: Map<String, i32> = #{\"a\": 1};
+            let f: fn(i32) -> i32 = |x| x; (a, m, f(2))";
+        assert_eq!(run(source).unwrap(), "([1], #{\"a\": 1}, 2)");
+    }
+
+    #[test]
+    fn numbers_take_their_type_from_how_they_are_used() {
+        let overflows =
+            |source, ty: &str| matches!(run(source), Err(Error::NumOverFlow(_, of)) if of == ty);
+        // `x` is a `u8`, so doubling it overflows
+        assert!(overflows("let x = 200; let y: u8 = x; x + x", "u8"));
+        // Only what nothing constrains defaults to `i32`
+        assert!(overflows("let x = 2000000000; x + x", "i32"));
+        assert!(overflows(
+            "let mut v = []; v.push(1u16); v[0] + 65535",
+            "u16"
+        ));
+        assert_eq!(
+            run("let f = |x| x + 1; let y: i64 = f(4000000000); y").unwrap(),
+            "4000000001"
+        );
+        // Fractions are `f64` unless used as another type, here `f32`
+        assert_eq!(run("let x = 0.1; x + 0.2").unwrap(), "0.30000000000000004");
+        assert_eq!(run("let x = 0.1; let y: f32 = x; x + 0.2").unwrap(), "0.3");
+        let mismatched = |source| matches!(run(source), Err(Error::MismatchedTypes(..)));
+        assert!(mismatched("let x = 5; let y: u8 = x; let z: i32 = x;"));
+        assert!(mismatched("let x = 1.5; let y: i32 = x;"));
+        assert!(mismatched("let id = |x| x; (id(1), id(\"a\"))"));
+    }
+
+    #[test]
+    fn generics_take_the_types_they_are_used_with() {
+        let max = "fn max<T: Ord>(a: T, b: T) -> T { if a > b { a } else { b } }";
+        assert_eq!(
+            run(&format!("{max} (max(1, 2), max(\"b\", \"a\"))")).unwrap(),
+            "(2, \"b\")"
+        );
+        let mismatched = run(&format!("{max} max(1, \"a\")"));
+        assert!(matches!(mismatched, Err(Error::MismatchedTypes(..))));
+        // Comparisons are not mistaken for type arguments
+        assert_eq!(
+            run("let (a, b) = (1, 2); (a < b, a > b)").unwrap(),
+            "(true, false)"
+        );
+        let pair = "struct Pair<A, B> { a: A, b: B }";
+        let source = format!("{pair} let p = Pair {{ a: 1, b: \"x\" }}; (p, p.b)");
+        assert_eq!(run(&source).unwrap(), "(Pair { a: 1, b: \"x\" }, \"x\")");
+        let arguments = run(&format!("{pair} let p: Pair<i32> = Pair {{ a: 1, b: 2 }};"));
+        assert!(matches!(arguments, Err(Error::TypeArguments(..))));
+        let mismatched = run(&format!(
+            "{pair} let p: Pair<i32, i32> = Pair {{ a: 1, b: \"a\" }};"
+        ));
+        assert!(matches!(mismatched, Err(Error::MismatchedTypes(..))));
+        let unbounded = run("fn f<T>(a: T) -> T { a + a }");
+        assert!(matches!(unbounded, Err(Error::UnboundedOperator(..))));
+    }
+
+    #[test]
+    fn traits_are_implemented_and_dispatched() {
+        let shapes = "trait Shape { fn area(self) -> f64; fn name(self) -> String { \"shape\" } }
+            struct Sq { s: f64 }
+            struct Circle { r: f64 }
+            impl Shape for Sq {
+                fn area(self) -> f64 { self.s * self.s }
+                fn name(self) -> String { \"square\" }
+            }
+            impl Shape for Circle { fn area(self) -> f64 { self.r * 3.0 } }
+            fn total<T: Shape>(a: T, b: T) -> f64 { a.area() + b.area() }";
+        let source = format!(
+            "{shapes} let mut all: [dyn Shape] = [];
+            all.push(Sq {{ s: 2.0 }}); all.push(Circle {{ r: 1.0 }});
+            (all.map(|s| s.name()), all.map(|s| s.area()), total(Sq {{ s: 1.0 }}, Sq {{ s: 2.0 }}))"
+        );
+        let shown = "([\"square\", \"shape\"], [4.0, 3.0], 5.0)";
+        assert_eq!(run(&source).unwrap(), shown);
+        let t = "trait T { fn f(self); } struct S {}";
+        let missing = run(&format!("{t} impl T for S {{}}"));
+        assert!(matches!(missing, Err(Error::MissingMethods(..))));
+        let other = run(&format!(
+            "{t} impl T for S {{ fn f(self) {{}} fn g(self) {{}} }}"
+        ));
+        assert!(matches!(other, Err(Error::NotTraitMethod(..))));
+        let twice = run(&format!(
+            "{t} impl T for S {{ fn f(self) {{}} }} impl T for S {{ fn f(self) {{}} }}"
+        ));
+        assert!(matches!(twice, Err(Error::ConflictingImpls(..))));
+        assert!(matches!(
+            run(&format!("{t} fn g<X: T>(x: X) {{}} g(1);")),
+            Err(Error::UnsatisfiedBound(..))
+        ));
+        assert!(matches!(
+            run("struct S {} impl Nope for S {}"),
+            Err(Error::UnknownTrait(_))
+        ));
+        let orphan =
+            run("impl Ord for Ordering { fn cmp(self, other: Ordering) -> Ordering { self } }");
+        assert!(matches!(orphan, Err(Error::OrphanImpl(..))));
+    }
+
+    #[test]
+    fn builtin_types_have_the_methods_of_their_standard_traits() {
+        let source =
+            "let x: i32 = 1; (x.cmp(2), x.add(2), x.neg(), \"a\".add(\"b\"), true.eq(false))";
+        assert_eq!(
+            run(source).unwrap(),
+            "(Ordering::Less, 3, -1, \"ab\", false)"
+        );
+        let unknown = |source| matches!(run(source), Err(Error::UnknownMethod(..)));
+        assert!(unknown("true.cmp(false)") && unknown("1u8.neg()"));
+        assert!(matches!(
+            run("1i32.add(\"a\")"),
+            Err(Error::MismatchedTypes(..))
+        ));
+    }
+
+    #[test]
+    fn patterns_have_the_shape_of_the_value() {
+        assert!(mismatched("let (a, b) = 5;"));
+        assert!(mismatched("let (a, b, c) = (1, 2);"));
+        assert!(mismatched("match 5 { (a, b) => {} }"));
+        assert!(mismatched("struct P { x: i32 } match 5 { P { x } => {} }"));
+        assert!(mismatched(
+            "struct P { x: i32 } struct Q { x: i32 } match (P { x: 1 }) { Q { x } => {} }"
+        ));
+        assert!(mismatched(
+            "enum E { A(i32) } match 5 { E::A(x) => {}, _ => {} }"
+        ));
+        assert!(run("let (a, b) = (1, 2); let [c] = [a + b];").is_err());
+        assert!(run("let (a, b) = (1, 2); a + b").is_ok());
+        assert!(run("struct P { x: i32 } let P { x } = P { x: 1 }; x").is_ok());
+    }
+
+    #[test]
+    fn loops_are_of_the_type_of_their_breaks() {
+        let mismatched = |source| matches!(run(source), Err(Error::MismatchedTypes(..)));
+        assert!(mismatched("let x: String = loop { break 5; };"));
+        assert!(mismatched(
+            "let x = loop { if true { break 1; } break \"a\"; };"
+        ));
+        assert!(mismatched(
+            "let x: String = 'outer: loop { loop { break 'outer 1; } break \"a\"; };"
+        ));
+        assert!(mismatched("loop x in 0..3 { break 1; }"));
+        assert!(run("let x: i64 = loop { break 5; }; x").is_ok());
+        assert!(run("let x: String = 'a: loop { loop { break 'a \"a\"; } }; x").is_ok());
+        // Closures can not leave the loops around them
+        assert!(run("let x = loop { let f = || loop { break 1; }; break f() + 1; }; x").is_ok());
+        // A loop that only returns never ends
+        assert!(run("fn f() -> i32 { loop { return 1; } } f()").is_ok());
+    }
+
+    #[test]
+    fn only_values_of_one_type_are_compared() {
+        let invalid = |source| matches!(run(source), Err(Error::InvalidOperation(..)));
+        assert!(invalid("1 == \"a\""));
+        assert!(invalid("(1, 2) == [1]"));
+        assert!(invalid("struct P {} struct Q {} P {} != Q {}"));
+        assert!(run("(1, \"a\") == (2, \"b\")").is_ok());
+        assert!(run("let x: i32? = 1; x == ? || x == 1 || 2 == x").is_ok());
+    }
+
+    #[test]
+    fn fixed_length_arrays_keep_their_length() {
+        let fixed = |source| matches!(run(source), Err(Error::FixedLength(..)));
+        assert!(fixed("let mut a: [i32; 2] = [1, 2]; a.push(3);"));
+        assert!(fixed("let mut a: [i32; 2] = [1, 2]; a.pop();"));
+        assert!(fixed("fn f(mut a: [i32; 1]) { a.pop(); }"));
+        assert!(run("let mut a: [i32; 2] = [1, 2]; a[0] = a.len() as i32; a").is_ok());
+        assert!(run("let a: [i32; 2] = [1, 2]; let mut b = a[0..1]; b.push(2); b").is_ok());
+        assert!(run("let mut a: [i32] = [1, 2]; a.push(3); a").is_ok());
+    }
+
+    #[test]
+    fn only_optionals_are_null() {
+        let shown = |source: &str| run(source).unwrap();
+        let p = "struct P { name: String } let p: P? = ?; let q: P? = P { name: \"a\" };";
+        let source = format!("{p} (p?.name, q?.name, p?.name ?? \"none\", q?.name ?? \"none\")");
+        assert_eq!(shown(&source), "(?, \"a\", \"none\", \"a\")");
+        // The right of `??` only runs when the left is null
+        let source = "let mut n = 0; let f = || { n = n + 1; 0 }; let x: i32? = 2; (x ?? f(), n)";
+        assert_eq!(shown(source), "(2, 0)");
+        assert!(matches!(
+            run("let x: i32 = ?;"),
+            Err(Error::NullAssignment(_))
+        ));
+        let invalid = run("let x: i32? = 1; x + 1");
+        assert!(matches!(invalid, Err(Error::InvalidOperation(..))));
+        let mismatched = run("let x: i32? = ?; x ?? \"a\"");
+        assert!(matches!(mismatched, Err(Error::MismatchedTypes(..))));
+    }
+}
@@ -0,0 +1,283 @@
+use crate::{
+    ast::{
+        stmt::{EnumDecl, StructDecl, TraitDecl},
+        token::Number,
+    },
+    position::Position,
+    runtime::bigint::BigInt,
+};
+use ascii::AsciiString;
+use std::{fmt::Display, rc::Rc};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum NumberType {
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    ISize,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    USize,
+    F32,
+    F64,
+    BigInt,
+    Decimal,
+}
+
+impl NumberType {
+    pub const ALL: [Self; 16] = [
+        Self::I8,
+        Self::I16,
+        Self::I32,
+        Self::I64,
+        Self::I128,
+        Self::ISize,
+        Self::U8,
+        Self::U16,
+        Self::U32,
+        Self::U64,
+        Self::U128,
+        Self::USize,
+        Self::F32,
+        Self::F64,
+        Self::BigInt,
+        Self::Decimal,
+    ];
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::I8 => "i8",
+            Self::I16 => "i16",
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::I128 => "i128",
+            Self::ISize => "isize",
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::U64 => "u64",
+            Self::U128 => "u128",
+            Self::USize => "usize",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+            Self::BigInt => "bigint",
+            Self::Decimal => "decimal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|ty| ty.name() == name)
+    }
+
+    pub fn of(number: &Number) -> Self {
+        Self::from_name(number.type_name()).unwrap()
+    }
+
+    pub const fn is_float(&self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+
+    // Whether numbers written with a fraction can be of the type
+    pub const fn is_fractional(&self) -> bool {
+        self.is_float() || matches!(self, Self::Decimal)
+    }
+
+    pub const fn is_unsigned(&self) -> bool {
+        matches!(
+            self,
+            Self::U8 | Self::U16 | Self::U32 | Self::U64 | Self::U128 | Self::USize
+        )
+    }
+
+    // The bits of an integer, `isize` and `usize` as wide as on 64-bit
+    // targets, or the bits of the mantissa of a float with its implicit one.
+    // `bigint` and `decimal` have no bound.
+    const fn bits(&self) -> u32 {
+        match self {
+            Self::I8 | Self::U8 => 8,
+            Self::I16 | Self::U16 => 16,
+            Self::F32 => 24,
+            Self::I32 | Self::U32 => 32,
+            Self::F64 => 53,
+            Self::I64 | Self::U64 | Self::ISize | Self::USize => 64,
+            Self::I128 | Self::U128 => 128,
+            Self::BigInt | Self::Decimal => u32::MAX,
+        }
+    }
+
+    // The least and the greatest value of an integer type, `None` for
+    // `bigint` and the types with fractions
+    pub fn range(&self) -> Option<(BigInt, BigInt)> {
+        if self.is_fractional() || *self == Self::BigInt {
+            return None;
+        }
+        let shift = 128 - self.bits();
+        Some(if self.is_unsigned() {
+            (BigInt::default(), BigInt::from_u128(u128::MAX >> shift))
+        } else {
+            (
+                BigInt::from_i128(i128::MIN >> shift),
+                BigInt::from_i128(i128::MAX >> shift),
+            )
+        })
+    }
+
+    // Whether `as` keeps every value of `self` when casting it to `to`
+    pub const fn lossless(&self, to: Self) -> bool {
+        let (from, bits) = (self.bits(), to.bits());
+        match (self, to) {
+            // Floats become the shortest decimal they print as, which parses back
+            (_, Self::Decimal) => return true,
+            (Self::Decimal, _) => return false,
+            _ => {}
+        }
+        match (self.is_float(), to.is_float()) {
+            (false, false) if self.is_unsigned() => from < bits || to.is_unsigned() && from == bits,
+            (false, false) => !to.is_unsigned() && from <= bits,
+            // The sign of a signed integer takes a bit but not one of the mantissa
+            (false, true) => from - !self.is_unsigned() as u32 <= bits,
+            (true, false) => false,
+            (true, true) => from <= bits,
+        }
+    }
+}
+
+// The type of an expression as far as the checker can tell before running it
+#[derive(Clone)]
+pub enum Type {
+    // Not annotated, so only checked when the program runs
+    Unknown,
+    // Inferred from how the value is used, an index into the checker's variables
+    Var(usize),
+    // The type of `?`, which only optionals accept
+    Null,
+    // The type of expressions that never produce a value, like `return`
+    Never,
+    Unit,
+    Bool,
+    Char,
+    String,
+    Number(NumberType),
+    // The elements of an array and whether its type fixes its length, `[T; N]`
+    Array(Box<Type>, bool),
+    Tuple(Vec<Type>),
+    Map(Box<Type>, Box<Type>),
+    Range(Box<Type>),
+    Optional(Box<Type>),
+    Function(Vec<Type>, Box<Type>),
+    // A struct or an enum with its type arguments
+    Struct(Rc<StructDecl>, Vec<Type>),
+    Enum(Rc<EnumDecl>, Vec<Type>),
+    // A type parameter inside of the declaration of it, which only accepts
+    // itself. Parameters are told apart by where they are declared.
+    Param(AsciiString, Position),
+    // A value of any type implementing the trait, whose methods are looked up
+    // when they are called
+    Dyn(Rc<TraitDecl>),
+}
+
+impl Type {
+    pub fn optional(inner: Type) -> Self {
+        match inner {
+            Type::Optional(_) | Type::Unknown | Type::Null => inner,
+            inner => Type::Optional(Box::new(inner)),
+        }
+    }
+
+    // The type with `f` applied to each of the types it is made of
+    pub fn map(&self, mut f: impl FnMut(&Type) -> Type) -> Type {
+        let mut all = |types: &[Type]| types.iter().map(&mut f).collect::<Vec<_>>();
+        match self {
+            Type::Array(element, fixed) => Type::Array(Box::new(f(element)), *fixed),
+            Type::Tuple(elements) => Type::Tuple(all(elements)),
+            Type::Map(key, value) => Type::Map(Box::new(f(key)), Box::new(f(value))),
+            Type::Range(bound) => Type::Range(Box::new(f(bound))),
+            Type::Optional(inner) => Type::optional(f(inner)),
+            Type::Function(params, ret) => {
+                let params = all(params);
+                Type::Function(params, Box::new(f(ret)))
+            }
+            Type::Struct(decl, args) => Type::Struct(Rc::clone(decl), all(args)),
+            Type::Enum(decl, args) => Type::Enum(Rc::clone(decl), all(args)),
+            ty => ty.clone(),
+        }
+    }
+}
+
+// Separates the displayed types with commas
+fn list(types: &[Type]) -> String {
+    let types = types.iter().map(Type::to_string).collect::<Vec<_>>();
+    types.join(", ")
+}
+
+// Types are shown the way `Value::type_name` names the values of the type
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Unknown | Type::Var(_) => write!(f, "_"),
+            Type::Null => write!(f, "?"),
+            Type::Never => write!(f, "!"),
+            Type::Unit => write!(f, "()"),
+            Type::Bool => write!(f, "bool"),
+            Type::Char => write!(f, "char"),
+            Type::String => write!(f, "String"),
+            Type::Number(number) => write!(f, "{}", number.name()),
+            Type::Array(element, _) => write!(f, "[{element}]"),
+            Type::Tuple(elements) if elements.len() == 1 => write!(f, "({},)", elements[0]),
+            Type::Tuple(elements) => write!(f, "({})", list(elements)),
+            Type::Map(key, value) => write!(f, "Map<{key}, {value}>"),
+            Type::Range(bound) => write!(f, "Range<{bound}>"),
+            Type::Optional(inner) => write!(f, "{inner}?"),
+            Type::Function(params, ret) => match **ret {
+                Type::Unit => write!(f, "fn({})", list(params)),
+                _ => write!(f, "fn({}) -> {ret}", list(params)),
+            },
+            Type::Struct(decl, args) if args.is_empty() => write!(f, "{}", decl.name),
+            Type::Struct(decl, args) => write!(f, "{}<{}>", decl.name, list(args)),
+            Type::Enum(decl, args) if args.is_empty() => write!(f, "{}", decl.name),
+            Type::Enum(decl, args) => write!(f, "{}<{}>", decl.name, list(args)),
+            Type::Param(name, _) => write!(f, "{name}"),
+            Type::Dyn(decl) => write!(f, "dyn {}", decl.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NumberType::{self, *};
+
+    #[test]
+    fn casts_are_lossless_within_the_range_of_the_target() {
+        let lossless = |from: NumberType, to: NumberType| from.lossless(to);
+        assert!(lossless(I8, I16) && lossless(U8, I16) && lossless(U8, U16));
+        assert!(!lossless(I8, U8) && !lossless(U8, I8) && !lossless(I16, I8));
+        assert!(lossless(U64, U128) && lossless(USize, U64) && !lossless(U64, I64));
+        assert!(lossless(I32, F64) && lossless(I16, F32) && lossless(U16, F32));
+        assert!(!lossless(I32, F32) && !lossless(I64, F64) && !lossless(U32, F32));
+        assert!(lossless(F32, F64) && !lossless(F64, F32) && !lossless(F32, I128));
+        assert!(lossless(F64, Decimal) && lossless(I128, Decimal) && !lossless(Decimal, F64));
+        assert!(lossless(U128, BigInt) && !lossless(BigInt, I128) && !lossless(BigInt, F64));
+    }
+
+    #[test]
+    fn integer_ranges_follow_the_bits() {
+        let range = |ty: NumberType| {
+            ty.range()
+                .map(|(min, max)| (min.to_string(), max.to_string()))
+        };
+        assert_eq!(range(U8), Some(("0".into(), "255".into())));
+        assert_eq!(range(I8), Some(("-128".into(), "127".into())));
+        assert_eq!(range(U128), Some(("0".into(), u128::MAX.to_string())));
+        assert_eq!(
+            range(I128),
+            Some((i128::MIN.to_string(), i128::MAX.to_string()))
+        );
+        assert_eq!(range(BigInt), None);
+        assert_eq!(range(F64), None);
+    }
+}
@@ -1,4 +1,4 @@
-use super::Result;
+use super::{Lint, Result, Warning};
 use crate::{ast::token::Number, position::Position};
 use color_print::cformat;
 use std::fmt::Debug;
@@ -11,6 +11,67 @@ pub enum Error {
     NumOverFlow(String, String),
     UnclosedComment(Position),
     InvalidEscapeSequence(char),
+    UnexpectedToken(String, String),
+    UnexpectedEndOfFile(String),
+    Misplaced(String, String),
+    InvalidAssignment,
+    ArityMismatch(String, usize, usize, Option<Position>),
+    NotCallable(String),
+    InvalidOperation(String, String, String),
+    InvalidOperand(String, String),
+    DivisionByZero,
+    StackOverflow(String),
+    IncompatibleBranches(String, String, Position),
+    InvalidCondition(String),
+    UndeclaredLabel(String),
+    NotIterable(String),
+    IndexOutOfBounds(String, usize),
+    UnknownMethod(String, String),
+    InvalidKey(String),
+    MissingKey(String),
+    UnknownType(String),
+    UnknownField(String, String),
+    MissingField(String, String),
+    DuplicateField(String),
+    ImmutableAssignment(String, Option<Position>),
+    UnknownVariant(String, String),
+    VariantForm(String, String),
+    NoMatch(String),
+    NonExhaustive(Vec<String>),
+    RefutablePattern(Vec<String>, String),
+    UnreachablePattern,
+    MismatchedPatterns(String, String),
+    NullAssignment(String),
+    MissingModule(String, String),
+    ImportCycle(Vec<String>),
+    UnknownItem(String, String),
+    PrivateItem(String, String),
+    DuplicateDefinition(String, Position),
+    MismatchedTypes(String, String, Option<Position>),
+    TypeArguments(String, usize, usize),
+    UnknownTrait(String),
+    MissingMethods(Vec<String>, String, String),
+    NotTraitMethod(String, String),
+    ConflictingImpls(String, String, Position),
+    OrphanImpl(String, String),
+    TraitNotImported(String, String, String),
+    UnsatisfiedBound(String, String),
+    OperatorNotImplemented(String, String, String),
+    UnboundedOperator(String, String, String),
+    UninitializedRead(String, Vec<String>),
+    UnknownLint(String),
+    // Why the thread running the program could not be started
+    ThreadSpawn(String),
+    NotConst(String),
+    RecursiveConst(String),
+    EvaluationLimit,
+    ArrayLength(usize, usize),
+    // A method changing the length of an array whose type fixes it
+    FixedLength(String),
+    InvalidCast(String, String),
+    NonFinite(String, String),
+    // A warning of a lint set to `Level::Deny`
+    Denied(Warning),
 }
 
 impl Error {
@@ -38,6 +99,64 @@ impl Error {
             Error::NumOverFlow(_, num_type) => return format!("{num_type} number overflow"),
             Error::UnclosedComment(_) => "unclosed comment",
             Error::InvalidEscapeSequence(_) => "invalid escape sequence",
+            Error::UnexpectedToken(..) => "unexpected token",
+            Error::UnexpectedEndOfFile(_) => "unexpected end of file",
+            Error::Misplaced(keyword, _) => return format!("misplaced `{keyword}`"),
+            Error::InvalidAssignment => "invalid assignment",
+            Error::ArityMismatch(..) => "arity mismatch",
+            Error::NotCallable(_) => "not callable",
+            Error::InvalidOperation(..)
+            | Error::InvalidOperand(..)
+            | Error::OperatorNotImplemented(..)
+            | Error::UnboundedOperator(..) => "invalid operation",
+            Error::DivisionByZero => "division by zero",
+            Error::StackOverflow(_) => "stack overflow",
+            Error::IncompatibleBranches(..) => "incompatible branches",
+            Error::InvalidCondition(_) => "invalid condition",
+            Error::UndeclaredLabel(_) => "undeclared label",
+            Error::NotIterable(_) => "not iterable",
+            Error::IndexOutOfBounds(..) => "index out of bounds",
+            Error::UnknownMethod(..) => "unknown method",
+            Error::InvalidKey(_) => "invalid key",
+            Error::MissingKey(_) => "missing key",
+            Error::UnknownType(_) => "unknown type",
+            Error::UnknownVariant(..) => "unknown variant",
+            Error::VariantForm(..) => "wrong variant form",
+            Error::NoMatch(_) => "no matching arm",
+            Error::NonExhaustive(_) => "non-exhaustive match",
+            Error::RefutablePattern(..) => "refutable pattern",
+            Error::UnreachablePattern => "unreachable pattern",
+            Error::MismatchedPatterns(..) => "mismatched patterns",
+            Error::NullAssignment(_) => "null assignment",
+            Error::MissingModule(..) => "missing module",
+            Error::ImportCycle(_) => "import cycle",
+            Error::UnknownItem(..) => "unknown item",
+            Error::PrivateItem(..) => "private item",
+            Error::DuplicateDefinition(..) => "duplicate definition",
+            Error::MismatchedTypes(..) => "mismatched types",
+            Error::TypeArguments(..) => "wrong number of type arguments",
+            Error::UnknownTrait(_) => "unknown trait",
+            Error::MissingMethods(..) => "missing trait methods",
+            Error::NotTraitMethod(..) => "unknown trait method",
+            Error::ConflictingImpls(..) => "conflicting implementations",
+            Error::OrphanImpl(..) => "orphan implementation",
+            Error::TraitNotImported(..) => "method not found",
+            Error::UnsatisfiedBound(..) => "unsatisfied trait bound",
+            Error::UnknownField(..) => "unknown field",
+            Error::MissingField(..) => "missing field",
+            Error::DuplicateField(_) => "duplicate field",
+            Error::ImmutableAssignment(..) => "immutable assignment",
+            Error::UninitializedRead(..) => "uninitialized variable",
+            Error::UnknownLint(_) => "unknown lint",
+            Error::ThreadSpawn(_) => "thread error",
+            Error::NotConst(_) => "not constant",
+            Error::RecursiveConst(_) => "recursive constant",
+            Error::EvaluationLimit => "evaluation limit",
+            Error::ArrayLength(..) => "mismatched array length",
+            Error::FixedLength(..) => "fixed array length",
+            Error::InvalidCast(..) => "invalid cast",
+            Error::NonFinite(..) => "non-finite number",
+            Error::Denied(warning) => warning.name(),
         })
     }
 
@@ -47,6 +166,9 @@ impl Error {
             Error::InvalidSuffix(sfx) => format!("\"{sfx}\" is an invalid suffix"),
             Error::FileErr(msg) => String::from(msg),
             Error::UnknownIdent(ident) => cformat!("unknown identifer <g>\"{ident}\"</>"),
+            Error::NumOverFlow(num, num_type) if num.starts_with('-') => {
+                format!("{num} is beyond the min value of {num_type}")
+            }
             Error::NumOverFlow(num, num_type) => {
                 format!("{num} is beyond the max value of {num_type}")
             }
@@ -56,6 +178,172 @@ impl Error {
             Error::InvalidEscapeSequence(es) => {
                 cformat!("<g>'{es}'</> is not recognized as <u>escape sequence</>")
             }
+            Error::UnexpectedToken(found, expected) => {
+                cformat!("expected {expected} but found <g>{found}</>")
+            }
+            Error::UnexpectedEndOfFile(expected) => {
+                format!("expected {expected} but the source ended")
+            }
+            Error::Misplaced(keyword, place) => {
+                cformat!("<g>`{keyword}`</> can only be used inside {place}")
+            }
+            Error::InvalidAssignment => String::from("left hand side can not be assigned to"),
+            Error::ArityMismatch(name, expected, found, _) => {
+                let plural = if *expected == 1 { "" } else { "s" };
+                let verb = if *found == 1 { "was" } else { "were" };
+                cformat!(
+                    "<g>`{name}`</> takes {expected} argument{plural} but {found} {verb} given"
+                )
+            }
+            Error::NotCallable(ty) => cformat!("value of type <g>{ty}</> is not a function"),
+            Error::InvalidOperation(op, lhs, rhs) => {
+                cformat!("can not apply <g>`{op}`</> to <u>{lhs}</> and <u>{rhs}</>")
+            }
+            Error::InvalidOperand(op, ty) => {
+                cformat!("can not apply <g>`{op}`</> to <u>{ty}</>")
+            }
+            Error::DivisionByZero => String::from("attempted to divide by zero"),
+            Error::StackOverflow(name) => {
+                cformat!("too many nested calls while calling <g>`{name}`</>")
+            }
+            Error::IncompatibleBranches(then_type, else_type, _) => cformat!(
+                "<g>`if`</> branch yields <u>{then_type}</> but <g>`else`</> branch yields <u>{else_type}</>"
+            ),
+            Error::InvalidCondition(ty) => {
+                cformat!("condition must be a <u>bool</> but found <u>{ty}</>")
+            }
+            Error::UndeclaredLabel(label) => {
+                cformat!("no enclosing loop is labeled <g>`'{label}`</>")
+            }
+            Error::NotIterable(ty) => cformat!("value of type <u>{ty}</> can not be iterated"),
+            Error::IndexOutOfBounds(index, len) => {
+                cformat!("index <g>{index}</> is out of bounds for length <g>{len}</>")
+            }
+            Error::UnknownMethod(name, ty) => {
+                cformat!("<u>{ty}</> has no method named <g>`{name}`</>")
+            }
+            Error::InvalidKey(ty) => cformat!("<u>{ty}</> can not be used as a map key"),
+            Error::MissingKey(key) => cformat!("key <g>{key}</> is not in the map"),
+            Error::UnknownType(name) => cformat!("<g>`{name}`</> is not a struct or an enum"),
+            Error::UnknownVariant(variant, ty) => {
+                cformat!("<u>{ty}</> has no variant named <g>`{variant}`</>")
+            }
+            Error::VariantForm(variant, usage) => {
+                cformat!("<g>`{variant}`</> must be written as <g>{usage}</>")
+            }
+            Error::NoMatch(value) => cformat!("no pattern matches <g>{value}</>"),
+            Error::NonExhaustive(patterns) => {
+                let plural = if patterns.len() == 1 { "" } else { "s" };
+                cformat!("pattern{plural} <g>{}</> not covered", listed(patterns))
+            }
+            Error::RefutablePattern(patterns, place) => {
+                let plural = if patterns.len() == 1 { "" } else { "s" };
+                cformat!(
+                    "pattern in <g>{place}</> must match every value but pattern{plural} <g>{}</> not covered",
+                    listed(patterns)
+                )
+            }
+            Error::UnreachablePattern => {
+                String::from("earlier arms already match every value this arm matches")
+            }
+            Error::MismatchedPatterns(expected, found) => {
+                cformat!("expected a pattern of type <u>{expected}</> but found <u>{found}</>")
+            }
+            Error::NullAssignment(ty) => {
+                cformat!("<g>`?`</> can not be used as a value of type <u>{ty}</>")
+            }
+            Error::MissingModule(module, _) => cformat!("module <g>`{module}`</> was not found"),
+            Error::ImportCycle(cycle) => {
+                let cycle = cycle.join(" -> ");
+                cformat!("modules import each other in a cycle: <g>{cycle}</>")
+            }
+            Error::UnknownItem(item, module) => {
+                cformat!("module <g>`{module}`</> has no item named <g>`{item}`</>")
+            }
+            Error::PrivateItem(item, module) => {
+                cformat!("<g>`{item}`</> is private to module <g>`{module}`</>")
+            }
+            Error::DuplicateDefinition(name, _) => {
+                cformat!("<g>`{name}`</> is defined more than once in the same scope")
+            }
+            Error::MismatchedTypes(expected, found, _) => {
+                cformat!("expected a value of type <u>{expected}</> but found <u>{found}</>")
+            }
+            Error::TypeArguments(name, expected, found) => {
+                let plural = if *expected == 1 { "" } else { "s" };
+                let verb = if *found == 1 { "was" } else { "were" };
+                cformat!(
+                    "<g>`{name}`</> takes {expected} type argument{plural} but {found} {verb} given"
+                )
+            }
+            Error::UnknownTrait(name) => cformat!("<g>`{name}`</> is not a trait"),
+            Error::MissingMethods(methods, name, ty) => {
+                let plural = if methods.len() == 1 { "" } else { "s" };
+                cformat!(
+                    "<u>{ty}</> does not implement method{plural} <g>{}</> of trait <g>`{name}`</>",
+                    listed(methods)
+                )
+            }
+            Error::NotTraitMethod(method, name) => {
+                cformat!("<g>`{method}`</> is not a method of trait <g>`{name}`</>")
+            }
+            Error::ConflictingImpls(name, ty, _) => {
+                cformat!("trait <g>`{name}`</> is implemented more than once for <u>{ty}</>")
+            }
+            Error::OrphanImpl(name, ty) => {
+                cformat!("neither trait <g>`{name}`</> nor <u>{ty}</> is declared in this module")
+            }
+            Error::TraitNotImported(method, ty, name) => cformat!(
+                "<u>{ty}</> has no method named <g>`{method}`</> in scope, trait <g>`{name}`</> is implemented but not imported"
+            ),
+            Error::UnsatisfiedBound(ty, name) => {
+                cformat!("<u>{ty}</> does not implement trait <g>`{name}`</>")
+            }
+            Error::OperatorNotImplemented(op, ty, name) => cformat!(
+                "can not apply <g>`{op}`</> to <u>{ty}</>, which does not implement trait <g>`{name}`</>"
+            ),
+            Error::UnboundedOperator(op, ty, name) => cformat!(
+                "can not apply <g>`{op}`</> to <u>{ty}</>, which is not bounded by trait <g>`{name}`</>"
+            ),
+            Error::UnknownField(field, ty) => {
+                cformat!("<u>{ty}</> has no field named <g>`{field}`</>")
+            }
+            Error::MissingField(field, ty) => {
+                cformat!("field <g>`{field}`</> of <u>{ty}</> is not initialized")
+            }
+            Error::DuplicateField(field) => {
+                cformat!("field <g>`{field}`</> is initialized more than once")
+            }
+            Error::ImmutableAssignment(name, _) => {
+                cformat!("<g>`{name}`</> is not mutable, so neither it nor its fields and elements can be changed")
+            }
+            Error::UninitializedRead(name, path) if path.is_empty() => {
+                cformat!("<g>`{name}`</> is used before it is assigned a value")
+            }
+            Error::UninitializedRead(name, _) => {
+                cformat!("<g>`{name}`</> is used where it may not be assigned a value")
+            }
+            Error::UnknownLint(name) => cformat!("there is no lint named <g>`{name}`</>"),
+            Error::ThreadSpawn(reason) => format!("the program could not be started: {reason}"),
+            Error::NotConst(what) => format!("{what} can not be evaluated at compile time"),
+            Error::RecursiveConst(name) => {
+                cformat!("the value of constant <g>`{name}`</> depends on itself")
+            }
+            Error::EvaluationLimit => {
+                String::from("the constant takes too many steps to compute")
+            }
+            Error::ArrayLength(expected, found) => {
+                let plural = if *expected == 1 { "" } else { "s" };
+                format!("expected an array of {expected} element{plural} but found {found}")
+            }
+            Error::FixedLength(method) => cformat!(
+                "<g>`{method}`</> changes the length of the array, which its type fixes"
+            ),
+            Error::InvalidCast(from, to) => {
+                cformat!("can not cast <u>{from}</> to <u>{to}</> with <g>`as`</>")
+            }
+            Error::NonFinite(number, ty) => format!("{number} has no value as a {ty}"),
+            Error::Denied(warning) => warning.msg(),
         }
     }
 
@@ -68,6 +356,7 @@ impl Error {
                     'i' => 0..=5,
                     'u' => 6..=12,
                     'f' => 13..=15,
+                    'n' | 'd' => 16..=17,
                     _ => 0..=13,
                 };
                 for i in range {
@@ -82,16 +371,191 @@ impl Error {
             Error::UnknownIdent(ident) => {
                 cformat!("remove or declare <g>\"{ident}\"</> in source")
             }
-            Error::NumOverFlow(_, num_type) => format!("{num_type}"),
+            Error::NumOverFlow(_, num_type) => num_type.to_string(),
             Error::UnclosedComment(pos) => cformat!("add <g>\"*/\"</> to <dim>{pos:?}</>",),
             Error::InvalidEscapeSequence(es) => {
                 let c = es.escape_default().nth(0).unwrap();
                 cformat!("remove <g>'{c}'</> & replace it with <g>'\\'</>",)
             }
+            Error::UnexpectedToken(_, expected) | Error::UnexpectedEndOfFile(expected) => {
+                format!("add {expected} here")
+            }
+            Error::Misplaced(keyword, _) => cformat!("remove <g>`{keyword}`</> from here"),
+            Error::InvalidAssignment => {
+                String::from("only variables, elements and fields can be assigned to")
+            }
+            Error::ArityMismatch(name, expected, _, Some(pos)) => {
+                let plural = if *expected == 1 { "" } else { "s" };
+                cformat!("<g>`{name}`</> is declared at <dim>{pos:?}</> with {expected} parameter{plural}")
+            }
+            Error::ArityMismatch(_, expected, _, None) => {
+                format!("pass exactly {expected} argument(s)")
+            }
+            Error::NotCallable(_) => String::from("only functions can be called"),
+            Error::InvalidOperation(..) | Error::InvalidOperand(..) => {
+                String::from("convert the operands to matching types")
+            }
+            Error::DivisionByZero => String::from("check the divisor before dividing"),
+            Error::StackOverflow(_) => String::from("make sure the recursion has a base case"),
+            Error::IncompatibleBranches(then_type, _, pos) => {
+                cformat!("make this branch yield <u>{then_type}</> like the one at <dim>{pos:?}</>")
+            }
+            Error::InvalidCondition(_) => {
+                cformat!("compare the value instead, e.g. <g>`x != 0`</>")
+            }
+            Error::UndeclaredLabel(label) => {
+                cformat!("label a loop with <g>`'{label}: loop`</> or remove the label")
+            }
+            Error::NotIterable(_) => cformat!(
+                "iterate over a range, a string, an array, a map, a function returning <g>`?`</> when done or a value with a <g>`next`</> method that does"
+            ),
+            Error::IndexOutOfBounds(_, len) => match len {
+                0 => String::from("the collection is empty"),
+                len => format!("indices start at 0 and end at {}", len - 1),
+            },
+            Error::UnknownMethod(..) => String::from("check the spelling of the method"),
+            Error::InvalidKey(_) => String::from(
+                "keys can be `?`, bool, char, String or any number other than NaN",
+            ),
+            Error::MissingKey(_) => cformat!("use <g>`.get(key)`</> to get <g>`?`</> for missing keys"),
+            Error::UnknownType(name) => {
+                cformat!("declare it with <g>`struct {name} {{ ... }}`</> or <g>`enum {name} {{ ... }}`</>")
+            }
+            Error::UnknownVariant(..) => String::from("check the spelling of the variant"),
+            Error::VariantForm(..) => {
+                String::from("construct the variant the way it is declared")
+            }
+            Error::NoMatch(_) => {
+                String::from("the patterns are of a different type than the value")
+            }
+            Error::NonExhaustive(_) => {
+                cformat!("add arms for the missing patterns or a <g>`_`</> arm")
+            }
+            Error::UnreachablePattern => {
+                String::from("remove the arm or move it before the arms covering it")
+            }
+            Error::RefutablePattern(..) => {
+                cformat!("use <g>`match`</> to handle the values the pattern does not cover")
+            }
+            Error::MismatchedPatterns(..) => {
+                String::from("every arm must match values of the same type")
+            }
+            Error::NullAssignment(ty) => cformat!("make the type optional with <g>`{ty}?`</>"),
+            Error::MissingModule(_, file) => cformat!("create <g>\"{file}\"</>"),
+            Error::ImportCycle(_) => {
+                String::from("move what the modules share into a module that imports neither")
+            }
+            Error::UnknownItem(..) => {
+                String::from("only functions, constants, structs, enums and traits can be imported")
+            }
+            Error::PrivateItem(item, module) => {
+                cformat!("declare <g>`{item}`</> with <g>`pub`</> in <g>`{module}`</>")
+            }
+            Error::DuplicateDefinition(name, first) => {
+                cformat!("<g>`{name}`</> is first defined at <dim>{first:?}</>, rename one of them")
+            }
+            Error::MismatchedTypes(_, _, Some(pos)) => {
+                cformat!("the expected type comes from <dim>{pos:?}</>")
+            }
+            Error::MismatchedTypes(expected, ..) => {
+                cformat!("convert the value to <u>{expected}</>")
+            }
+            Error::TypeArguments(name, expected, _) => match expected {
+                0 => format!("remove the `<..>` after `{name}`"),
+                _ => format!("write it as `{name}<..>` with {expected} types"),
+            },
+            Error::UnknownTrait(name) => cformat!("declare it with <g>`trait {name} {{ ... }}`</>"),
+            Error::MissingMethods(..) => {
+                String::from("add the methods to the `impl` block or give them a body in the trait")
+            }
+            Error::NotTraitMethod(..) => {
+                String::from("declare it in the trait or move it to an `impl` block of the type")
+            }
+            Error::ConflictingImpls(_, _, first) => {
+                cformat!("the first implementation is at <dim>{first:?}</>, remove one of them")
+            }
+            Error::OrphanImpl(..) => String::from(
+                "implement the trait in the module declaring it or the one declaring the type",
+            ),
+            Error::TraitNotImported(_, _, name) => {
+                cformat!("import <g>`{name}`</> from the module declaring it")
+            }
+            Error::UnsatisfiedBound(_, name) => {
+                cformat!("implement it with <g>`impl {name} for ..`</> or use a type that does")
+            }
+            Error::OperatorNotImplemented(_, ty, name) => {
+                cformat!("overload the operator with <g>`impl {name} for {ty}`</>")
+            }
+            Error::UnboundedOperator(_, ty, name) => {
+                cformat!("bound the type parameter with <g>`{ty}: {name}`</>")
+            }
+            Error::UnknownField(..) => String::from("check the spelling of the field"),
+            Error::MissingField(field, _) => cformat!("add <g>`{field}: value`</> to the literal"),
+            Error::DuplicateField(_) => String::from("remove one of the initializers"),
+            Error::ImmutableAssignment(name, Some(pos)) => {
+                cformat!("declare it as <g>`mut {name}`</> at <dim>{pos:?}</>")
+            }
+            Error::ImmutableAssignment(name, None) => {
+                cformat!("declare it with <g>`let mut {name}`</> or take <g>`mut self`</>")
+            }
+            Error::UninitializedRead(name, path) if path.is_empty() => {
+                cformat!("assign <g>`{name}`</> a value before using it")
+            }
+            Error::UninitializedRead(_, path) => {
+                format!("it is not assigned when {}", path.join(", then "))
+            }
+            Error::UnknownLint(_) => {
+                let lints = Lint::ALL.map(Lint::name);
+                format!("the lints are `warnings`, for all of them, and {}", lints.join(", "))
+            }
+            Error::ThreadSpawn(_) => {
+                String::from("programs run on a thread with a stack of 1 GiB, free some memory")
+            }
+            Error::NotConst(_) => cformat!(
+                "constants can only use literals, operators, other constants and calls to <g>`const fn`</>"
+            ),
+            Error::RecursiveConst(_) => {
+                String::from("compute it from constants that do not refer back to it")
+            }
+            Error::EvaluationLimit => {
+                cformat!("compute the value at runtime with <g>`let`</> instead")
+            }
+            Error::ArrayLength(..) => {
+                String::from("add or remove elements to match the length of the array type")
+            }
+            Error::FixedLength(_) => {
+                String::from("leave the length out of the array type to change it")
+            }
+            Error::InvalidCast(..) => cformat!(
+                "<g>`as`</> converts between numbers, from <u>bool</> or <u>char</> to an integer and from <u>u8</> to <u>char</>"
+            ),
+            Error::NonFinite(..) => {
+                String::from("only finite floats convert to `bigint` and `decimal`")
+            }
+            Error::Denied(warning) => cformat!(
+                "{}, <g>`{}`</> is denied",
+                warning.hint(),
+                warning.lint().name()
+            ),
         }
     }
 }
 
+// Lists patterns as `a`, `b` and `c`, cutting long lists short with `and 2 more`
+fn listed(patterns: &[String]) -> String {
+    let shown = patterns
+        .iter()
+        .take(3)
+        .map(|pattern| format!("`{pattern}`"))
+        .collect::<Vec<_>>();
+    match (patterns.len(), shown.split_last()) {
+        (n, _) if n > 3 => format!("{} and {} more", shown.join(", "), n - 3),
+        (_, Some((last, []))) => last.clone(),
+        (_, Some((last, rest))) => format!("{} and {last}", rest.join(", ")),
+        (_, None) => String::new(),
+    }
+}
+
 impl Debug for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.format())
@@ -1,6 +1,11 @@
+#[allow(clippy::module_inception)]
 pub mod error;
+pub mod warning;
 
-pub use crate::error::error::Error;
+pub use crate::error::{
+    error::Error,
+    warning::{Level, Levels, Lint, Warning},
+};
 use std::result;
 
 pub type Result<T> = result::Result<T, Error>;
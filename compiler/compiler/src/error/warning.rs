@@ -0,0 +1,166 @@
+use crate::position::Position;
+use color_print::cformat;
+use std::{collections::HashMap, fmt::Debug};
+
+// The checks warning about code that runs but likely does not do what was
+// meant, silenced by `#[allow(..)]` and configured by `-W` and `-D`
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    UnusedVariables,
+    UnusedImports,
+    UnusedFunctions,
+    UnreachableCode,
+    UnusedAssignments,
+    ConstantConditions,
+    LossyCasts,
+}
+
+impl Lint {
+    pub const ALL: [Self; 7] = [
+        Self::UnusedVariables,
+        Self::UnusedImports,
+        Self::UnusedFunctions,
+        Self::UnreachableCode,
+        Self::UnusedAssignments,
+        Self::ConstantConditions,
+        Self::LossyCasts,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::UnusedVariables => "unused_variables",
+            Self::UnusedImports => "unused_imports",
+            Self::UnusedFunctions => "unused_functions",
+            Self::UnreachableCode => "unreachable_code",
+            Self::UnusedAssignments => "unused_assignments",
+            Self::ConstantConditions => "constant_conditions",
+            Self::LossyCasts => "lossy_casts",
+        }
+    }
+
+    pub fn named(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Allow,
+    Warn,
+    // Reports the warning as an error
+    Deny,
+}
+
+// The level of every lint, all of them warn unless set otherwise
+#[derive(Clone, Default)]
+pub struct Levels(HashMap<Lint, Level>);
+
+impl Levels {
+    pub fn set(&mut self, lint: Lint, level: Level) {
+        self.0.insert(lint, level);
+    }
+
+    // Sets the level of a lint not set before
+    pub fn set_default(&mut self, lint: Lint, level: Level) {
+        self.0.entry(lint).or_insert(level);
+    }
+
+    pub fn level(&self, lint: Lint) -> Level {
+        self.0.get(&lint).copied().unwrap_or(Level::Warn)
+    }
+}
+
+pub enum Warning {
+    UnusedVariable(String),
+    UnusedImport(String),
+    UnusedFunction(String),
+    UnreachableCode,
+    UnusedAssignment(String),
+    ConstantCondition(bool),
+    // The number types an `as` casts from and to
+    LossyCast(String, String),
+}
+
+impl Warning {
+    pub const fn lint(&self) -> Lint {
+        match self {
+            Self::UnusedVariable(_) => Lint::UnusedVariables,
+            Self::UnusedImport(_) => Lint::UnusedImports,
+            Self::UnusedFunction(_) => Lint::UnusedFunctions,
+            Self::UnreachableCode => Lint::UnreachableCode,
+            Self::UnusedAssignment(_) => Lint::UnusedAssignments,
+            Self::ConstantCondition(_) => Lint::ConstantConditions,
+            Self::LossyCast(..) => Lint::LossyCasts,
+        }
+    }
+
+    // Printed where errors are, but the program still runs
+    pub fn report(&self, pos: &Position) {
+        eprintln!("{}", cformat!("<b>--></b> <dim>{pos:?}</>"));
+        eprintln!("{self:?}");
+    }
+
+    pub(super) fn name(&self) -> &'static str {
+        match self {
+            Self::UnusedVariable(_) => "unused variable",
+            Self::UnusedImport(_) => "unused import",
+            Self::UnusedFunction(_) => "unused function",
+            Self::UnreachableCode => "unreachable code",
+            Self::UnusedAssignment(_) => "unused assignment",
+            Self::ConstantCondition(_) => "constant condition",
+            Self::LossyCast(..) => "lossy cast",
+        }
+    }
+
+    pub(super) fn msg(&self) -> String {
+        match self {
+            Self::UnusedVariable(name) => cformat!("<g>`{name}`</> is never used"),
+            Self::UnusedImport(name) => cformat!("<g>`{name}`</> is imported but never used"),
+            Self::UnusedFunction(name) => cformat!("function <g>`{name}`</> is never called"),
+            Self::UnreachableCode => String::from("this code never runs"),
+            Self::UnusedAssignment(name) => {
+                cformat!("the value assigned to <g>`{name}`</> is never read")
+            }
+            Self::ConstantCondition(value) => format!("this condition is always {value}"),
+            Self::LossyCast(from, to) => {
+                cformat!("casting <u>{from}</> to <u>{to}</> can change the value")
+            }
+        }
+    }
+
+    pub(super) fn hint(&self) -> String {
+        match self {
+            Self::UnusedVariable(name) => {
+                cformat!("remove it or prefix it with an underscore, as in <g>`_{name}`</>")
+            }
+            Self::UnusedImport(_) => String::from("remove it from the `use` declaration"),
+            Self::UnusedFunction(name) => {
+                cformat!("remove it, call it or export it with <g>`pub fn {name}`</>")
+            }
+            Self::UnreachableCode => String::from(
+                "it comes after a `return`, `break` or `continue`, or a loop that never ends",
+            ),
+            Self::UnusedAssignment(_) => {
+                String::from("remove the assignment or read the value before it is overwritten")
+            }
+            Self::ConstantCondition(true) => String::from("remove the condition"),
+            Self::ConstantCondition(false) => String::from("remove the code it guards"),
+            Self::LossyCast(_, to) => {
+                cformat!("use <g>`.to_{to}()`</>, which is <g>`?`</> when the value does not fit")
+            }
+        }
+    }
+}
+
+impl Debug for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let formated = cformat!(
+            "<y,s>?</> - {name}: {message} <dim>[{lint}]</>\n<c>hint</>: {hint}",
+            name = self.name(),
+            message = self.msg(),
+            lint = self.lint().name(),
+            hint = self.hint()
+        );
+        write!(f, "{formated}")
+    }
+}
@@ -1,31 +1,139 @@
 use super::error::*;
-use crate::ast::lexer::Lexer;
+use crate::{
+    ast::{lexer::Lexer, parser::Parser},
+    check::Checker,
+    module::Loader,
+    runtime::{interpreter::Interpreter, value::Value},
+};
 use color_print::cprint;
 use std::{
     fs,
     io::{self, BufRead, Write},
+    path::Path,
 };
 
-pub fn repl() -> Result<()> {
+// Everything a program keeps between the lines of the REPL
+struct Session {
+    loader: Loader,
+    checker: Checker,
+    interpreter: Interpreter,
+    // The line the next source starts at. Declarations are known by where
+    // they are, so no two lines of the REPL share a position.
+    line: u16,
+}
+
+impl Session {
+    // Imports are resolved relative to `root`
+    fn new(root: &Path, levels: Levels) -> Self {
+        Self {
+            loader: Loader::new(root),
+            checker: Checker::new(levels),
+            interpreter: Interpreter::new(),
+            line: 1,
+        }
+    }
+
+    fn eval(&mut self, file: &str, name: &str, source: &str) -> Result<Value> {
+        let tokens = Lexer::at_line(file, source, self.line).tokenize();
+        let lines = source.lines().count().max(1);
+        self.line = self
+            .line
+            .saturating_add(lines.try_into().unwrap_or(u16::MAX));
+        let tokens = tokens?;
+        let mut parser = Parser::new(tokens);
+        let stmts = parser.parse()?;
+        let modules = self.loader.load(name, &stmts)?;
+        for (i, module) in modules.iter().enumerate() {
+            let result = self.checker.check_module(module);
+            self.report();
+            if let Err(error) = result.and_then(|()| self.interpreter.run_module(module)) {
+                self.loader.unload(&modules[i..]);
+                return Err(error);
+            }
+        }
+        let result = self.checker.check(&stmts, &parser.allows());
+        self.report();
+        result?;
+        self.interpreter.run(&stmts)
+    }
+
+    // Warnings are printed once their program is checked, before it runs
+    fn report(&mut self) {
+        for (warning, pos) in self.checker.warnings() {
+            warning.report(&pos);
+        }
+    }
+}
+
+// Later lines can use what a line declares and assigns, so the lints about
+// unused code are allowed unless `levels` sets them
+pub fn repl(mut levels: Levels) -> Result<()> {
+    for lint in [
+        Lint::UnusedVariables,
+        Lint::UnusedImports,
+        Lint::UnusedFunctions,
+        Lint::UnusedAssignments,
+    ] {
+        levels.set_default(lint, Level::Allow);
+    }
+    let mut session = Session::new(Path::new("."), levels);
     loop {
         cprint!("<s,#C0C0C0>Na</> <#8989FF>>></> ");
         io::stdout().lock().flush().unwrap();
 
         let mut line = String::new();
-        io::stdin().lock().read_line(&mut line).unwrap();
-        if let Err(e) = eval("<stdin>", &line) {
-            eprintln!("{e:?}")
+        if io::stdin().lock().read_line(&mut line).unwrap() == 0 {
+            return Ok(());
+        }
+        match session.eval("<stdin>", "<stdin>", &line) {
+            Ok(Value::Unit) => {}
+            Ok(value) => println!("{value}"),
+            Err(e) => eprintln!("{e:?}"),
         }
     }
 }
 
-pub fn script(name: &str) -> Result<()> {
+// The directory of the script is the root of the project
+pub fn script(name: &str, levels: Levels) -> Result<()> {
     let content = fs::read_to_string(name).map_err(|e| Error::FileErr(e.to_string()))?;
-    eval(name, &content)
+    let path = Path::new(name);
+    let root = path.parent().unwrap_or(Path::new("."));
+    let module = path
+        .file_stem()
+        .map_or(name.into(), |stem| stem.to_string_lossy());
+    let mut session = Session::new(root, levels);
+    session.eval(name, &module, &content).map(|_| ())
+}
+
+// Runs a program as a script of its own and shows the value it ends with,
+// for the tests of every stage. Like `nai`, it runs on a thread with room for
+// the deepest recursion the interpreter allows.
+#[cfg(test)]
+pub fn run(source: &str) -> Result<String> {
+    let source = source.to_owned();
+    std::thread::Builder::new()
+        .stack_size(1 << 30)
+        .spawn(move || {
+            let mut session = Session::new(Path::new("."), Levels::default());
+            let value = session.eval("<test>", "test", &source)?;
+            Ok(value.to_string())
+        })
+        .unwrap()
+        .join()
+        .unwrap()
 }
 
-fn eval(file: &str, source: &str) -> Result<()> {
-    let tokens = Lexer::new(file, source).tokenize()?;
-    println!("{tokens:?}");
-    Ok(())
+// The warnings about a program that checks, in the order of the source
+#[cfg(test)]
+pub fn warnings(source: &str) -> Vec<Warning> {
+    let tokens = Lexer::new("<test>", source).tokenize().unwrap();
+    let mut parser = Parser::new(tokens);
+    let stmts = parser.parse().unwrap();
+    let mut checker = Checker::new(Levels::default());
+    checker.check(&stmts, &parser.allows()).unwrap();
+    checker
+        .warnings()
+        .into_iter()
+        .map(|(warning, _)| warning)
+        .collect()
 }
@@ -1,4 +1,8 @@
 pub mod ast;
+pub mod check;
 pub mod error;
 pub mod io;
+pub mod module;
 pub mod position;
+pub mod prelude;
+pub mod runtime;
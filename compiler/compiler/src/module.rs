@@ -0,0 +1,204 @@
+use crate::{
+    ast::{
+        lexer::Lexer,
+        parser::Parser,
+        pattern::path_name,
+        stmt::{Allow, Stmt},
+    },
+    error::*,
+    position::Position,
+};
+use ascii::AsciiString;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+// A `.na` file, named after its path from the project root so `a/b.na` is `a::b`
+pub struct Module {
+    pub name: String,
+    pub stmts: Vec<Stmt>,
+    pub allows: Vec<Allow>,
+}
+
+// Finds, parses and orders the modules a program imports
+pub struct Loader {
+    root: PathBuf,
+    // The items of every loaded module and whether they are `pub`
+    exports: HashMap<String, HashMap<AsciiString, bool>>,
+    // The modules whose imports are being loaded, importers first
+    loading: Vec<String>,
+}
+
+impl Loader {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            exports: HashMap::new(),
+            loading: Vec::new(),
+        }
+    }
+
+    // Loads the modules `stmts` import that were not loaded before, each one
+    // after the modules it imports itself, so they can run in order
+    pub fn load(&mut self, name: &str, stmts: &[Stmt]) -> Result<Vec<Module>> {
+        let mut modules = Vec::new();
+        self.loading = vec![name.to_owned()];
+        let result = self.imports(stmts, &mut modules);
+        self.loading.clear();
+        match result {
+            Ok(()) => Ok(modules),
+            Err(error) => {
+                self.unload(&modules);
+                Err(error)
+            }
+        }
+    }
+
+    // Forgets modules that failed to run, so importing them again retries
+    pub fn unload(&mut self, modules: &[Module]) {
+        for module in modules {
+            self.exports.remove(&module.name);
+        }
+    }
+
+    fn imports(&mut self, stmts: &[Stmt], modules: &mut Vec<Module>) -> Result<()> {
+        for stmt in stmts {
+            let Stmt::Use { path, items, pos } = stmt else {
+                continue;
+            };
+            let name = path_name(path);
+            if !self.exports.contains_key(&name) {
+                self.module(path, &name, pos, modules)?;
+            }
+            let exports = &self.exports[&name];
+            for (item, pos) in items {
+                match exports.get(item) {
+                    Some(true) => {}
+                    Some(false) => Error::PrivateItem(item.to_string(), name.clone()).throw(pos)?,
+                    None => Error::UnknownItem(item.to_string(), name.clone()).throw(pos)?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn module(
+        &mut self,
+        path: &[AsciiString],
+        name: &str,
+        pos: &Position,
+        modules: &mut Vec<Module>,
+    ) -> Result<()> {
+        if let Some(start) = self.loading.iter().position(|module| module == name) {
+            let mut cycle = self.loading[start..].to_vec();
+            cycle.push(name.to_owned());
+            return Error::ImportCycle(cycle).throw(pos);
+        }
+
+        let mut file = self.root.clone();
+        file.extend(path.iter().map(|segment| segment.as_str()));
+        file.set_extension("na");
+        let file = file.display().to_string();
+        let Ok(source) = fs::read_to_string(&file) else {
+            return Error::MissingModule(name.to_owned(), file).throw(pos);
+        };
+        let tokens = Lexer::new(&file, &source).tokenize()?;
+        let mut parser = Parser::new(tokens);
+        let stmts = parser.parse()?;
+
+        self.loading.push(name.to_owned());
+        self.imports(&stmts, modules)?;
+        self.loading.pop();
+
+        let exports = stmts
+            .iter()
+            .filter_map(Stmt::item)
+            .map(|(item, public)| (item.clone(), public))
+            .collect();
+        self.exports.insert(name.to_owned(), exports);
+        modules.push(Module {
+            name: name.to_owned(),
+            stmts,
+            allows: parser.allows(),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Loader, Module};
+    use crate::{
+        ast::{lexer::Lexer, parser::Parser},
+        error::{Error, Result},
+    };
+    use std::{
+        env, fs,
+        path::{Path, PathBuf},
+        process,
+    };
+
+    // A project of its own for each test, since they run in parallel
+    fn project(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = env::temp_dir().join(format!("nai-{test}-{}", process::id()));
+        for (path, source) in files {
+            let file = root.join(path);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, source).unwrap();
+        }
+        root
+    }
+
+    fn load(root: &Path, source: &str) -> Result<Vec<Module>> {
+        let tokens = Lexer::new("main.na", source).tokenize().unwrap();
+        let stmts = Parser::new(tokens).parse().unwrap();
+        Loader::new(root).load("main", &stmts)
+    }
+
+    #[test]
+    fn modules_load_after_the_modules_they_import() {
+        let root = project(
+            "order",
+            &[
+                (
+                    "geo/shapes.na",
+                    "use geo::units::unit; pub fn area() -> i32 { unit() }",
+                ),
+                ("geo/units.na", "pub fn unit() -> i32 { 1 }"),
+            ],
+        );
+        let modules = load(&root, "use geo::shapes::area; use geo::units::unit;").unwrap();
+        let names = modules.iter().map(|module| module.name.as_str());
+        assert_eq!(names.collect::<Vec<_>>(), ["geo::units", "geo::shapes"]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn imports_name_public_items_of_modules_that_exist() {
+        let root = project(
+            "items",
+            &[
+                ("a.na", "use b::y; pub fn x() {}"),
+                ("b.na", "use a::x; pub fn y() {}"),
+                ("c.na", "pub fn shown() {} fn hidden() {}"),
+            ],
+        );
+        let cycle = match load(&root, "use a::x;") {
+            Err(Error::ImportCycle(cycle)) => cycle,
+            _ => Vec::new(),
+        };
+        assert_eq!(cycle, ["a", "b", "a"]);
+        let missing = load(&root, "use nope::x;");
+        assert!(matches!(missing, Err(Error::MissingModule(..))));
+        let private = load(&root, "use c::{shown, hidden};");
+        assert!(matches!(private, Err(Error::PrivateItem(item, _)) if item == "hidden"));
+        assert!(matches!(
+            load(&root, "use c::other;"),
+            Err(Error::UnknownItem(..))
+        ));
+        assert!(load(&root, "use c::shown;").is_ok());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
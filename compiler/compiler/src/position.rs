@@ -1,10 +1,10 @@
 use std::fmt::Debug;
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Position {
     file: String,
     line: u16,
-    column: u8,
+    column: u16,
 }
 
 pub enum Next {
@@ -14,9 +14,13 @@ pub enum Next {
 
 impl Position {
     pub fn new(file: &str) -> Self {
+        Self::at_line(file, 1)
+    }
+
+    pub fn at_line(file: &str, line: u16) -> Self {
         Self {
             file: file.to_owned(),
-            line: 1,
+            line,
             column: 1,
         }
     }
@@ -29,17 +33,24 @@ impl Position {
         self.line
     }
 
-    pub const fn column(&self) -> u8 {
+    pub const fn column(&self) -> u16 {
         self.column
     }
 
+    // Whether the position is in the source from `start` to `end`, both included
+    pub fn within(&self, start: &Self, end: &Self) -> bool {
+        let at = (self.line, self.column);
+        self.file == start.file && (start.line, start.column) <= at && at <= (end.line, end.column)
+    }
+
+    // Positions past the last line or column a position holds stay at it
     pub fn next(&mut self, by: Next) {
         match by {
             Next::Line => {
-                self.line += 1;
+                self.line = self.line.saturating_add(1);
                 self.column = 1
             }
-            Next::Column => self.column += 1,
+            Next::Column => self.column = self.column.saturating_add(1),
         }
     }
 }
@@ -55,3 +66,23 @@ impl Debug for Position {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Next, Position};
+
+    #[test]
+    fn long_lines_keep_their_columns() {
+        let mut pos = Position::new("test");
+        for _ in 0..300 {
+            pos.next(Next::Column);
+        }
+        assert_eq!(pos.column(), 301);
+        for _ in 0..u16::MAX {
+            pos.next(Next::Column);
+        }
+        assert_eq!(pos.column(), u16::MAX);
+        pos.next(Next::Line);
+        assert_eq!((pos.line(), pos.column()), (2, 1));
+    }
+}
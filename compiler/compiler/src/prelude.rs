@@ -0,0 +1,116 @@
+use crate::ast::{
+    lexer::Lexer,
+    parser::Parser,
+    stmt::{EnumDecl, Stmt, TraitDecl},
+    token::Operator,
+};
+use std::rc::Rc;
+
+const SOURCE: &str = include_str!("prelude.na");
+
+// The trait and the method an operator calls on the values of other types
+pub type Overload = (&'static str, &'static str);
+
+pub const NEG: Overload = ("Neg", "neg");
+pub const INDEX: Overload = ("Index", "index");
+
+thread_local! {
+    // Parsed once, so the checker and the interpreter of every module share
+    // the same declarations
+    static STMTS: Rc<[Stmt]> = {
+        let tokens = Lexer::new("<prelude>", SOURCE).tokenize();
+        let stmts = tokens.and_then(|tokens| Parser::new(tokens).parse());
+        Rc::from(stmts.expect("the prelude parses"))
+    };
+}
+
+// The items declared in every module before its own
+pub fn stmts() -> Rc<[Stmt]> {
+    STMTS.with(Rc::clone)
+}
+
+pub fn trait_decl(name: &str) -> Rc<TraitDecl> {
+    stmts()
+        .iter()
+        .find_map(|stmt| match stmt {
+            Stmt::Trait { decl } if decl.name == name => Some(Rc::clone(decl)),
+            _ => None,
+        })
+        .expect("the prelude declares the trait")
+}
+
+pub fn enum_decl(name: &str) -> Rc<EnumDecl> {
+    stmts()
+        .iter()
+        .find_map(|stmt| match stmt {
+            Stmt::Enum { decl } if decl.name == name => Some(Rc::clone(decl)),
+            _ => None,
+        })
+        .expect("the prelude declares the enum")
+}
+
+// Whether the trait is one of the standard ones rather than one of the same name
+pub fn is_standard(decl: &TraitDecl) -> bool {
+    stmts()
+        .iter()
+        .any(|stmt| matches!(stmt, Stmt::Trait { decl: standard } if standard.pos == decl.pos))
+}
+
+// Comparisons call `eq` and `cmp`, `!=` negates `eq`
+pub fn binary(operator: Operator) -> Option<Overload> {
+    Some(match operator {
+        Operator::Plus => ("Add", "add"),
+        Operator::Minus => ("Sub", "sub"),
+        Operator::Asterisk => ("Mul", "mul"),
+        Operator::Slash => ("Div", "div"),
+        Operator::Percent => ("Rem", "rem"),
+        Operator::EqualTo | Operator::NotEqualTo => ("Eq", "eq"),
+        Operator::LessThan
+        | Operator::LessThanOrEqualTo
+        | Operator::GreaterThan
+        | Operator::GreaterThanOrEqualTo => ("Ord", "cmp"),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{binary, is_standard, trait_decl};
+    use crate::{ast::token::Operator, error::Error, io::run};
+
+    #[test]
+    fn operators_call_the_methods_of_standard_traits() {
+        assert_eq!(binary(Operator::NotEqualTo), Some(("Eq", "eq")));
+        assert_eq!(binary(Operator::GreaterThanOrEqualTo), Some(("Ord", "cmp")));
+        assert_eq!(binary(Operator::And), None);
+        assert!(is_standard(&trait_decl("Index")));
+    }
+
+    #[test]
+    fn user_types_overload_operators() {
+        let v = "struct V { x: i32, y: i32 }
+            impl Add for V {
+                fn add(self, other: V) -> V { V { x: self.x + other.x, y: self.y + other.y } }
+            }
+            impl Neg for V { fn neg(self) -> V { V { x: -self.x, y: -self.y } } }
+            impl Eq for V { fn eq(self, other: V) -> bool { self.x == other.x } }
+            impl Ord for V { fn cmp(self, other: V) -> Ordering { self.x.cmp(other.x) } }
+            impl Index for V {
+                fn index(self, i: i32) -> i32 { if i == 0 { self.x } else { self.y } }
+            }
+            let a = V { x: 1, y: 2 };
+            let b = V { x: 3, y: 4 };";
+        let source =
+            format!("{v} (a + b, -a, a == V {{ x: 1, y: 9 }}, a != b, a < b, a >= b, b[1])");
+        let shown = "(V { x: 4, y: 6 }, V { x: -1, y: -2 }, true, true, true, false, 4)";
+        assert_eq!(run(&source).unwrap(), shown);
+        let unimplemented =
+            |source: &str| matches!(run(source), Err(Error::OperatorNotImplemented(..)));
+        let w = "struct W {}";
+        assert!(unimplemented(&format!("{w} W {{}} + W {{}}")));
+        assert!(unimplemented(&format!("{w} W {{}} < W {{}}")));
+        assert!(unimplemented(&format!("{w} W {{}}[0]")));
+        // Without `Eq` values are compared field by field
+        assert_eq!(run(&format!("{w} W {{}} == W {{}}")).unwrap(), "true");
+    }
+}
@@ -0,0 +1,385 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+// Each digit of the magnitude holds nine decimal digits, which keeps printing
+// and parsing exact and the product of two digits within a `u64`
+const BASE: u64 = 1_000_000_000;
+const BASE_DIGITS: usize = 9;
+
+// An integer of any size, the value of the `bigint` number type. The digits of
+// the magnitude go from the least significant, without leading zeros, so zero
+// has none and is never negative.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BigInt {
+    negative: bool,
+    digits: Vec<u32>,
+}
+
+impl BigInt {
+    fn new(negative: bool, mut digits: Vec<u32>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        let negative = negative && !digits.is_empty();
+        Self { negative, digits }
+    }
+
+    // Decimal digits with an optional sign, `None` for anything else
+    pub fn parse(number: &str) -> Option<Self> {
+        let (negative, number) = match number.strip_prefix('-') {
+            Some(number) => (true, number),
+            None => (false, number),
+        };
+        if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let digits = number
+            .as_bytes()
+            .rchunks(BASE_DIGITS)
+            .map(|chunk| chunk.iter().fold(0, |n, b| n * 10 + u32::from(b - b'0')))
+            .collect();
+        Some(Self::new(negative, digits))
+    }
+
+    pub fn from_i128(n: i128) -> Self {
+        let magnitude = Self::from_u128(n.unsigned_abs());
+        Self::new(n < 0, magnitude.digits)
+    }
+
+    pub fn from_u128(mut n: u128) -> Self {
+        let mut digits = Vec::new();
+        while n > 0 {
+            digits.push((n % u128::from(BASE)) as u32);
+            n /= u128::from(BASE);
+        }
+        Self::new(false, digits)
+    }
+
+    // The integer part of a float, `None` for NaN and infinities
+    pub fn from_f64(n: f64) -> Option<Self> {
+        if !n.is_finite() {
+            return None;
+        }
+        let n = n.trunc();
+        if n.abs() < 2f64.powi(127) {
+            return Some(Self::from_i128(n as i128));
+        }
+        // Floats this large are their 53-bit mantissa times a power of two
+        let bits = n.to_bits();
+        let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+        let exponent = ((bits >> 52) & 0x7ff) as i32 - 1075;
+        let mut magnitude = Self::from_u128(u128::from(mantissa));
+        let step = Self::from_u128(1 << 64);
+        for _ in 0..exponent / 64 {
+            magnitude = &magnitude * &step;
+        }
+        magnitude = &magnitude * &Self::from_u128(1 << (exponent % 64));
+        Some(if n < 0.0 { -magnitude } else { magnitude })
+    }
+
+    // The lowest 128 bits of the two's complement, which `as` keeps when
+    // casting to a narrower integer
+    pub fn wrapping_u128(&self) -> u128 {
+        let magnitude = self.digits.iter().rev().fold(0u128, |n, &digit| {
+            n.wrapping_mul(u128::from(BASE))
+                .wrapping_add(u128::from(digit))
+        });
+        if self.negative {
+            magnitude.wrapping_neg()
+        } else {
+            magnitude
+        }
+    }
+
+    // The nearest float, infinite beyond the range of `f64`
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub const fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    // The quotient rounded towards zero and the remainder with the sign of
+    // `self`, as integer division does, `None` when dividing by zero
+    pub fn div_rem(&self, rhs: &Self) -> Option<(Self, Self)> {
+        if rhs.is_zero() {
+            return None;
+        }
+        let mut quotient = vec![0; self.digits.len()];
+        let mut remainder = Self::default();
+        let divisor = rhs.abs();
+        for (i, &digit) in self.digits.iter().enumerate().rev() {
+            // Shifting in the next digit leaves a remainder less than
+            // `BASE * divisor`, so the next digit of the quotient is below
+            // `BASE` and found by bisection
+            remainder.digits.insert(0, digit);
+            remainder = Self::new(false, remainder.digits);
+            let (mut low, mut high) = (0, BASE as u32 - 1);
+            while low < high {
+                let middle = low + (high - low).div_ceil(2);
+                if divisor.times(middle) <= remainder {
+                    low = middle;
+                } else {
+                    high = middle - 1;
+                }
+            }
+            remainder = &remainder - &divisor.times(low);
+            quotient[i] = low;
+        }
+        let quotient = Self::new(self.negative != rhs.negative, quotient);
+        let remainder = Self::new(self.negative, remainder.digits);
+        Some((quotient, remainder))
+    }
+
+    // `self * 10^exponent`
+    pub fn scale_up(&self, exponent: u32) -> Self {
+        let exponent = exponent as usize;
+        let mut digits = vec![0; exponent / BASE_DIGITS];
+        digits.extend(&self.digits);
+        Self::new(self.negative, digits).times(10u32.pow((exponent % BASE_DIGITS) as u32))
+    }
+
+    pub fn abs(&self) -> Self {
+        Self::new(false, self.digits.clone())
+    }
+
+    // The product with a single digit
+    fn times(&self, factor: u32) -> Self {
+        let mut digits = Vec::with_capacity(self.digits.len() + 1);
+        let mut carry = 0;
+        for &digit in &self.digits {
+            let product = u64::from(digit) * u64::from(factor) + carry;
+            digits.push((product % BASE) as u32);
+            carry = product / BASE;
+        }
+        digits.push(carry as u32);
+        Self::new(self.negative, digits)
+    }
+
+    // The sum of the magnitudes, or their difference when `subtract` is set.
+    // The difference takes the sign of the larger magnitude.
+    fn combine(&self, rhs: &Self, subtract: bool) -> Self {
+        if !subtract {
+            let mut digits = Vec::with_capacity(self.digits.len().max(rhs.digits.len()) + 1);
+            let mut carry = 0;
+            for i in 0..self.digits.len().max(rhs.digits.len()) {
+                let sum = u64::from(self.digit(i)) + u64::from(rhs.digit(i)) + carry;
+                digits.push((sum % BASE) as u32);
+                carry = sum / BASE;
+            }
+            digits.push(carry as u32);
+            return Self::new(self.negative, digits);
+        }
+        let (larger, smaller) = match self.compare_magnitude(rhs) {
+            Ordering::Less => (rhs, self),
+            _ => (self, rhs),
+        };
+        let mut digits = Vec::with_capacity(larger.digits.len());
+        let mut borrow = 0;
+        for i in 0..larger.digits.len() {
+            let mut difference = i64::from(larger.digit(i)) - i64::from(smaller.digit(i)) - borrow;
+            borrow = i64::from(difference < 0);
+            if difference < 0 {
+                difference += BASE as i64;
+            }
+            digits.push(difference as u32);
+        }
+        Self::new(larger.negative, digits)
+    }
+
+    fn digit(&self, i: usize) -> u32 {
+        self.digits.get(i).copied().unwrap_or(0)
+    }
+
+    fn compare_magnitude(&self, rhs: &Self) -> Ordering {
+        self.digits
+            .len()
+            .cmp(&rhs.digits.len())
+            .then_with(|| self.digits.iter().rev().cmp(rhs.digits.iter().rev()))
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, rhs: Self) -> BigInt {
+        self.combine(rhs, self.negative != rhs.negative)
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, rhs: Self) -> BigInt {
+        self + &-rhs.clone()
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, rhs: Self) -> BigInt {
+        let mut digits = vec![0u64; self.digits.len() + rhs.digits.len() + 1];
+        for (i, &a) in self.digits.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &b) in rhs.digits.iter().enumerate() {
+                let product = digits[i + j] + u64::from(a) * u64::from(b) + carry;
+                digits[i + j] = product % BASE;
+                carry = product / BASE;
+            }
+            digits[i + rhs.digits.len()] += carry;
+        }
+        let digits = digits.into_iter().map(|digit| digit as u32).collect();
+        BigInt::new(self.negative != rhs.negative, digits)
+    }
+}
+
+impl Neg for BigInt {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        let negative = !self.negative;
+        Self::new(negative, self.digits)
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => self.compare_magnitude(other),
+            (true, true) => other.compare_magnitude(self),
+        }
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some((last, rest)) = self.digits.split_last() else {
+            return write!(f, "0");
+        };
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{last}")?;
+        for digit in rest.iter().rev() {
+            write!(f, "{digit:09}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BigInt;
+
+    fn big(n: &str) -> BigInt {
+        BigInt::parse(n).unwrap()
+    }
+
+    #[test]
+    fn parses_and_prints_every_digit() {
+        for n in [
+            "0",
+            "7",
+            "-7",
+            "1000000000",
+            "-123456789012345678901234567890",
+        ] {
+            assert_eq!(big(n).to_string(), n);
+        }
+        assert_eq!(big("-0").to_string(), "0");
+        assert_eq!(big("000042").to_string(), "42");
+        assert!(BigInt::parse("").is_none());
+        assert!(BigInt::parse("-").is_none());
+        assert!(BigInt::parse("1.5").is_none());
+    }
+
+    #[test]
+    fn carries_and_borrows_across_digits() {
+        assert_eq!((&big("999999999") + &big("1")).to_string(), "1000000000");
+        assert_eq!((&big("1000000000") - &big("1")).to_string(), "999999999");
+        assert_eq!((&big("5") - &big("12")).to_string(), "-7");
+        assert_eq!((&big("-5") + &big("12")).to_string(), "7");
+        assert_eq!((&big("-5") - &big("-5")).to_string(), "0");
+        assert!(!(&big("-5") - &big("-5")).is_negative());
+    }
+
+    #[test]
+    fn multiplies_beyond_128_bits() {
+        let n = big("340282366920938463463374607431768211455");
+        assert_eq!(
+            (&n * &n).to_string(),
+            "115792089237316195423570985008687907852589419931798687112530834793049593217025"
+        );
+        assert_eq!((&big("-3") * &big("4")).to_string(), "-12");
+        assert_eq!((&big("-3") * &big("0")).to_string(), "0");
+    }
+
+    #[test]
+    fn divides_towards_zero() {
+        let div_rem = |a: &str, b: &str| {
+            let (quotient, remainder) = big(a).div_rem(&big(b)).unwrap();
+            (quotient.to_string(), remainder.to_string())
+        };
+        assert_eq!(div_rem("7", "2"), ("3".into(), "1".into()));
+        assert_eq!(div_rem("-7", "2"), ("-3".into(), "-1".into()));
+        assert_eq!(div_rem("7", "-2"), ("-3".into(), "1".into()));
+        assert_eq!(div_rem("-7", "-2"), ("3".into(), "-1".into()));
+        assert_eq!(
+            div_rem(
+                "100000000000000000000000000000000000000007",
+                "100000000000000000000"
+            ),
+            ("1000000000000000000000".into(), "7".into())
+        );
+        assert!(big("1").div_rem(&big("0")).is_none());
+    }
+
+    #[test]
+    fn converts_from_and_to_machine_numbers() {
+        assert_eq!(
+            BigInt::from_i128(i128::MIN).to_string(),
+            i128::MIN.to_string()
+        );
+        assert_eq!(
+            BigInt::from_u128(u128::MAX).to_string(),
+            u128::MAX.to_string()
+        );
+        assert_eq!(big("-1").wrapping_u128(), u128::MAX);
+        assert_eq!(
+            big("340282366920938463463374607431768211457").wrapping_u128(),
+            1
+        );
+        assert_eq!(BigInt::from_f64(-2.9).unwrap().to_string(), "-2");
+        assert_eq!(
+            BigInt::from_f64(2f64.powi(200)).unwrap().to_string(),
+            "1606938044258990275541962092341162602522202993782792835301376"
+        );
+        assert!(BigInt::from_f64(f64::NAN).is_none());
+        assert!(BigInt::from_f64(f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn orders_by_value() {
+        assert!(big("-10") < big("-9"));
+        assert!(big("-1") < big("0"));
+        assert!(big("1000000000") > big("999999999"));
+        assert_eq!(big("10").scale_up(20).to_string(), "1000000000000000000000");
+    }
+}
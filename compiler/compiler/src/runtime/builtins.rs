@@ -0,0 +1,415 @@
+use super::{
+    environment::Env,
+    interpreter::{binary, Interpreter},
+    iterator::Iter,
+    map::{Key, Map},
+    value::Value,
+};
+use crate::{
+    ast::token::{Number, Operator},
+    error::*,
+    position::Position,
+};
+use ascii::AsciiString;
+use std::cmp::Ordering;
+
+pub type NativeFn = fn(&mut Interpreter, Vec<Value>, &Position) -> Result<Value>;
+
+#[derive(Clone)]
+pub struct Native {
+    pub name: &'static str,
+    // `None` accepts any number of arguments
+    pub arity: Option<usize>,
+    pub func: NativeFn,
+}
+
+const NATIVES: [Native; 1] = [Native {
+    name: "print",
+    arity: None,
+    func: print,
+}];
+
+// Methods take their receiver as the first argument, which `arity` does not count
+const ARRAY_METHODS: [Native; 3] = [
+    Native {
+        name: "push",
+        arity: Some(1),
+        func: push,
+    },
+    Native {
+        name: "pop",
+        arity: Some(0),
+        func: pop,
+    },
+    Native {
+        name: "len",
+        arity: Some(0),
+        func: len,
+    },
+];
+
+const MAP_METHODS: [Native; 7] = [
+    Native {
+        name: "get",
+        arity: Some(1),
+        func: get,
+    },
+    Native {
+        name: "insert",
+        arity: Some(2),
+        func: insert,
+    },
+    Native {
+        name: "remove",
+        arity: Some(1),
+        func: remove,
+    },
+    Native {
+        name: "contains",
+        arity: Some(1),
+        func: contains,
+    },
+    Native {
+        name: "len",
+        arity: Some(0),
+        func: len,
+    },
+    Native {
+        name: "keys",
+        arity: Some(0),
+        func: keys,
+    },
+    Native {
+        name: "values",
+        arity: Some(0),
+        func: values,
+    },
+];
+
+const STRING_METHODS: [Native; 1] = [Native {
+    name: "len",
+    arity: Some(0),
+    func: len,
+}];
+
+// `to_u8` and the like convert a number to the type they name, `?` when the
+// value would change, see `Number::convert`
+macro_rules! conversions {
+    ($($name:literal => $ty:literal),*) => {
+        [$(Native {
+            name: $name,
+            arity: Some(0),
+            func: |_, arguments, _| Ok(convert(&arguments[0], $ty)),
+        }),*]
+    };
+}
+
+const NUMBER_METHODS: [Native; 16] = conversions![
+    "to_i8" => "i8",
+    "to_i16" => "i16",
+    "to_i32" => "i32",
+    "to_i64" => "i64",
+    "to_i128" => "i128",
+    "to_isize" => "isize",
+    "to_u8" => "u8",
+    "to_u16" => "u16",
+    "to_u32" => "u32",
+    "to_u64" => "u64",
+    "to_u128" => "u128",
+    "to_usize" => "usize",
+    "to_f32" => "f32",
+    "to_f64" => "f64",
+    "to_bigint" => "bigint",
+    "to_decimal" => "decimal"
+];
+
+macro_rules! operators {
+    ($($name:literal => $operator:ident),*) => {
+        [$(Native {
+            name: $name,
+            arity: Some(1),
+            func: |_, arguments, pos| binary(&arguments[0], Operator::$operator, &arguments[1], pos),
+        }),*]
+    };
+}
+
+// The methods of the standard traits the builtin types implement, which do
+// what the operators calling them do
+const STANDARD_METHODS: [Native; 8] = {
+    let [add, sub, mul, div, rem, eq] = operators![
+        "add" => Plus,
+        "sub" => Minus,
+        "mul" => Asterisk,
+        "div" => Slash,
+        "rem" => Percent,
+        "eq" => EqualTo
+    ];
+    let neg = Native {
+        name: "neg",
+        arity: Some(0),
+        func: neg,
+    };
+    let cmp = Native {
+        name: "cmp",
+        arity: Some(1),
+        func: cmp,
+    };
+    [add, sub, mul, div, rem, neg, eq, cmp]
+};
+
+// Shared by everything a `loop` can iterate, the function is called for each item
+const ITERATOR_METHODS: [Native; 3] = [
+    Native {
+        name: "map",
+        arity: Some(1),
+        func: map,
+    },
+    Native {
+        name: "filter",
+        arity: Some(1),
+        func: filter,
+    },
+    Native {
+        name: "fold",
+        arity: Some(2),
+        func: fold,
+    },
+];
+
+pub fn method(receiver: &Value, name: &str) -> Option<Native> {
+    let methods: &[Native] = match receiver {
+        Value::Array(_) => &ARRAY_METHODS,
+        Value::Map(_) => &MAP_METHODS,
+        Value::String(_) => &STRING_METHODS,
+        Value::Number(_) => &NUMBER_METHODS,
+        _ => &[],
+    };
+    let iterable = matches!(
+        receiver,
+        Value::Array(_) | Value::Map(_) | Value::String(_) | Value::Range(_)
+    );
+    let iterator: &[Native] = if iterable { &ITERATOR_METHODS } else { &[] };
+    let standard = STANDARD_METHODS
+        .iter()
+        .filter(|method| implements(receiver, method.name));
+    methods
+        .iter()
+        .chain(iterator)
+        .chain(standard)
+        .find(|method| method.name == name)
+        .cloned()
+}
+
+// Whether the builtin type of the receiver implements the standard trait of
+// the method, as the checker has it
+fn implements(receiver: &Value, method: &str) -> bool {
+    match receiver {
+        Value::Number(_) => true,
+        Value::String(_) => matches!(method, "add" | "eq" | "cmp"),
+        Value::Char(_) => matches!(method, "eq" | "cmp"),
+        Value::Boolean(_) => method == "eq",
+        _ => false,
+    }
+}
+
+pub fn names() -> impl Iterator<Item = &'static str> {
+    NATIVES.iter().map(|native| native.name)
+}
+
+pub fn define(globals: &Env) {
+    for native in NATIVES {
+        globals.borrow_mut().define(
+            AsciiString::from_ascii(native.name).unwrap(),
+            Value::Native(native),
+        );
+    }
+}
+
+fn print(_: &mut Interpreter, arguments: Vec<Value>, _: &Position) -> Result<Value> {
+    let line = arguments
+        .iter()
+        .map(|argument| argument.to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    println!("{line}");
+    Ok(Value::Unit)
+}
+
+fn push(_: &mut Interpreter, arguments: Vec<Value>, _: &Position) -> Result<Value> {
+    let mut arguments = arguments.into_iter();
+    if let (Some(Value::Array(array)), Some(value)) = (arguments.next(), arguments.next()) {
+        array.borrow_mut().push(value);
+    }
+    Ok(Value::Unit)
+}
+
+fn pop(_: &mut Interpreter, arguments: Vec<Value>, _: &Position) -> Result<Value> {
+    Ok(match &arguments[0] {
+        Value::Array(array) => array.borrow_mut().pop().unwrap_or(Value::Null),
+        _ => Value::Null,
+    })
+}
+
+fn len(_: &mut Interpreter, arguments: Vec<Value>, _: &Position) -> Result<Value> {
+    Ok(Value::Number(Number::USize(match &arguments[0] {
+        Value::Array(array) => array.borrow().len(),
+        Value::Map(map) => map.borrow().len(),
+        Value::String(s) => s.chars().count(),
+        _ => 0,
+    })))
+}
+
+fn neg(_: &mut Interpreter, arguments: Vec<Value>, pos: &Position) -> Result<Value> {
+    match &arguments[0] {
+        Value::Number(n) => match n.negate() {
+            Ok(n) => Ok(Value::Number(n)),
+            Err(error) => error.throw(pos),
+        },
+        value => Error::InvalidOperand(String::from("-"), value.type_name()).throw(pos),
+    }
+}
+
+// Values neither less than nor equal to the other, like NaN, are greater
+fn cmp(interpreter: &mut Interpreter, arguments: Vec<Value>, pos: &Position) -> Result<Value> {
+    let (a, b) = (&arguments[0], &arguments[1]);
+    let ordering = match (
+        binary(a, Operator::LessThan, b, pos)?,
+        binary(a, Operator::EqualTo, b, pos)?,
+    ) {
+        (Value::Boolean(true), _) => Ordering::Less,
+        (_, Value::Boolean(true)) => Ordering::Equal,
+        _ => Ordering::Greater,
+    };
+    Ok(interpreter.ordering(ordering))
+}
+
+fn convert(number: &Value, ty: &str) -> Value {
+    match number {
+        Value::Number(n) => n.convert(ty).map_or(Value::Null, Value::Number),
+        _ => Value::Null,
+    }
+}
+
+// Calls `f` with the map receiver and the key, both taken from the arguments
+fn with_key<T>(
+    arguments: &[Value],
+    pos: &Position,
+    f: impl FnOnce(&mut Map, Key) -> T,
+) -> Result<Option<T>> {
+    match &arguments[0] {
+        Value::Map(map) => Ok(Some(f(
+            &mut map.borrow_mut(),
+            Key::new(&arguments[1], pos)?,
+        ))),
+        _ => Ok(None),
+    }
+}
+
+fn get(_: &mut Interpreter, arguments: Vec<Value>, pos: &Position) -> Result<Value> {
+    let value = with_key(&arguments, pos, |map, key| map.get(&key).cloned())?;
+    Ok(value.flatten().unwrap_or(Value::Null))
+}
+
+fn insert(_: &mut Interpreter, mut arguments: Vec<Value>, pos: &Position) -> Result<Value> {
+    let value = arguments.pop().unwrap();
+    let previous = with_key(&arguments, pos, |map, key| map.insert(key, value))?;
+    Ok(previous.flatten().unwrap_or(Value::Null))
+}
+
+fn remove(_: &mut Interpreter, arguments: Vec<Value>, pos: &Position) -> Result<Value> {
+    let value = with_key(&arguments, pos, |map, key| map.remove(&key))?;
+    Ok(value.flatten().unwrap_or(Value::Null))
+}
+
+fn contains(_: &mut Interpreter, arguments: Vec<Value>, pos: &Position) -> Result<Value> {
+    let contains = with_key(&arguments, pos, |map, key| map.get(&key).is_some())?;
+    Ok(Value::Boolean(contains.unwrap_or(false)))
+}
+
+fn keys(_: &mut Interpreter, arguments: Vec<Value>, _: &Position) -> Result<Value> {
+    Ok(match &arguments[0] {
+        Value::Map(map) => {
+            Value::array(map.borrow().entries().map(|(key, _)| key.value()).collect())
+        }
+        _ => Value::Null,
+    })
+}
+
+// Calls `f` with each item of the receiver
+fn each(
+    interpreter: &mut Interpreter,
+    receiver: Value,
+    pos: &Position,
+    mut f: impl FnMut(&mut Interpreter, Value) -> Result<()>,
+) -> Result<()> {
+    let mut items = Iter::new(receiver, pos)?;
+    while let Some(item) = items.next(interpreter, pos)? {
+        f(interpreter, item)?;
+    }
+    Ok(())
+}
+
+fn map(interpreter: &mut Interpreter, arguments: Vec<Value>, pos: &Position) -> Result<Value> {
+    let [receiver, function] = <[Value; 2]>::try_from(arguments).ok().unwrap();
+    let mut mapped = Vec::new();
+    each(interpreter, receiver, pos, |interpreter, item| {
+        mapped.push(interpreter.call(function.clone(), vec![item], pos)?);
+        Ok(())
+    })?;
+    Ok(Value::array(mapped))
+}
+
+fn filter(interpreter: &mut Interpreter, arguments: Vec<Value>, pos: &Position) -> Result<Value> {
+    let [receiver, predicate] = <[Value; 2]>::try_from(arguments).ok().unwrap();
+    let mut kept = Vec::new();
+    each(interpreter, receiver, pos, |interpreter, item| {
+        match interpreter.call(predicate.clone(), vec![item.clone()], pos)? {
+            Value::Boolean(true) => kept.push(item),
+            Value::Boolean(false) => {}
+            value => return Error::InvalidCondition(value.type_name()).throw(pos),
+        }
+        Ok(())
+    })?;
+    Ok(Value::array(kept))
+}
+
+fn fold(interpreter: &mut Interpreter, arguments: Vec<Value>, pos: &Position) -> Result<Value> {
+    let [receiver, initial, function] = <[Value; 3]>::try_from(arguments).ok().unwrap();
+    let mut accumulator = initial;
+    each(interpreter, receiver, pos, |interpreter, item| {
+        let previous = std::mem::replace(&mut accumulator, Value::Unit);
+        accumulator = interpreter.call(function.clone(), vec![previous, item], pos)?;
+        Ok(())
+    })?;
+    Ok(accumulator)
+}
+
+fn values(_: &mut Interpreter, arguments: Vec<Value>, _: &Position) -> Result<Value> {
+    Ok(match &arguments[0] {
+        Value::Map(map) => Value::array(
+            map.borrow()
+                .entries()
+                .map(|(_, value)| value.clone())
+                .collect(),
+        ),
+        _ => Value::Null,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::io::run;
+
+    #[test]
+    fn arrays_grow_and_shrink_at_their_end() {
+        let source = "let mut a = [1, 2, 3]; a.push(4); let last = a.pop(); (a, last, a.len())";
+        assert_eq!(run(source).unwrap(), "([1, 2, 3], 4, 3)");
+        assert_eq!(
+            run("let mut a: [i32] = []; (a.pop(), a.len())").unwrap(),
+            "(?, 0)"
+        );
+        // Arrays are shared, not copied
+        let source = "let a = [1, 2]; let mut b = a; b.push(3); a";
+        assert_eq!(run(source).unwrap(), "[1, 2, 3]");
+    }
+}
@@ -0,0 +1,276 @@
+use super::bigint::BigInt;
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+// The fractional digits a quotient keeps beyond the ones of its operands
+const DIVISION_DIGITS: u32 = 28;
+
+// An exact decimal number, the value of the `decimal` number type. It is
+// `mantissa / 10^scale` and keeps the digits it is written with, so `1.10d`
+// prints as `1.10` while comparing equal to `1.1d`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Decimal {
+    mantissa: BigInt,
+    scale: u32,
+}
+
+impl Decimal {
+    // Decimal digits with an optional sign and fraction, `None` for anything else
+    pub fn parse(number: &str) -> Option<Self> {
+        let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if fraction.starts_with(['-', '+']) {
+            return None;
+        }
+        let mantissa = BigInt::parse(&format!("{integer}{fraction}"))?;
+        let scale = u32::try_from(fraction.len()).ok()?;
+        Some(Self { mantissa, scale })
+    }
+
+    pub const fn from_bigint(mantissa: BigInt) -> Self {
+        Self { mantissa, scale: 0 }
+    }
+
+    // The shortest decimal the float prints as, so `0.1f64` becomes `0.1`
+    // rather than the binary fraction closest to it. `None` for NaN and
+    // infinities.
+    pub fn from_float(n: impl Display) -> Option<Self> {
+        Self::parse(&n.to_string())
+    }
+
+    // The integer part, dropping the fraction
+    pub fn trunc(&self) -> BigInt {
+        let divisor = BigInt::from_u128(1).scale_up(self.scale);
+        self.mantissa.div_rem(&divisor).unwrap().0
+    }
+
+    // The nearest float, infinite beyond the range of `f64`
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa.is_zero()
+    }
+
+    pub const fn is_negative(&self) -> bool {
+        self.mantissa.is_negative()
+    }
+
+    // The same value without trailing zeros in the fraction, the one a map
+    // key of the value is stored as
+    pub fn normalized(&self) -> Self {
+        let ten = BigInt::from_u128(10);
+        let mut normalized = self.clone();
+        while normalized.scale > 0 {
+            match normalized.mantissa.div_rem(&ten) {
+                Some((quotient, remainder)) if remainder.is_zero() => {
+                    normalized.mantissa = quotient;
+                    normalized.scale -= 1;
+                }
+                _ => break,
+            }
+        }
+        normalized
+    }
+
+    // The quotient with up to 28 more fractional digits than the operands,
+    // rounded half to even, without trailing zeros beyond the digits of the
+    // operands. `None` when dividing by zero.
+    pub fn checked_div(&self, rhs: &Self) -> Option<Self> {
+        let scale = self.scale.max(rhs.scale) + DIVISION_DIGITS;
+        let dividend = self.mantissa.scale_up(scale - self.scale + rhs.scale);
+        let (mut quotient, remainder) = dividend.div_rem(&rhs.mantissa)?;
+        let twice = &remainder.abs() + &remainder.abs();
+        let odd = !quotient.div_rem(&BigInt::from_u128(2)).unwrap().1.is_zero();
+        if twice > rhs.mantissa.abs() || twice == rhs.mantissa.abs() && odd {
+            let one = BigInt::from_u128(1);
+            quotient = if dividend.is_negative() != rhs.is_negative() {
+                &quotient - &one
+            } else {
+                &quotient + &one
+            };
+        }
+        let mut quotient = Self {
+            mantissa: quotient,
+            scale,
+        }
+        .normalized();
+        if quotient.scale < self.scale.max(rhs.scale) {
+            quotient = quotient.rescale(self.scale.max(rhs.scale));
+        }
+        Some(quotient)
+    }
+
+    // The remainder of the division rounded towards zero, with the sign of
+    // `self`, `None` when dividing by zero
+    pub fn checked_rem(&self, rhs: &Self) -> Option<Self> {
+        let scale = self.scale.max(rhs.scale);
+        let (_, remainder) = self
+            .rescale(scale)
+            .mantissa
+            .div_rem(&rhs.rescale(scale).mantissa)?;
+        Some(Self {
+            mantissa: remainder,
+            scale,
+        })
+    }
+
+    // Compares the values, which `==` does not since it tells apart the
+    // digits they are written with
+    pub fn compare(&self, rhs: &Self) -> Ordering {
+        let (a, b) = self.aligned(rhs);
+        a.mantissa.cmp(&b.mantissa)
+    }
+
+    // The same value with `scale` fractional digits, which is at least its own
+    fn rescale(&self, scale: u32) -> Self {
+        Self {
+            mantissa: self.mantissa.scale_up(scale - self.scale),
+            scale,
+        }
+    }
+
+    // Both values with as many fractional digits as the longer of the two
+    fn aligned(&self, rhs: &Self) -> (Self, Self) {
+        let scale = self.scale.max(rhs.scale);
+        (self.rescale(scale), rhs.rescale(scale))
+    }
+}
+
+impl Add for &Decimal {
+    type Output = Decimal;
+
+    fn add(self, rhs: Self) -> Decimal {
+        let (a, b) = self.aligned(rhs);
+        Decimal {
+            mantissa: &a.mantissa + &b.mantissa,
+            scale: a.scale,
+        }
+    }
+}
+
+impl Sub for &Decimal {
+    type Output = Decimal;
+
+    fn sub(self, rhs: Self) -> Decimal {
+        let (a, b) = self.aligned(rhs);
+        Decimal {
+            mantissa: &a.mantissa - &b.mantissa,
+            scale: a.scale,
+        }
+    }
+}
+
+impl Mul for &Decimal {
+    type Output = Decimal;
+
+    fn mul(self, rhs: Self) -> Decimal {
+        Decimal {
+            mantissa: &self.mantissa * &rhs.mantissa,
+            scale: self.scale + rhs.scale,
+        }
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            mantissa: -self.mantissa,
+            scale: self.scale,
+        }
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digits = self.mantissa.abs().to_string();
+        let scale = self.scale as usize;
+        // At least one digit before the point
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        if self.is_negative() {
+            write!(f, "-")?;
+        }
+        match fraction {
+            "" => write!(f, "{integer}"),
+            fraction => write!(f, "{integer}.{fraction}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Decimal;
+    use std::cmp::Ordering;
+
+    fn dec(n: &str) -> Decimal {
+        Decimal::parse(n).unwrap()
+    }
+
+    fn div(a: &str, b: &str) -> String {
+        dec(a).checked_div(&dec(b)).unwrap().to_string()
+    }
+
+    #[test]
+    fn keeps_the_digits_it_is_written_with() {
+        for n in ["0", "1.10", "-0.05", "123.000"] {
+            assert_eq!(dec(n).to_string(), n);
+        }
+        assert_eq!(dec("1.10").compare(&dec("1.1")), Ordering::Equal);
+        assert_eq!(dec("1.10").normalized().to_string(), "1.1");
+        assert!(Decimal::parse("1.-5").is_none());
+        assert!(Decimal::parse("1e5").is_none());
+    }
+
+    #[test]
+    fn adds_and_multiplies_exactly() {
+        assert_eq!((&dec("0.1") + &dec("0.2")).to_string(), "0.3");
+        assert_eq!((&dec("1.5") - &dec("2.25")).to_string(), "-0.75");
+        assert_eq!((&dec("1.5") * &dec("1.5")).to_string(), "2.25");
+        assert_eq!((&dec("-0.1") * &dec("0.1")).to_string(), "-0.01");
+    }
+
+    #[test]
+    fn divides_to_28_more_digits() {
+        assert_eq!(div("1", "3"), "0.3333333333333333333333333333");
+        assert_eq!(div("2", "3"), "0.6666666666666666666666666667");
+        assert_eq!(div("-2", "3"), "-0.6666666666666666666666666667");
+        assert_eq!(div("1", "8"), "0.125");
+        // Trailing zeros are dropped down to the digits of the operands
+        assert_eq!(div("10.00", "4"), "2.50");
+        assert_eq!(div("6", "3"), "2");
+        assert!(dec("1").checked_div(&dec("0.0")).is_none());
+    }
+
+    #[test]
+    fn rounds_halves_to_even() {
+        let half = "20000000000000000000000000000";
+        assert_eq!(div("1", half), "0");
+        assert_eq!(div("3", half), "0.0000000000000000000000000002");
+        assert_eq!(div("5", half), "0.0000000000000000000000000002");
+        assert_eq!(div("7", half), "0.0000000000000000000000000004");
+        assert_eq!(div("-3", half), "-0.0000000000000000000000000002");
+    }
+
+    #[test]
+    fn keeps_the_sign_of_the_dividend_in_remainders() {
+        let rem = |a: &str, b: &str| dec(a).checked_rem(&dec(b)).unwrap().to_string();
+        assert_eq!(rem("7.5", "2"), "1.5");
+        assert_eq!(rem("-7.5", "2"), "-1.5");
+        assert_eq!(rem("7.5", "-2"), "1.5");
+        assert!(dec("1").checked_rem(&dec("0")).is_none());
+    }
+
+    #[test]
+    fn truncates_to_integers() {
+        assert_eq!(dec("-2.7").trunc().to_string(), "-2");
+        assert_eq!(dec("2.7").trunc().to_string(), "2");
+        assert_eq!(Decimal::from_float(0.1f64).unwrap().to_string(), "0.1");
+        assert!(Decimal::from_float(f64::NAN).is_none());
+    }
+}
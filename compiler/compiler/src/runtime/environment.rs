@@ -0,0 +1,124 @@
+use super::value::Value;
+use ascii::AsciiString;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+pub type Env = Rc<RefCell<Environment>>;
+
+#[derive(Default)]
+pub struct Environment {
+    values: HashMap<AsciiString, Value>,
+    // The names declared with `mut` in this scope
+    mutable: HashSet<AsciiString>,
+    enclosing: Option<Env>,
+}
+
+impl Environment {
+    pub fn new(enclosing: Option<Env>) -> Env {
+        Rc::new(RefCell::new(Self {
+            values: HashMap::new(),
+            mutable: HashSet::new(),
+            enclosing,
+        }))
+    }
+
+    pub fn define(&mut self, name: AsciiString, value: Value) {
+        self.mutable.remove(&name);
+        self.values.insert(name, value);
+    }
+
+    pub fn define_mut(&mut self, name: AsciiString, value: Value) {
+        self.mutable.insert(name.clone());
+        self.values.insert(name, value);
+    }
+
+    // Copies every scope, later assignments on either side are not seen by the other
+    pub fn snapshot(env: &Env) -> Env {
+        let scope = env.borrow();
+        Rc::new(RefCell::new(Self {
+            values: scope.values.clone(),
+            mutable: scope.mutable.clone(),
+            enclosing: scope.enclosing.as_ref().map(Self::snapshot),
+        }))
+    }
+
+    // `None` when the variable was never declared
+    pub fn is_mutable(&self, name: &AsciiString) -> Option<bool> {
+        if self.values.contains_key(name) {
+            Some(self.mutable.contains(name))
+        } else {
+            self.enclosing.as_ref()?.borrow().is_mutable(name)
+        }
+    }
+
+    pub fn get(&self, name: &AsciiString) -> Option<Value> {
+        match self.values.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.enclosing.as_ref()?.borrow().get(name),
+        }
+    }
+
+    // Returns `false` when the variable was never declared
+    pub fn assign(&mut self, name: &AsciiString, value: Value) -> bool {
+        match self.values.get_mut(name) {
+            Some(slot) => {
+                *slot = value;
+                true
+            }
+            None => match &self.enclosing {
+                Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+                None => false,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Environment, Value};
+    use crate::{error::Error, io::run};
+    use ascii::AsciiString;
+
+    #[test]
+    fn snapshots_do_not_share_later_assignments() {
+        let name = AsciiString::from_ascii("n").unwrap();
+        let outer = Environment::new(None);
+        outer
+            .borrow_mut()
+            .define_mut(name.clone(), Value::Boolean(true));
+        let inner = Environment::new(Some(outer.clone()));
+        let snapshot = Environment::snapshot(&inner);
+        assert!(inner.borrow_mut().assign(&name, Value::Boolean(false)));
+        assert_eq!(outer.borrow().get(&name).unwrap().to_string(), "false");
+        assert_eq!(snapshot.borrow().get(&name).unwrap().to_string(), "true");
+        assert_eq!(snapshot.borrow().is_mutable(&name), Some(true));
+        let unknown = AsciiString::from_ascii("x").unwrap();
+        assert!(!inner.borrow_mut().assign(&unknown, Value::Null));
+    }
+
+    #[test]
+    fn closures_capture_their_environment() {
+        let shown = |source: &str| run(source).unwrap();
+        let source =
+            "let n = 10; let add = |x, y| x + y; let f = |x: i32| x + n; (add(1, 2), f(5))";
+        assert_eq!(shown(source), "(3, 15)");
+        let source = "([1, 2, 3].map(|x| x * 2), [1, 2, 3, 4].filter(|x| x % 2 == 0),
+            [1, 2, 3].fold(0, |a, x| a + x))";
+        assert_eq!(shown(source), "([2, 4, 6], [2, 4], 6)");
+        // By reference unless `move` copies the environment
+        assert_eq!(
+            shown("let mut c = 0; let inc = || { c = c + 1; }; inc(); inc(); c"),
+            "2"
+        );
+        assert_eq!(shown("let mut n = 1; let f = move || n; n = 5; f()"), "1");
+        let adder = "fn adder(n: i32) -> fn(i32) -> i32 { |x| x + n }";
+        assert_eq!(shown(&format!("{adder} adder(3)(4)")), "7");
+        assert!(matches!(
+            run("let f = |x| x; f(1, 2)"),
+            Err(Error::ArityMismatch(..))
+        ));
+    }
+}
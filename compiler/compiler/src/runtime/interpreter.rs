@@ -0,0 +1,1567 @@
+use super::{
+    builtins,
+    environment::{Env, Environment},
+    iterator::Iter,
+    map::{Key, Map},
+    value::{Enum, Function, Instance, Payload, Range, Struct, Trait, Traits, Value, Variant},
+};
+use crate::{
+    ast::{
+        expr::{Arm, Expr, Side},
+        pattern::{self, Pattern},
+        stmt::{self, Stmt},
+        token::{Number, Operator},
+        types::TypeExpr,
+    },
+    error::*,
+    module::Module,
+    position::Position,
+    prelude::{self, Overload},
+};
+use ascii::AsciiString;
+use std::{cell::RefCell, cmp::Ordering, collections::HashMap, mem, rc::Rc, result};
+
+const MAX_DEPTH: usize = 256;
+
+// Anything that unwinds the evaluation of an expression
+pub enum Control {
+    Return(Value),
+    Break(Option<AsciiString>, Value),
+    Continue(Option<AsciiString>),
+    Error(Error),
+}
+
+impl From<Error> for Control {
+    fn from(error: Error) -> Self {
+        Control::Error(error)
+    }
+}
+
+pub type Flow<T> = result::Result<T, Control>;
+
+pub struct Interpreter {
+    env: Env,
+    depth: usize,
+    // The globals of each module that ran
+    modules: HashMap<String, Env>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        let mut interpreter = Self {
+            env: globals(),
+            depth: 0,
+            modules: HashMap::new(),
+        };
+        interpreter.prelude();
+        interpreter
+    }
+
+    // Runs a module in globals of its own, which later imports read from
+    pub fn run_module(&mut self, module: &Module) -> Result<()> {
+        let env = mem::replace(&mut self.env, globals());
+        self.prelude();
+        let result = self.run(&module.stmts);
+        let globals = mem::replace(&mut self.env, env);
+        self.modules.insert(module.name.clone(), globals);
+        result.map(|_| ())
+    }
+
+    // The prelude only declares items, which can not fail
+    fn prelude(&mut self) {
+        self.declare(&prelude::stmts())
+            .expect("the prelude declares nothing but items");
+    }
+
+    // The `Ordering` the builtin types return from `cmp`, a variant of the
+    // prelude enum unless the program declares its own
+    pub fn ordering(&self, ordering: Ordering) -> Value {
+        let decl = prelude::enum_decl("Ordering");
+        let ty = match self.env.borrow().get(&decl.name) {
+            Some(Value::Enum(ty)) if Rc::ptr_eq(&ty.decl, &decl) => ty,
+            _ => Rc::new(Enum {
+                decl,
+                methods: RefCell::new(HashMap::new()),
+                traits: RefCell::new(Vec::new()),
+            }),
+        };
+        let index = match ordering {
+            Ordering::Less => 0,
+            Ordering::Equal => 1,
+            Ordering::Greater => 2,
+        };
+        Value::Variant(Rc::new(Variant {
+            ty,
+            index,
+            payload: Payload::Unit,
+        }))
+    }
+
+    // Runs the statements and returns the value of the last one
+    pub fn run(&mut self, stmts: &[Stmt]) -> Result<Value> {
+        self.declare(stmts)?;
+        let mut last = Value::Unit;
+        for stmt in stmts {
+            last = match self.execute(stmt) {
+                Ok(value) => value,
+                Err(Control::Error(error)) => return Err(error),
+                // The parser rejects `return`, `break` and `continue` outside
+                // of functions and loops
+                Err(_) => unreachable!(),
+            };
+        }
+        Ok(last)
+    }
+
+    // Items are usable in their whole block, before the statements declaring them.
+    // Types are declared before `impl` blocks, which add to them.
+    fn declare(&mut self, stmts: &[Stmt]) -> Result<()> {
+        for stmt in stmts {
+            match stmt {
+                Stmt::Function { decl } => {
+                    let function = Function {
+                        decl: Rc::clone(decl),
+                        closure: Rc::clone(&self.env),
+                    };
+                    self.env
+                        .borrow_mut()
+                        .define(decl.name.clone(), Value::Function(Rc::new(function)));
+                }
+                Stmt::Enum { decl } => {
+                    let ty = Enum {
+                        decl: Rc::clone(decl),
+                        methods: RefCell::new(HashMap::new()),
+                        traits: RefCell::new(Vec::new()),
+                    };
+                    self.env
+                        .borrow_mut()
+                        .define(decl.name.clone(), Value::Enum(Rc::new(ty)));
+                }
+                Stmt::Struct { decl } => {
+                    let ty = Struct {
+                        decl: Rc::clone(decl),
+                        methods: RefCell::new(HashMap::new()),
+                        traits: RefCell::new(Vec::new()),
+                    };
+                    self.env
+                        .borrow_mut()
+                        .define(decl.name.clone(), Value::Struct(Rc::new(ty)));
+                }
+                Stmt::Trait { decl } => {
+                    let trait_ = Trait {
+                        decl: Rc::clone(decl),
+                        closure: Rc::clone(&self.env),
+                    };
+                    self.env
+                        .borrow_mut()
+                        .define(decl.name.clone(), Value::Trait(Rc::new(trait_)));
+                }
+                // The checker computes constants before the program runs
+                Stmt::Const { decl } => {
+                    let value = decl
+                        .folded
+                        .get()
+                        .expect("constants are computed by the checker");
+                    self.env
+                        .borrow_mut()
+                        .define(decl.name.clone(), Value::from(value.clone()));
+                }
+                // The loader runs imported modules first and checks the items exist
+                Stmt::Use { path, items, .. } => {
+                    let module = self.modules[&pattern::path_name(path)].borrow();
+                    for (item, _) in items {
+                        let value = module.get(item).unwrap();
+                        self.env.borrow_mut().define(item.clone(), value);
+                    }
+                }
+                _ => {}
+            }
+        }
+        for stmt in stmts {
+            let Stmt::Impl {
+                trait_,
+                ty,
+                methods,
+                pos,
+                ..
+            } = stmt
+            else {
+                continue;
+            };
+            let value = self.env.borrow().get(ty);
+            let (mut table, mut traits) = match &value {
+                Some(Value::Struct(ty)) => (ty.methods.borrow_mut(), ty.traits.borrow_mut()),
+                Some(Value::Enum(ty)) => (ty.methods.borrow_mut(), ty.traits.borrow_mut()),
+                _ => return Error::UnknownType(ty.to_string()).throw(pos),
+            };
+            // The methods the trait provides, unless the block overrides them
+            if let Some((name, pos)) = trait_ {
+                let Some(Value::Trait(trait_)) = self.env.borrow().get(name) else {
+                    return Error::UnknownTrait(name.to_string()).throw(pos);
+                };
+                traits.push(Rc::clone(&trait_.decl));
+                for method in trait_.decl.methods.iter().filter(|method| method.provided) {
+                    let function = Function {
+                        decl: Rc::clone(&method.decl),
+                        closure: Rc::clone(&trait_.closure),
+                    };
+                    table.insert(method.decl.name.clone(), Rc::new(function));
+                }
+            }
+            for decl in methods {
+                let function = Function {
+                    decl: Rc::clone(decl),
+                    closure: Rc::clone(&self.env),
+                };
+                table.insert(decl.name.clone(), Rc::new(function));
+            }
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, stmt: &Stmt) -> Flow<Value> {
+        match stmt {
+            Stmt::Expr { expresion } => return self.evaluate(expresion),
+            Stmt::Let {
+                pattern,
+                value,
+                pos,
+                ..
+            } => {
+                let value = match value {
+                    Some(value) => self.evaluate(value)?,
+                    None => Value::Unit,
+                };
+                let env = Rc::clone(&self.env);
+                if !self.bind(pattern, &value, &env)? {
+                    Error::NoMatch(value.repr()).throw(pos)?;
+                }
+            }
+            // Declared when their block is entered
+            Stmt::Function { .. }
+            | Stmt::Const { .. }
+            | Stmt::Enum { .. }
+            | Stmt::Impl { .. }
+            | Stmt::Struct { .. }
+            | Stmt::Trait { .. }
+            | Stmt::Use { .. } => {}
+        }
+        Ok(Value::Unit)
+    }
+
+    fn evaluate_in(&mut self, expr: &Expr, env: Env) -> Flow<Value> {
+        let previous = mem::replace(&mut self.env, env);
+        let result = self.evaluate(expr);
+        self.env = previous;
+        result
+    }
+
+    fn block(&mut self, stmts: &[Stmt], tail: &Option<Box<Expr>>) -> Flow<Value> {
+        self.declare(stmts)?;
+        for stmt in stmts {
+            self.execute(stmt)?;
+        }
+        match tail {
+            Some(tail) => self.evaluate(tail),
+            None => Ok(Value::Unit),
+        }
+    }
+
+    fn evaluate(&mut self, expr: &Expr) -> Flow<Value> {
+        Ok(match expr {
+            Expr::Array { elements, .. } => {
+                let mut values = Vec::with_capacity(elements.len());
+                for element in elements {
+                    values.push(self.evaluate(element)?);
+                }
+                Value::array(values)
+            }
+            Expr::Assign { target, value, pos } => {
+                let value = self.evaluate(value)?;
+                self.assign(target, value.clone(), pos)?;
+                value
+            }
+            Expr::Binary {
+                left,
+                operator,
+                right,
+                pos,
+            } => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                match prelude::binary(*operator).and_then(|overload| overloaded(&left, overload)) {
+                    Some(method) => self.call_overloaded(method, left, *operator, right, pos)?,
+                    None => binary(&left, *operator, &right, pos)?,
+                }
+            }
+            Expr::Block { stmts, tail, .. } => {
+                let env = Environment::new(Some(Rc::clone(&self.env)));
+                let previous = mem::replace(&mut self.env, env);
+                let result = self.block(stmts, tail);
+                self.env = previous;
+                result?
+            }
+            Expr::Break { label, value, .. } => {
+                let value = match value {
+                    Some(value) => self.evaluate(value)?,
+                    None => Value::Unit,
+                };
+                return Err(Control::Break(label.clone(), value));
+            }
+            Expr::Call {
+                callee,
+                arguments,
+                pos,
+            } => {
+                let callee = self.evaluate(callee)?;
+                let mut values = Vec::with_capacity(arguments.len());
+                for argument in arguments {
+                    values.push(self.evaluate(argument)?);
+                }
+                self.call(callee, values, pos)?
+            }
+            Expr::Continue { label, .. } => return Err(Control::Continue(label.clone())),
+            Expr::Field {
+                object,
+                name,
+                safe,
+                pos,
+            } => match self.evaluate(object)? {
+                Value::Null if *safe => Value::Null,
+                Value::Instance(instance) => match instance.borrow_mut().field(name) {
+                    Some(value) => value.clone(),
+                    None => Error::UnknownField(
+                        name.to_string(),
+                        instance.borrow().ty.decl.name.to_string(),
+                    )
+                    .throw(pos)?,
+                },
+                value => Error::UnknownField(name.to_string(), value.type_name()).throw(pos)?,
+            },
+            // Type arguments only matter to the type checker
+            Expr::Group { expresion } | Expr::TypeArgs { expresion, .. } => {
+                self.evaluate(expresion)?
+            }
+            Expr::Cast { expresion, ty, pos } => cast(self.evaluate(expresion)?, ty, pos)?,
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => match self.evaluate(condition)? {
+                Value::Boolean(true) => self.evaluate(then_branch)?,
+                Value::Boolean(false) => match else_branch {
+                    Some(else_branch) => self.evaluate(else_branch)?,
+                    None => Value::Unit,
+                },
+                value => Error::InvalidCondition(value.type_name()).throw(condition.pos())?,
+            },
+            Expr::Literal { value, .. } => Value::from(value.clone()),
+            Expr::Logical {
+                left,
+                operator: Operator::Coalesce,
+                right,
+                ..
+            } => match self.evaluate(left)? {
+                Value::Null => self.evaluate(right)?,
+                value => value,
+            },
+            Expr::Logical {
+                left,
+                operator,
+                right,
+                pos,
+            } => {
+                let left = truthy(self.evaluate(left)?, *operator, pos)?;
+                // Short-circuit when the left side already decides the result
+                if left == (*operator == Operator::Or) {
+                    Value::Boolean(left)
+                } else {
+                    Value::Boolean(truthy(self.evaluate(right)?, *operator, pos)?)
+                }
+            }
+            Expr::Index { object, index, pos } => {
+                let object = self.evaluate(object)?;
+                let index = self.evaluate(index)?;
+                match overloaded(&object, prelude::INDEX) {
+                    Some(method) => self.call(Value::Function(method), vec![object, index], pos)?,
+                    None => self::index(&object, &index, pos)?,
+                }
+            }
+            Expr::Map { entries, .. } => {
+                let mut map = Map::default();
+                for (key, value) in entries {
+                    let key = Key::new(&self.evaluate(key)?, key.pos())?;
+                    map.insert(key, self.evaluate(value)?);
+                }
+                Value::Map(Rc::new(RefCell::new(map)))
+            }
+            Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                let value = self.evaluate(scrutinee)?;
+                self.match_(value, arms, scrutinee.pos())?
+            }
+            Expr::Method {
+                receiver,
+                name,
+                arguments,
+                safe,
+                pos,
+            } => {
+                let receiver = self.evaluate(receiver)?;
+                // The arguments are not evaluated either
+                if *safe && matches!(receiver, Value::Null) {
+                    return Ok(Value::Null);
+                }
+                let mut values = vec![receiver.clone()];
+                for argument in arguments {
+                    values.push(self.evaluate(argument)?);
+                }
+                self.call_method(receiver, name, values, pos)?
+            }
+            Expr::Loop { label, body, .. } => loop {
+                if let Some(value) = iteration(label, self.evaluate(body))? {
+                    break value;
+                }
+            },
+            Expr::LoopIn {
+                label,
+                pattern,
+                iterable,
+                body,
+                pos,
+            } => {
+                let mut iter = Iter::new(self.evaluate(iterable)?, iterable.pos())?;
+                loop {
+                    let Some(value) = iter.next(self, pos)? else {
+                        break Value::Unit;
+                    };
+                    let env = Environment::new(Some(Rc::clone(&self.env)));
+                    if !self.bind(pattern, &value, &env)? {
+                        Error::NoMatch(value.repr()).throw(pattern.pos())?;
+                    }
+                    let result = self.evaluate_in(body, env);
+                    if let Some(value) = iteration(label, result)? {
+                        break value;
+                    }
+                }
+            }
+            Expr::Path { segments, pos } => self.path(segments, pos)?,
+            Expr::Range {
+                start,
+                end,
+                inclusive,
+                pos,
+            } => {
+                let start = match start {
+                    Some(start) => Some(self.evaluate(start)?),
+                    None => None,
+                };
+                let end = match end {
+                    Some(end) => Some(self.evaluate(end)?),
+                    None => None,
+                };
+                Value::Range(range(start, end, *inclusive, pos)?)
+            }
+            Expr::Return { value, .. } => {
+                let value = match value {
+                    Some(value) => self.evaluate(value)?,
+                    None => Value::Unit,
+                };
+                return Err(Control::Return(value));
+            }
+            Expr::Struct { path, fields, pos } => self.struct_literal(path, fields, pos)?,
+            Expr::Closure { decl, moves } => {
+                let closure = if *moves {
+                    Environment::snapshot(&self.env)
+                } else {
+                    Rc::clone(&self.env)
+                };
+                Value::Function(Rc::new(Function {
+                    decl: Rc::clone(decl),
+                    closure,
+                }))
+            }
+            Expr::Tuple { elements, .. } if elements.is_empty() => Value::Unit,
+            Expr::Tuple { elements, .. } => {
+                let mut values = Vec::with_capacity(elements.len());
+                for element in elements {
+                    values.push(self.evaluate(element)?);
+                }
+                Value::Tuple(Rc::new(values))
+            }
+            Expr::TupleIndex {
+                object,
+                index,
+                safe,
+                pos,
+            } => match self.evaluate(object)? {
+                Value::Null if *safe => Value::Null,
+                Value::Tuple(elements) => match elements.get(*index) {
+                    Some(element) => element.clone(),
+                    None => {
+                        Error::IndexOutOfBounds(index.to_string(), elements.len()).throw(pos)?
+                    }
+                },
+                value => Error::UnknownField(index.to_string(), value.type_name()).throw(pos)?,
+            },
+            Expr::Unary {
+                operator,
+                expresion,
+                pos,
+            } => self.unary(*operator, expresion, pos)?,
+            Expr::Variable { name, pos } => match self.env.borrow().get(name) {
+                Some(value) => value,
+                None => Error::UnknownIdent(name.to_string()).throw(pos)?,
+            },
+        })
+    }
+
+    fn assign(&mut self, target: &Expr, value: Value, pos: &Position) -> Flow<()> {
+        match target {
+            Expr::Variable { name, pos } => {
+                if !self.env.borrow_mut().assign(name, value) {
+                    Error::UnknownIdent(name.to_string()).throw(pos)?;
+                }
+                Ok(())
+            }
+            Expr::Index { object, index, pos } => {
+                let object = self.evaluate(object)?;
+                let index = self.evaluate(index)?;
+                match (&object, &index) {
+                    (Value::Array(array), Value::Number(n)) if n.is_integer() => {
+                        let mut array = array.borrow_mut();
+                        let len = array.len();
+                        match n.as_index().and_then(|i| array.get_mut(i)) {
+                            Some(element) => {
+                                *element = value;
+                                Ok(())
+                            }
+                            None => Ok(Error::IndexOutOfBounds(n.to_string(), len).throw(pos)?),
+                        }
+                    }
+                    (Value::Map(map), key) => {
+                        map.borrow_mut().insert(Key::new(key, pos)?, value);
+                        Ok(())
+                    }
+                    _ => Ok(Error::InvalidOperation(
+                        String::from("[]="),
+                        object.type_name(),
+                        index.type_name(),
+                    )
+                    .throw(pos)?),
+                }
+            }
+            Expr::Field {
+                object, name, pos, ..
+            } => {
+                self.check_mutable(object, pos)?;
+                match self.evaluate(object)? {
+                    Value::Instance(instance) => {
+                        let mut instance = instance.borrow_mut();
+                        match instance.field(name) {
+                            Some(field) => {
+                                *field = value;
+                                Ok(())
+                            }
+                            None => Ok(Error::UnknownField(
+                                name.to_string(),
+                                instance.ty.decl.name.to_string(),
+                            )
+                            .throw(pos)?),
+                        }
+                    }
+                    object => {
+                        Ok(Error::UnknownField(name.to_string(), object.type_name()).throw(pos)?)
+                    }
+                }
+            }
+            // Tuples are values, so the updated tuple is written back to where it came from
+            Expr::TupleIndex {
+                object, index, pos, ..
+            } => {
+                self.check_mutable(object, pos)?;
+                match self.evaluate(object)? {
+                    Value::Tuple(elements) => {
+                        let mut elements = elements.to_vec();
+                        let len = elements.len();
+                        match elements.get_mut(*index) {
+                            Some(element) => *element = value,
+                            None => Error::IndexOutOfBounds(index.to_string(), len).throw(pos)?,
+                        }
+                        self.assign(object, Value::Tuple(Rc::new(elements)), pos)
+                    }
+                    object => Ok(
+                        Error::UnknownField(index.to_string(), object.type_name()).throw(pos)?
+                    ),
+                }
+            }
+            _ => Ok(Error::InvalidAssignment.throw(pos)?),
+        }
+    }
+
+    // Fields and tuple elements can only be assigned through a `mut` variable
+    fn check_mutable(&self, object: &Expr, pos: &Position) -> Result<()> {
+        if let Some(root) = root(object) {
+            if self.env.borrow().is_mutable(root) == Some(false) {
+                return Error::ImmutableAssignment(root.to_string(), None).throw(pos);
+            }
+        }
+        Ok(())
+    }
+
+    // `Type::function` names an associated function or method of a type and
+    // `Enum::Variant` a variant without payload or the constructor of a tuple variant
+    fn path(&mut self, segments: &[AsciiString], pos: &Position) -> Result<Value> {
+        let unknown = || Error::UnknownIdent(pattern::path_name(segments)).throw(pos);
+        let [ty, name] = segments else {
+            return unknown();
+        };
+        let value = self.env.borrow().get(ty);
+        match value {
+            Some(Value::Struct(ty)) => match ty.method(name) {
+                Some(function) => Ok(Value::Function(function)),
+                None => unknown(),
+            },
+            Some(Value::Enum(ty)) => match ty.decl.variant(name) {
+                Some((index, variant)) => match variant.payload {
+                    stmt::Payload::Unit => Ok(Value::Variant(Rc::new(Variant {
+                        ty: Rc::clone(&ty),
+                        index,
+                        payload: Payload::Unit,
+                    }))),
+                    stmt::Payload::Tuple(_) => Ok(Value::Constructor(Rc::clone(&ty), index)),
+                    stmt::Payload::Struct(_) => {
+                        Error::VariantForm(ty.decl.path(index), ty.decl.usage(index)).throw(pos)
+                    }
+                },
+                None => match ty.method(name) {
+                    Some(function) => Ok(Value::Function(function)),
+                    None => {
+                        Error::UnknownVariant(name.to_string(), ty.decl.name.to_string()).throw(pos)
+                    }
+                },
+            },
+            Some(value) => Error::UnknownType(value.type_name()).throw(pos),
+            None => unknown(),
+        }
+    }
+
+    // Runs the body of the first arm whose pattern matches and whose guard holds
+    fn match_(&mut self, value: Value, arms: &[Arm], pos: &Position) -> Flow<Value> {
+        for arm in arms {
+            let env = Environment::new(Some(Rc::clone(&self.env)));
+            if !self.bind(&arm.pattern, &value, &env)? {
+                continue;
+            }
+            if let Some(guard) = &arm.guard {
+                match self.evaluate_in(guard, Rc::clone(&env))? {
+                    Value::Boolean(true) => {}
+                    Value::Boolean(false) => continue,
+                    value => Error::InvalidCondition(value.type_name()).throw(guard.pos())?,
+                }
+            }
+            return self.evaluate_in(&arm.body, env);
+        }
+        Ok(Error::NoMatch(value.repr()).throw(pos)?)
+    }
+
+    // Matches the value against the pattern, defining its bindings in `env`
+    fn bind(&self, pattern: &Pattern, value: &Value, env: &Env) -> Result<bool> {
+        Ok(match (pattern, value) {
+            (Pattern::Wildcard { .. }, _) => true,
+            (
+                Pattern::Binding {
+                    name,
+                    mutable,
+                    constant,
+                    ..
+                },
+                value,
+            ) => match constant.get() {
+                // A constant is compared against like a literal
+                Some(literal) => {
+                    compare(&Value::from(literal.clone()), value) == Some(Ordering::Equal)
+                }
+                None => {
+                    if *mutable {
+                        env.borrow_mut().define_mut(name.clone(), value.clone());
+                    } else {
+                        env.borrow_mut().define(name.clone(), value.clone());
+                    }
+                    true
+                }
+            },
+            (Pattern::Literal { value: literal, .. }, value) => {
+                compare(&Value::from(literal.clone()), value) == Some(Ordering::Equal)
+            }
+            (
+                Pattern::Range {
+                    start,
+                    end,
+                    inclusive,
+                    ..
+                },
+                value,
+            ) => {
+                let above = match start {
+                    Some(start) => matches!(
+                        compare(value, &Value::from(start.clone())),
+                        Some(Ordering::Greater | Ordering::Equal)
+                    ),
+                    None => true,
+                };
+                let below = match end {
+                    Some(end) => match compare(value, &Value::from(end.clone())) {
+                        Some(Ordering::Less) => true,
+                        Some(Ordering::Equal) => *inclusive,
+                        _ => false,
+                    },
+                    None => true,
+                };
+                above && below
+            }
+            (Pattern::Tuple { elements, .. }, Value::Unit) => elements.is_empty(),
+            (Pattern::Tuple { elements, .. }, Value::Tuple(values)) => {
+                elements.len() == values.len() && self.bind_all(elements, values, env)?
+            }
+            (Pattern::Struct { path, fields, pos }, value) => {
+                let values = match (self.resolve(path, pos)?, value) {
+                    (Resolved::Struct(ty), Value::Instance(instance))
+                        if Rc::ptr_eq(&ty, &instance.borrow().ty) =>
+                    {
+                        instance.borrow().fields.clone()
+                    }
+                    (Resolved::Variant(ty, index), Value::Variant(variant))
+                        if Rc::ptr_eq(&ty, &variant.ty) && index == variant.index =>
+                    {
+                        match &variant.payload {
+                            Payload::Struct(fields) => fields.clone(),
+                            _ => return Ok(false),
+                        }
+                    }
+                    _ => return Ok(false),
+                };
+                for (name, pattern) in fields {
+                    match values.iter().find(|(field, _)| field == name) {
+                        Some((_, value)) if self.bind(pattern, value, env)? => {}
+                        _ => return Ok(false),
+                    }
+                }
+                true
+            }
+            (
+                Pattern::Variant {
+                    path,
+                    elements,
+                    pos,
+                },
+                value,
+            ) => match (self.resolve(path, pos)?, value) {
+                (Resolved::Variant(ty, index), Value::Variant(variant))
+                    if Rc::ptr_eq(&ty, &variant.ty) && index == variant.index =>
+                {
+                    match &variant.payload {
+                        Payload::Tuple(values) => {
+                            elements.len() == values.len()
+                                && self.bind_all(elements, values, env)?
+                        }
+                        _ => false,
+                    }
+                }
+                _ => false,
+            },
+            (Pattern::Path { path, pos }, value) => match (self.resolve(path, pos)?, value) {
+                (Resolved::Variant(ty, index), Value::Variant(variant)) => {
+                    Rc::ptr_eq(&ty, &variant.ty) && index == variant.index
+                }
+                _ => false,
+            },
+            _ => false,
+        })
+    }
+
+    fn bind_all(&self, patterns: &[Pattern], values: &[Value], env: &Env) -> Result<bool> {
+        for (pattern, value) in patterns.iter().zip(values) {
+            if !self.bind(pattern, value, env)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    // The struct or enum variant a pattern names
+    fn resolve(&self, path: &[AsciiString], pos: &Position) -> Result<Resolved> {
+        let value = self.env.borrow().get(&path[0]);
+        match (value, path) {
+            (Some(Value::Struct(ty)), [_]) => Ok(Resolved::Struct(ty)),
+            (Some(Value::Enum(ty)), [_, name]) => match ty.decl.variant(name) {
+                Some((index, _)) => Ok(Resolved::Variant(Rc::clone(&ty), index)),
+                None => {
+                    Error::UnknownVariant(name.to_string(), ty.decl.name.to_string()).throw(pos)
+                }
+            },
+            _ => Error::UnknownType(pattern::path_name(path)).throw(pos),
+        }
+    }
+
+    // `Point { .. }` builds an instance and `Shape::Rect { .. }` a variant with named fields
+    fn struct_literal(
+        &mut self,
+        path: &[AsciiString],
+        fields: &[(AsciiString, Expr)],
+        pos: &Position,
+    ) -> Flow<Value> {
+        let value = self.env.borrow().get(&path[0]);
+        match (value, path) {
+            (Some(Value::Struct(ty)), [_]) => {
+                let fields = self.initialize(&ty.decl.name, &ty.decl.fields, fields, pos)?;
+                Ok(Value::Instance(Rc::new(RefCell::new(Instance {
+                    ty,
+                    fields,
+                }))))
+            }
+            (Some(Value::Enum(ty)), [_, name]) => {
+                let Some((index, variant)) = ty.decl.variant(name) else {
+                    return Ok(
+                        Error::UnknownVariant(name.to_string(), ty.decl.name.to_string())
+                            .throw(pos)?,
+                    );
+                };
+                let stmt::Payload::Struct(declared) = &variant.payload else {
+                    return Ok(
+                        Error::VariantForm(ty.decl.path(index), ty.decl.usage(index)).throw(pos)?,
+                    );
+                };
+                let fields = self.initialize(&variant.name, declared, fields, pos)?;
+                Ok(Value::Variant(Rc::new(Variant {
+                    ty: Rc::clone(&ty),
+                    index,
+                    payload: Payload::Struct(fields),
+                })))
+            }
+            _ => Ok(Error::UnknownType(pattern::path_name(path)).throw(pos)?),
+        }
+    }
+
+    // Evaluates the fields of a literal in the order they are declared in
+    fn initialize(
+        &mut self,
+        name: &AsciiString,
+        declared: &[stmt::Field],
+        fields: &[(AsciiString, Expr)],
+        pos: &Position,
+    ) -> Flow<Vec<(AsciiString, Value)>> {
+        let mut values: Vec<Option<Value>> = vec![None; declared.len()];
+        for (field, value) in fields {
+            let Some(i) = declared.iter().position(|f| &f.name == field) else {
+                return Ok(
+                    Error::UnknownField(field.to_string(), name.to_string()).throw(value.pos())?
+                );
+            };
+            if values[i].is_some() {
+                return Ok(Error::DuplicateField(field.to_string()).throw(value.pos())?);
+            }
+            values[i] = Some(self.evaluate(value)?);
+        }
+        let mut initialized = Vec::with_capacity(values.len());
+        for (field, value) in declared.iter().zip(values) {
+            match value {
+                Some(value) => initialized.push((field.name.clone(), value)),
+                None => {
+                    return Ok(
+                        Error::MissingField(field.name.to_string(), name.to_string()).throw(pos)?,
+                    )
+                }
+            }
+        }
+        Ok(initialized)
+    }
+
+    fn unary(
+        &mut self,
+        operator: Operator,
+        expresion: &Side<Box<Expr>>,
+        pos: &Position,
+    ) -> Flow<Value> {
+        let operand = match expresion {
+            Side::Left(operand) | Side::Right(operand) => operand,
+        };
+        let value = self.evaluate(operand)?;
+        if operator == Operator::Minus {
+            if let Some(method) = overloaded(&value, prelude::NEG) {
+                return Ok(self.call(Value::Function(method), vec![value], pos)?);
+            }
+        }
+
+        match (operator, &value) {
+            (Operator::Exclamation, Value::Boolean(b)) => Ok(Value::Boolean(!b)),
+            (Operator::Minus, Value::Number(n)) => match n.negate() {
+                Ok(n) => Ok(Value::Number(n)),
+                Err(error) => Ok(error.throw(pos)?),
+            },
+            (Operator::Increment | Operator::Decrement, Value::Number(n)) => {
+                let by = if operator == Operator::Increment {
+                    Operator::Plus
+                } else {
+                    Operator::Minus
+                };
+                let updated = match n.arithmetic(by, &n.one()) {
+                    Ok(n) => Value::Number(n),
+                    Err(error) => return Ok(error.throw(pos)?),
+                };
+                self.assign(operand, updated.clone(), pos)?;
+                // Prefix operators yield the updated value, postfix ones the old value
+                Ok(match expresion {
+                    Side::Right(_) => updated,
+                    Side::Left(_) => value,
+                })
+            }
+            _ => Ok(Error::InvalidOperand(operator.to_string(), value.type_name()).throw(pos)?),
+        }
+    }
+
+    // `!=` negates what `eq` returns, the other comparisons compare the
+    // `Ordering` of `cmp` to `Equal`
+    fn call_overloaded(
+        &mut self,
+        method: Rc<Function>,
+        left: Value,
+        operator: Operator,
+        right: Value,
+        pos: &Position,
+    ) -> Result<Value> {
+        let value = self.call(Value::Function(method), vec![left, right], pos)?;
+        Ok(match (operator, value) {
+            (Operator::NotEqualTo, Value::Boolean(equal)) => Value::Boolean(!equal),
+            (
+                Operator::LessThan
+                | Operator::LessThanOrEqualTo
+                | Operator::GreaterThan
+                | Operator::GreaterThanOrEqualTo,
+                Value::Variant(ordering),
+            ) => {
+                let ordering = ordering.index.cmp(&1);
+                Value::Boolean(match operator {
+                    Operator::LessThan => ordering.is_lt(),
+                    Operator::LessThanOrEqualTo => ordering.is_le(),
+                    Operator::GreaterThan => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                })
+            }
+            (_, value) => value,
+        })
+    }
+
+    pub fn call_method(
+        &mut self,
+        receiver: Value,
+        name: &AsciiString,
+        arguments: Vec<Value>,
+        pos: &Position,
+    ) -> Result<Value> {
+        let method = match &receiver {
+            Value::Instance(instance) => instance.borrow().ty.method(name),
+            Value::Variant(variant) => variant.ty.method(name),
+            _ => None,
+        };
+        if let Some(method) = method.filter(|method| method.decl.is_method()) {
+            let decl = &method.decl;
+            // Report the arity without the receiver, as it is written at the call
+            if decl.params.len() != arguments.len() {
+                return Error::ArityMismatch(
+                    decl.name.to_string(),
+                    decl.params.len() - 1,
+                    arguments.len() - 1,
+                    Some(decl.pos.clone()),
+                )
+                .throw(pos);
+            }
+            return self.call(Value::Function(method), arguments, pos);
+        }
+        let Some(method) = builtins::method(&receiver, name.as_str()) else {
+            return Error::UnknownMethod(name.to_string(), receiver.type_name()).throw(pos);
+        };
+        // The receiver is not counted as an argument
+        if let Some(arity) = method.arity {
+            if arity != arguments.len() - 1 {
+                return Error::ArityMismatch(
+                    method.name.to_string(),
+                    arity,
+                    arguments.len() - 1,
+                    None,
+                )
+                .throw(pos);
+            }
+        }
+        (method.func)(self, arguments, pos)
+    }
+
+    pub fn call(&mut self, callee: Value, arguments: Vec<Value>, pos: &Position) -> Result<Value> {
+        match callee {
+            Value::Function(function) => {
+                let decl = &function.decl;
+                if decl.params.len() != arguments.len() {
+                    return Error::ArityMismatch(
+                        decl.name.to_string(),
+                        decl.params.len(),
+                        arguments.len(),
+                        Some(decl.pos.clone()),
+                    )
+                    .throw(pos);
+                }
+                if self.depth == MAX_DEPTH {
+                    return Error::StackOverflow(decl.name.to_string()).throw(pos);
+                }
+
+                let env = Environment::new(Some(Rc::clone(&function.closure)));
+                for (param, argument) in decl.params.iter().zip(arguments) {
+                    if param.mutable {
+                        env.borrow_mut().define_mut(param.name.clone(), argument);
+                    } else {
+                        env.borrow_mut().define(param.name.clone(), argument);
+                    }
+                }
+
+                self.depth += 1;
+                let result = self.evaluate_in(&decl.body, env);
+                self.depth -= 1;
+                match result {
+                    Ok(value) | Err(Control::Return(value)) => Ok(value),
+                    Err(Control::Error(error)) => Err(error),
+                    // The parser keeps `break` and `continue` inside loops
+                    Err(_) => unreachable!(),
+                }
+            }
+            Value::Constructor(ty, index) => {
+                let variant = &ty.decl.variants[index];
+                let stmt::Payload::Tuple(types) = &variant.payload else {
+                    unreachable!()
+                };
+                if types.len() != arguments.len() {
+                    return Error::ArityMismatch(
+                        ty.decl.path(index),
+                        types.len(),
+                        arguments.len(),
+                        Some(variant.pos.clone()),
+                    )
+                    .throw(pos);
+                }
+                Ok(Value::Variant(Rc::new(Variant {
+                    ty: Rc::clone(&ty),
+                    index,
+                    payload: Payload::Tuple(arguments),
+                })))
+            }
+            Value::Native(native) => {
+                if let Some(arity) = native.arity {
+                    if arity != arguments.len() {
+                        return Error::ArityMismatch(
+                            native.name.to_string(),
+                            arity,
+                            arguments.len(),
+                            None,
+                        )
+                        .throw(pos);
+                    }
+                }
+                (native.func)(self, arguments, pos)
+            }
+            value => Error::NotCallable(value.type_name()).throw(pos),
+        }
+    }
+}
+
+fn globals() -> Env {
+    let globals = Environment::new(None);
+    builtins::define(&globals);
+    globals
+}
+
+// The method an operator calls on a value of a type implementing the standard trait
+fn overloaded(value: &Value, (trait_, name): Overload) -> Option<Rc<Function>> {
+    let implements = |traits: &Traits| {
+        let decl = prelude::trait_decl(trait_);
+        traits
+            .borrow()
+            .iter()
+            .any(|implemented| Rc::ptr_eq(implemented, &decl))
+    };
+    let name = AsciiString::from_ascii(name).unwrap();
+    match value {
+        Value::Instance(instance) => {
+            let ty = Rc::clone(&instance.borrow().ty);
+            implements(&ty.traits).then(|| ty.method(&name)).flatten()
+        }
+        Value::Variant(variant) => implements(&variant.ty.traits)
+            .then(|| variant.ty.method(&name))
+            .flatten(),
+        _ => None,
+    }
+}
+
+// Handles the result of one loop iteration, yielding the value of the loop once it ends
+pub fn iteration(label: &Option<AsciiString>, result: Flow<Value>) -> Flow<Option<Value>> {
+    // Unlabeled jumps target the innermost loop
+    let targets = |target: &Option<AsciiString>| target.is_none() || target == label;
+    match result {
+        Ok(_) => Ok(None),
+        Err(Control::Continue(target)) if targets(&target) => Ok(None),
+        Err(Control::Break(target, value)) if targets(&target) => Ok(Some(value)),
+        Err(control) => Err(control),
+    }
+}
+
+// Compares pairs of values, stopping at the first pair that differs
+fn all_equal<'a>(
+    mut pairs: impl Iterator<Item = (&'a Value, &'a Value)>,
+    pos: &Position,
+) -> Result<bool> {
+    pairs.try_fold(true, |equal, (a, b)| {
+        Ok(equal && matches!(binary(a, Operator::EqualTo, b, pos)?, Value::Boolean(true)))
+    })
+}
+
+// What the path of a pattern names
+enum Resolved {
+    Struct(Rc<Struct>),
+    Variant(Rc<Enum>, usize),
+}
+
+// Orders two numbers or two chars, `None` for values of different types
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.compare(b).ok().flatten(),
+        (Value::Char(a), Value::Char(b)) => Some(a.cmp(b)),
+        (Value::Boolean(a), Value::Boolean(b)) => Some(a.cmp(b)),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+// The variable a chain of field accesses and indexing starts from
+fn root(expr: &Expr) -> Option<&AsciiString> {
+    match expr {
+        Expr::Variable { name, .. } => Some(name),
+        Expr::Field { object, .. }
+        | Expr::TupleIndex { object, .. }
+        | Expr::Index { object, .. } => root(object),
+        Expr::Group { expresion } => root(expresion),
+        _ => None,
+    }
+}
+
+fn index(object: &Value, index: &Value, pos: &Position) -> Result<Value> {
+    match (object, index) {
+        (Value::Array(array), Value::Number(n)) if n.is_integer() => {
+            let array = array.borrow();
+            match n.as_index().and_then(|i| array.get(i)) {
+                Some(element) => Ok(element.clone()),
+                None => Error::IndexOutOfBounds(n.to_string(), array.len()).throw(pos),
+            }
+        }
+        (Value::Array(array), Value::Range(range)) => {
+            let array = array.borrow();
+            let (start, end) = slice(range, array.len(), pos)?;
+            Ok(Value::array(array[start..end].to_vec()))
+        }
+        (Value::String(s), Value::Number(n)) if n.is_integer() => {
+            let len = s.chars().count();
+            match n.as_index().and_then(|i| s.chars().nth(i)) {
+                Some(c) => Ok(Value::Char(c)),
+                None => Error::IndexOutOfBounds(n.to_string(), len).throw(pos),
+            }
+        }
+        (Value::Map(map), key) => match map.borrow().get(&Key::new(key, pos)?) {
+            Some(value) => Ok(value.clone()),
+            None => Error::MissingKey(key.repr()).throw(pos),
+        },
+        (Value::String(s), Value::Range(range)) => {
+            let (start, end) = slice(range, s.chars().count(), pos)?;
+            Ok(Value::String(
+                s.chars().skip(start).take(end - start).collect(),
+            ))
+        }
+        _ => Error::InvalidOperation(String::from("[]"), object.type_name(), index.type_name())
+            .throw(pos),
+    }
+}
+
+// The bounds a range selects out of a collection with `len` elements
+fn slice(range: &Range, len: usize, pos: &Position) -> Result<(usize, usize)> {
+    let start = match &range.start {
+        Some(start) => start.as_index(),
+        None => Some(0),
+    };
+    let end = match &range.end {
+        Some(end) => end.as_index().map(|end| end + usize::from(range.inclusive)),
+        None => Some(len),
+    };
+    match (start, end) {
+        (Some(start), Some(end)) if start <= end && end <= len => Ok((start, end)),
+        _ => Error::IndexOutOfBounds(Value::Range(range.clone()).to_string(), len).throw(pos),
+    }
+}
+
+fn range(
+    start: Option<Value>,
+    end: Option<Value>,
+    inclusive: bool,
+    pos: &Position,
+) -> Result<Range> {
+    let bound = |value: Option<Value>| match value {
+        None => Ok(None),
+        Some(Value::Number(n)) if n.is_integer() => Ok(Some(n)),
+        Some(value) => Err(value),
+    };
+    let operator = if inclusive { "..=" } else { ".." };
+    match (bound(start), bound(end)) {
+        (Ok(start), Ok(end)) => {
+            if let (Some(s), Some(e)) = (&start, &end) {
+                if s.type_name() != e.type_name() {
+                    return Error::InvalidOperation(
+                        operator.to_string(),
+                        s.type_name().to_string(),
+                        e.type_name().to_string(),
+                    )
+                    .throw(pos);
+                }
+            }
+            Ok(Range {
+                start,
+                end,
+                inclusive,
+            })
+        }
+        (Err(value), _) | (_, Err(value)) => {
+            Error::InvalidOperand(operator.to_string(), value.type_name()).throw(pos)
+        }
+    }
+}
+
+pub fn truthy(value: Value, operator: Operator, pos: &Position) -> Result<bool> {
+    match value {
+        Value::Boolean(b) => Ok(b),
+        value => Error::InvalidOperand(operator.to_string(), value.type_name()).throw(pos),
+    }
+}
+
+// `value as ty`, see `Number::cast` for how numbers change. `bool` and `char`
+// become integers and only a `u8` becomes a `char`.
+pub fn cast(value: Value, ty: &TypeExpr, pos: &Position) -> Result<Value> {
+    let name = match ty {
+        TypeExpr::Named { name, args, .. } if args.is_empty() => name.as_str(),
+        _ => "",
+    };
+    let integer = |n: Number| n.cast(name).ok().filter(Number::is_integer);
+    let cast = match &value {
+        Value::Number(Number::U8(n)) if name == "char" => Some(Value::Char(*n as char)),
+        Value::Number(n) => match n.cast(name) {
+            Ok(n) => Some(Value::Number(n)),
+            Err(Error::InvalidCast(..)) => None,
+            Err(error) => return error.throw(pos),
+        },
+        Value::Boolean(b) => integer(Number::U8(*b as u8)).map(Value::Number),
+        Value::Char(c) => integer(Number::U32(*c as u32)).map(Value::Number),
+        _ => None,
+    };
+    match cast {
+        Some(cast) => Ok(cast),
+        None => Error::InvalidCast(value.type_name(), ty.to_string()).throw(pos),
+    }
+}
+
+pub fn binary(left: &Value, operator: Operator, right: &Value, pos: &Position) -> Result<Value> {
+    let invalid = || {
+        Error::InvalidOperation(operator.to_string(), left.type_name(), right.type_name())
+            .throw(pos)
+    };
+
+    let ordering = match (left, right) {
+        (Value::Number(a), Value::Number(b)) => match operator {
+            Operator::Plus
+            | Operator::Minus
+            | Operator::Asterisk
+            | Operator::Slash
+            | Operator::Percent => {
+                return match a.arithmetic(operator, b) {
+                    Ok(n) => Ok(Value::Number(n)),
+                    Err(error) => error.throw(pos),
+                }
+            }
+            _ => match a.compare(b) {
+                Ok(ordering) => ordering,
+                Err(_) => return invalid(),
+            },
+        },
+        (Value::String(a), Value::String(b)) => {
+            if operator == Operator::Plus {
+                return Ok(Value::String(format!("{a}{b}")));
+            }
+            Some(a.cmp(b))
+        }
+        (Value::Char(a), Value::Char(b)) => Some(a.cmp(b)),
+        (Value::Boolean(a), Value::Boolean(b)) => match operator {
+            Operator::EqualTo | Operator::NotEqualTo => Some(a.cmp(b)),
+            _ => return invalid(),
+        },
+        (Value::Unit, Value::Unit) | (Value::Null, Value::Null) => Some(Ordering::Equal),
+        (Value::Instance(a), Value::Instance(b)) => match operator {
+            // Instances are equal when they are of the same struct and their fields are equal
+            Operator::EqualTo | Operator::NotEqualTo => {
+                let (a, b) = (a.borrow(), b.borrow());
+                let fields = a
+                    .fields
+                    .iter()
+                    .zip(&b.fields)
+                    .map(|((_, a), (_, b))| (a, b));
+                (Rc::ptr_eq(&a.ty, &b.ty) && all_equal(fields, pos)?).then_some(Ordering::Equal)
+            }
+            _ => return invalid(),
+        },
+        (Value::Tuple(a), Value::Tuple(b)) => match operator {
+            Operator::EqualTo | Operator::NotEqualTo => {
+                let equal = a.len() == b.len() && all_equal(a.iter().zip(b.iter()), pos)?;
+                equal.then_some(Ordering::Equal)
+            }
+            _ => return invalid(),
+        },
+        (Value::Variant(a), Value::Variant(b)) => match operator {
+            Operator::EqualTo | Operator::NotEqualTo => {
+                let same = Rc::ptr_eq(&a.ty, &b.ty) && a.index == b.index;
+                let equal = match (&a.payload, &b.payload) {
+                    (Payload::Tuple(a), Payload::Tuple(b)) => all_equal(a.iter().zip(b), pos)?,
+                    (Payload::Struct(a), Payload::Struct(b)) => {
+                        let fields = a.iter().zip(b).map(|((_, a), (_, b))| (a, b));
+                        all_equal(fields, pos)?
+                    }
+                    _ => true,
+                };
+                (same && equal).then_some(Ordering::Equal)
+            }
+            _ => return invalid(),
+        },
+        (Value::Array(a), Value::Array(b)) => match operator {
+            Operator::EqualTo | Operator::NotEqualTo => {
+                let (a, b) = (a.borrow(), b.borrow());
+                let mut equal = a.len() == b.len();
+                for (a, b) in a.iter().zip(b.iter()) {
+                    if !equal {
+                        break;
+                    }
+                    equal = matches!(binary(a, Operator::EqualTo, b, pos)?, Value::Boolean(true));
+                }
+                equal.then_some(Ordering::Equal)
+            }
+            _ => return invalid(),
+        },
+        (Value::Map(a), Value::Map(b)) => match operator {
+            // Maps are equal when they hold the same entries, in any order
+            Operator::EqualTo | Operator::NotEqualTo => {
+                let (a, b) = (a.borrow(), b.borrow());
+                let mut equal = a.len() == b.len();
+                for (key, a) in a.entries() {
+                    if !equal {
+                        break;
+                    }
+                    equal = match b.get(key) {
+                        Some(b) => {
+                            matches!(binary(a, Operator::EqualTo, b, pos)?, Value::Boolean(true))
+                        }
+                        None => false,
+                    };
+                }
+                equal.then_some(Ordering::Equal)
+            }
+            _ => return invalid(),
+        },
+        _ => match operator {
+            // Values of different kinds are never equal
+            Operator::EqualTo | Operator::NotEqualTo => None,
+            _ => return invalid(),
+        },
+    };
+
+    Ok(Value::Boolean(match operator {
+        Operator::EqualTo => ordering == Some(Ordering::Equal),
+        Operator::NotEqualTo => ordering != Some(Ordering::Equal),
+        Operator::LessThan => ordering == Some(Ordering::Less),
+        Operator::LessThanOrEqualTo => {
+            matches!(ordering, Some(Ordering::Less | Ordering::Equal))
+        }
+        Operator::GreaterThan => ordering == Some(Ordering::Greater),
+        Operator::GreaterThanOrEqualTo => {
+            matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+        }
+        _ => return invalid(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::Error, io::run};
+    use std::thread;
+
+    fn shown(source: &str) -> String {
+        run(source).unwrap().to_string()
+    }
+
+    #[test]
+    fn functions_return_their_last_value_or_what_they_return() {
+        assert_eq!(
+            shown("fn add(a: i32, b: i32) -> i32 { a + b } add(2, 3)"),
+            "5"
+        );
+        let sign = "fn sign(n: i32) -> i32 { if n < 0 { return -1; } 1 }";
+        assert_eq!(shown(&format!("{sign} sign(-5) + sign(5) * 10")), "9");
+        assert_eq!(shown("fn f() {} f()"), "()");
+        let fib = "fn fib(n: u64) -> u64 { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }";
+        assert_eq!(shown(&format!("{fib} fib(15)")), "610");
+        // Functions can be called before they are declared
+        assert_eq!(shown("fn a() -> i32 { b() } fn b() -> i32 { 2 } a()"), "2");
+    }
+
+    #[test]
+    fn calls_take_as_many_arguments_as_declared() {
+        let arity = |source| matches!(run(source), Err(Error::ArityMismatch(..)));
+        assert!(arity("fn f(a: i32) {} f()"));
+        assert!(arity("fn f(a: i32) {} f(1, 2)"));
+        assert!(matches!(run("let x = 1; x()"), Err(Error::NotCallable(_))));
+    }
+
+    #[test]
+    fn blocks_ifs_and_loops_are_expressions() {
+        assert_eq!(shown("let x = { let a = 2; a * 3 }; x"), "6");
+        assert_eq!(shown("{ 1; }"), "()");
+        let sign =
+            "fn sign(n: i32) -> char { if n < 0 { '-' } else if n > 0 { '+' } else { '0' } }";
+        let signs = shown(&format!("{sign} [sign(-2), sign(0), sign(3)]"));
+        assert_eq!(signs, "['-', '0', '+']");
+        let source = "let mut i = 0; let x = loop { i = i + 1; if i == 4 { break i * 10; } }; x";
+        assert_eq!(shown(source), "40");
+        let source = "let mut n = 0; loop { n = n + 1; if n < 3 { continue; } break; } n";
+        assert_eq!(shown(source), "3");
+    }
+
+    #[test]
+    fn branches_yield_values_of_one_type() {
+        let branches = |source| matches!(run(source), Err(Error::IncompatibleBranches(..)));
+        assert!(branches("let x = if true { 1 } else { \"a\" };"));
+        // Without `else` the missing branch yields `()`
+        assert!(branches("let x: i32 = if true { 1 };"));
+        assert!(matches!(run("if 1 { 2 }"), Err(Error::InvalidCondition(_))));
+        assert!(matches!(run("break;"), Err(Error::Misplaced(..))));
+    }
+
+    #[test]
+    fn arrays_are_indexed_and_sliced() {
+        let a = "let mut a = [1, 2, 3, 4];";
+        assert_eq!(
+            shown(&format!("{a} (a[0], a[3], [[1], [2, 3]][1][0])")),
+            "(1, 4, 2)"
+        );
+        assert_eq!(
+            shown(&format!("{a} (a[1..3], a[..2], a[2..], a[1..=2])")),
+            "([2, 3], [1, 2], [3, 4], [2, 3])"
+        );
+        assert_eq!(shown(&format!("{a} a[0] = 10; a[4..]")), "[]");
+        assert_eq!(shown(&format!("{a} a[1] = 20; a")), "[1, 20, 3, 4]");
+        let out_of_bounds = |index| {
+            let source = format!("{a} a[{index}]");
+            matches!(run(&source), Err(Error::IndexOutOfBounds(..)))
+        };
+        assert!(out_of_bounds("4") && out_of_bounds("-1") && out_of_bounds("2..5"));
+        assert!(matches!(run("[1, \"a\"]"), Err(Error::MismatchedTypes(..))));
+    }
+
+    #[test]
+    fn structs_are_built_changed_and_given_methods() {
+        let point = "struct Point { x: i32, y: i32 }
+            impl Point {
+                fn new(x: i32, y: i32) -> Point { Point { x: x, y } }
+                fn sum(self) -> i32 { self.x + self.y }
+                fn shift(mut self, by: i32) { self.x = self.x + by; }
+            }";
+        let source = format!("{point} let mut p = Point::new(1, 2); p.y = 5; p.shift(10); p");
+        assert_eq!(shown(&source), "Point { x: 11, y: 5 }");
+        assert_eq!(shown(&format!("{point} Point {{ y: 3, x: 4 }}.sum()")), "7");
+        let p = "struct P { x: i32 } let p = P { x: 1 };";
+        let immutable = run(&format!("{p} p.x = 2;"));
+        assert!(matches!(immutable, Err(Error::ImmutableAssignment(..))));
+        assert!(matches!(
+            run(&format!("{p} p.z")),
+            Err(Error::UnknownField(..))
+        ));
+        assert!(matches!(
+            run("struct P { x: i32 } P {}"),
+            Err(Error::MissingField(..))
+        ));
+        let unknown = run("struct P { x: i32 } P { x: 1, y: 2 }");
+        assert!(matches!(unknown, Err(Error::UnknownField(..))));
+        assert!(matches!(
+            run(&format!("{p} p.f()")),
+            Err(Error::UnknownMethod(..))
+        ));
+    }
+
+    #[test]
+    fn enum_variants_are_built_with_their_payloads() {
+        let shape = "enum Shape { Circle(f64), Rect { w: f64, h: f64 }, Empty }";
+        let source = format!("{shape} (Shape::Circle(1.5), Shape::Rect {{ w: 1.0, h: 2.0 }})");
+        assert_eq!(
+            shown(&source),
+            "(Shape::Circle(1.5), Shape::Rect { w: 1.0, h: 2.0 })"
+        );
+        let source =
+            format!("{shape} (Shape::Empty == Shape::Empty, Shape::Circle(1.0) == Shape::Empty)");
+        assert_eq!(shown(&source), "(true, false)");
+        let e = "enum E { A(i32) }";
+        assert!(matches!(
+            run(&format!("{e} E::B")),
+            Err(Error::UnknownVariant(..))
+        ));
+        assert!(matches!(
+            run(&format!("{e} E::A(1, 2)")),
+            Err(Error::ArityMismatch(..))
+        ));
+        let payload = run(&format!("{e} E::A(\"a\")"));
+        assert!(matches!(payload, Err(Error::MismatchedTypes(..))));
+    }
+
+    #[test]
+    fn tuples_hold_several_values() {
+        let divmod = "fn divmod(a: i32, b: i32) -> (i32, i32) { (a / b, a % b) }";
+        let source = format!("{divmod} let t = divmod(7, 2); (t, t.0, t.1, (1,), ())");
+        assert_eq!(shown(&source), "((3, 1), 3, 1, (1,), ())");
+        assert_eq!(
+            shown("let t = ((1, 2), 3); (t.0.1, t.1, (1.5, 2).0)"),
+            "(2, 3, 1.5)"
+        );
+        assert_eq!(shown("let mut t = (1, 2); t.0 = 5; t"), "(5, 2)");
+        let out_of_bounds = run("let t = (1, 2); t.2");
+        assert!(matches!(out_of_bounds, Err(Error::IndexOutOfBounds(..))));
+        let mismatched = run("let t: (i32, String) = (1, 2);");
+        assert!(matches!(mismatched, Err(Error::MismatchedTypes(..))));
+    }
+
+    #[test]
+    fn unbounded_recursion_overflows_the_stack() {
+        // The interpreter nests deeply before it reports the overflow
+        let overflows = thread::Builder::new()
+            .stack_size(1 << 30)
+            .spawn(|| {
+                let source = "fn f(n: i32) -> i32 { f(n + 1) } f(0)";
+                matches!(run(source), Err(Error::StackOverflow(name)) if name == "f")
+            })
+            .unwrap();
+        assert!(overflows.join().unwrap());
+    }
+}
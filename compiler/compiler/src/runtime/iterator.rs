@@ -0,0 +1,166 @@
+use super::{
+    interpreter::Interpreter,
+    value::{Range, Value},
+};
+use crate::{
+    ast::token::{Number, Operator},
+    error::*,
+    position::Position,
+};
+use ascii::AsciiString;
+use std::{cmp::Ordering, rc::Rc, vec};
+
+// The state of a `loop pattern in iterable`
+pub enum Iter {
+    Range {
+        next: Option<Number>,
+        end: Option<Number>,
+        inclusive: bool,
+    },
+    Chars(vec::IntoIter<char>),
+    // A snapshot of the elements of a collection
+    Items(vec::IntoIter<Value>),
+    // Anything callable without arguments, exhausted once it returns `?`
+    Function(Value),
+    // A struct or an enum whose `next` method is called until it returns `?`
+    Next(Value),
+}
+
+impl Iter {
+    pub fn new(iterable: Value, pos: &Position) -> Result<Self> {
+        Ok(match iterable {
+            Value::Range(Range {
+                start: Some(start),
+                end,
+                inclusive,
+            }) => Iter::Range {
+                next: Some(start),
+                end,
+                inclusive,
+            },
+            Value::String(s) => Iter::Chars(s.chars().collect::<Vec<_>>().into_iter()),
+            Value::Array(array) => Iter::Items(array.borrow().clone().into_iter()),
+            // Maps yield `(key, value)` tuples in insertion order
+            Value::Map(map) => Iter::Items(
+                map.borrow()
+                    .entries()
+                    .map(|(key, value)| Value::Tuple(Rc::new(vec![key.value(), value.clone()])))
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            ),
+            Value::Function(_) | Value::Native(_) => Iter::Function(iterable),
+            Value::Instance(ref instance) if instance.borrow().ty.method(&next()).is_some() => {
+                Iter::Next(iterable)
+            }
+            Value::Variant(ref variant) if variant.ty.method(&next()).is_some() => {
+                Iter::Next(iterable)
+            }
+            value => return Error::NotIterable(value.type_name()).throw(pos),
+        })
+    }
+
+    pub fn next(&mut self, interpreter: &mut Interpreter, pos: &Position) -> Result<Option<Value>> {
+        Ok(match self {
+            Iter::Range {
+                next,
+                end,
+                inclusive,
+            } => {
+                let Some(current) = next.take() else {
+                    return Ok(None);
+                };
+                if let Some(end) = end {
+                    let ordering = current.compare(end).unwrap_or(None);
+                    let done = match ordering {
+                        Some(Ordering::Less) => false,
+                        Some(Ordering::Equal) => !*inclusive,
+                        _ => true,
+                    };
+                    if done {
+                        return Ok(None);
+                    }
+                }
+                // Running past the largest value of the type ends the range
+                *next = current.arithmetic(Operator::Plus, &current.one()).ok();
+                Some(Value::Number(current))
+            }
+            Iter::Chars(chars) => chars.next().map(Value::Char),
+            Iter::Items(items) => items.next(),
+            Iter::Function(function) => {
+                match interpreter.call(function.clone(), Vec::new(), pos)? {
+                    Value::Null => None,
+                    value => Some(value),
+                }
+            }
+            Iter::Next(iterator) => {
+                let receiver = vec![iterator.clone()];
+                match interpreter.call_method(iterator.clone(), &next(), receiver, pos)? {
+                    Value::Null => None,
+                    value => Some(value),
+                }
+            }
+        })
+    }
+}
+
+fn next() -> AsciiString {
+    AsciiString::from_ascii("next").unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::Error, io::run};
+
+    // The items a `loop .. in` goes through, collected into an array
+    fn items(iterable: &str) -> String {
+        let source =
+            format!("let mut items = []; loop item in {iterable} {{ items.push(item); }} items");
+        run(&source).unwrap()
+    }
+
+    #[test]
+    fn ranges_count_up_to_their_end() {
+        assert_eq!(items("0..4"), "[0, 1, 2, 3]");
+        assert_eq!(items("1..=3"), "[1, 2, 3]");
+        assert_eq!(items("5..2"), "[]");
+        assert_eq!(items("250u8..=255"), "[250, 251, 252, 253, 254, 255]");
+        let open = "let mut sum = 0; loop i in 10.. { if i == 13 { break; } sum = sum + i; } sum";
+        assert_eq!(run(open).unwrap(), "33");
+    }
+
+    #[test]
+    fn strings_and_next_methods_are_iterated() {
+        assert_eq!(items("\"abc\""), "['a', 'b', 'c']");
+        let count = "struct Count { n: i32 }
+            impl Count {
+                fn next(mut self) -> i32? {
+                    if self.n == 0 { return ?; }
+                    self.n = self.n - 1;
+                    self.n
+                }
+            }";
+        let source = format!(
+            "{count} let mut down = []; loop n in (Count {{ n: 3 }}) {{ down.push(n); }} down"
+        );
+        assert_eq!(run(&source).unwrap(), "[2, 1, 0]");
+        assert!(matches!(run("loop x in 5 {}"), Err(Error::NotIterable(_))));
+    }
+
+    #[test]
+    fn labels_pick_the_loop_to_leave() {
+        let source = "let mut pairs = 0;
+            'outer: loop i in 0..3 {
+                loop j in 0..3 {
+                    if j > i { continue 'outer; }
+                    if i == 2 { break 'outer; }
+                    pairs = pairs + 1;
+                }
+            }
+            pairs";
+        assert_eq!(run(source).unwrap(), "3");
+        assert!(matches!(
+            run("loop { break 'a; }"),
+            Err(Error::UndeclaredLabel(_))
+        ));
+    }
+}
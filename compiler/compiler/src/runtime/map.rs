@@ -0,0 +1,215 @@
+use super::value::Value;
+use crate::{ast::token::Number, error::*, position::Position};
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    mem,
+    rc::Rc,
+};
+
+// A hashable value. Numbers of different widths are different keys, `-0.0`
+// is the same key as `0.0`, `1.10d` the same as `1.1d` and NaN is never a key
+// since it is not equal to itself.
+#[derive(Clone)]
+pub enum Key {
+    Null,
+    Char(char),
+    Boolean(bool),
+    Number(Number),
+    String(String),
+}
+
+impl Key {
+    pub fn new(value: &Value, pos: &Position) -> Result<Self> {
+        Ok(match value {
+            Value::Null => Key::Null,
+            Value::Char(c) => Key::Char(*c),
+            Value::Boolean(b) => Key::Boolean(*b),
+            Value::Number(Number::F32(n)) if n.is_nan() => {
+                return Error::InvalidKey(String::from("NaN")).throw(pos)
+            }
+            Value::Number(Number::F64(n)) if n.is_nan() => {
+                return Error::InvalidKey(String::from("NaN")).throw(pos)
+            }
+            Value::Number(Number::F32(n)) if *n == 0.0 => Key::Number(Number::F32(0.0)),
+            Value::Number(Number::F64(n)) if *n == 0.0 => Key::Number(Number::F64(0.0)),
+            Value::Number(Number::Decimal(n)) => {
+                Key::Number(Number::Decimal(Rc::new(n.normalized())))
+            }
+            Value::Number(n) => Key::Number(n.clone()),
+            Value::String(s) => Key::String(s.clone()),
+            value => return Error::InvalidKey(value.type_name()).throw(pos),
+        })
+    }
+
+    pub fn value(&self) -> Value {
+        match self {
+            Key::Null => Value::Null,
+            Key::Char(c) => Value::Char(*c),
+            Key::Boolean(b) => Value::Boolean(*b),
+            Key::Number(n) => Value::Number(n.clone()),
+            Key::String(s) => Value::String(s.clone()),
+        }
+    }
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Key::Null, Key::Null) => true,
+            (Key::Char(a), Key::Char(b)) => a == b,
+            (Key::Boolean(a), Key::Boolean(b)) => a == b,
+            // NaN is rejected, so floats compare like any other number
+            (Key::Number(a), Key::Number(b)) => a == b,
+            (Key::String(a), Key::String(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Key {}
+
+impl Hash for Key {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Key::Null => {}
+            Key::Char(c) => c.hash(state),
+            Key::Boolean(b) => b.hash(state),
+            Key::Number(n) => {
+                mem::discriminant(n).hash(state);
+                match n {
+                    Number::I8(n) => n.hash(state),
+                    Number::I16(n) => n.hash(state),
+                    Number::I32(n) => n.hash(state),
+                    Number::I64(n) => n.hash(state),
+                    Number::I128(n) => n.hash(state),
+                    Number::ISize(n) => n.hash(state),
+                    Number::U8(n) => n.hash(state),
+                    Number::U16(n) => n.hash(state),
+                    Number::U32(n) => n.hash(state),
+                    Number::U64(n) => n.hash(state),
+                    Number::U128(n) => n.hash(state),
+                    Number::USize(n) => n.hash(state),
+                    Number::F32(n) => n.to_bits().hash(state),
+                    Number::F64(n) => n.to_bits().hash(state),
+                    Number::BigInt(n) => n.hash(state),
+                    Number::Decimal(n) => n.hash(state),
+                }
+            }
+            Key::String(s) => s.hash(state),
+        }
+    }
+}
+
+// A hash map that remembers the order its keys were inserted in
+#[derive(Clone, Default)]
+pub struct Map {
+    entries: Vec<(Key, Value)>,
+    indices: HashMap<Key, usize>,
+}
+
+impl Map {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &Key) -> Option<&Value> {
+        self.indices.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    // Returns the previous value, keeping the key at its original position
+    pub fn insert(&mut self, key: Key, value: Value) -> Option<Value> {
+        match self.indices.get(&key) {
+            Some(&i) => Some(mem::replace(&mut self.entries[i].1, value)),
+            None => {
+                self.indices.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &Key) -> Option<Value> {
+        let i = self.indices.remove(key)?;
+        let (_, value) = self.entries.remove(i);
+        for (key, _) in &self.entries[i..] {
+            if let Some(index) = self.indices.get_mut(key) {
+                *index -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &(Key, Value)> {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Key, Map, Value};
+    use crate::{
+        ast::token::Number, error::Error, io::run, position::Position, runtime::decimal::Decimal,
+    };
+    use std::rc::Rc;
+
+    fn key(value: Value) -> Key {
+        Key::new(&value, &Position::new("test")).unwrap()
+    }
+
+    fn number(n: Number) -> Key {
+        key(Value::Number(n))
+    }
+
+    #[test]
+    fn keys_stay_in_insertion_order() {
+        let mut map = Map::default();
+        for (i, name) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            map.insert(
+                key(Value::String(name.to_owned())),
+                Value::Number(Number::I32(i as i32)),
+            );
+        }
+        assert!(map
+            .insert(key(Value::String("a".to_owned())), Value::Null)
+            .is_some());
+        assert!(map.remove(&key(Value::String("b".to_owned()))).is_some());
+        let keys = map.entries().map(|(key, _)| key.value().to_string());
+        assert_eq!(keys.collect::<Vec<_>>(), ["a", "c", "d"]);
+        // Removing shifts the later entries, which are still found
+        assert!(map.get(&key(Value::String("d".to_owned()))).is_some());
+        assert_eq!(map.len(), 3);
+    }
+
+    #[test]
+    fn equal_numbers_of_one_type_are_one_key() {
+        assert!(number(Number::F64(-0.0)) == number(Number::F64(0.0)));
+        let decimal = |digits| Number::Decimal(Rc::new(Decimal::parse(digits).unwrap()));
+        assert!(number(decimal("1.10")) == number(decimal("1.1")));
+        assert!(number(Number::I32(1)) != number(Number::I64(1)));
+        let nan = Value::Number(Number::F64(f64::NAN));
+        assert!(matches!(
+            Key::new(&nan, &Position::new("test")),
+            Err(Error::InvalidKey(_))
+        ));
+    }
+
+    #[test]
+    fn maps_are_written_and_changed_like_in_source() {
+        let m = "let mut m = #{\"a\": 1, \"b\": 2};";
+        let source = format!("{m} m.insert(\"c\", 3); m[\"a\"] = 5; m.remove(\"b\"); m");
+        assert_eq!(run(&source).unwrap(), "#{\"a\": 5, \"c\": 3}");
+        let source = format!("{m} (m.get(\"z\"), m.contains(\"a\"), m.keys(), m.values())");
+        assert_eq!(run(&source).unwrap(), "(?, true, [\"a\", \"b\"], [1, 2])");
+        assert!(matches!(
+            run(&format!("{m} m[\"z\"]")),
+            Err(Error::MissingKey(_))
+        ));
+        assert!(matches!(run("#{[1]: 2}"), Err(Error::InvalidKey(_))));
+    }
+}
@@ -0,0 +1,9 @@
+pub mod bigint;
+pub mod builtins;
+pub mod decimal;
+pub mod environment;
+pub mod interpreter;
+pub mod iterator;
+pub mod map;
+pub mod number;
+pub mod value;
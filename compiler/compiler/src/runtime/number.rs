@@ -0,0 +1,495 @@
+use super::{bigint::BigInt, decimal::Decimal};
+use crate::{
+    ast::token::{Number, Operator},
+    error::Error,
+};
+use std::{cmp::Ordering, fmt::Display, rc::Rc, result};
+
+macro_rules! arithmetic {
+    ($lhs:expr, $operator:expr, $rhs:expr; $($int:ident),*; $($float:ident),*) => {
+        match ($lhs, $rhs) {
+            $((&Number::$int(a), &Number::$int(b)) => {
+                if b == 0 && matches!($operator, Operator::Slash | Operator::Percent) {
+                    return Err(Error::DivisionByZero);
+                }
+                match $operator {
+                    Operator::Plus => a.checked_add(b),
+                    Operator::Minus => a.checked_sub(b),
+                    Operator::Asterisk => a.checked_mul(b),
+                    Operator::Slash => a.checked_div(b),
+                    Operator::Percent => a.checked_rem(b),
+                    _ => return Err($lhs.invalid($operator, $rhs)),
+                }
+                .map(Number::$int)
+            })*
+            $((&Number::$float(a), &Number::$float(b)) => Some(Number::$float(match $operator {
+                Operator::Plus => a + b,
+                Operator::Minus => a - b,
+                Operator::Asterisk => a * b,
+                Operator::Slash => a / b,
+                Operator::Percent => a % b,
+                _ => return Err($lhs.invalid($operator, $rhs)),
+            })),)*
+            (Number::BigInt(a), Number::BigInt(b)) => Some(Number::BigInt(Rc::new(match $operator {
+                Operator::Plus => &**a + b,
+                Operator::Minus => &**a - b,
+                Operator::Asterisk => &**a * b,
+                Operator::Slash => a.div_rem(b).ok_or(Error::DivisionByZero)?.0,
+                Operator::Percent => a.div_rem(b).ok_or(Error::DivisionByZero)?.1,
+                _ => return Err($lhs.invalid($operator, $rhs)),
+            }))),
+            (Number::Decimal(a), Number::Decimal(b)) => Some(Number::Decimal(Rc::new(match $operator {
+                Operator::Plus => &**a + b,
+                Operator::Minus => &**a - b,
+                Operator::Asterisk => &**a * b,
+                Operator::Slash => a.checked_div(b).ok_or(Error::DivisionByZero)?,
+                Operator::Percent => a.checked_rem(b).ok_or(Error::DivisionByZero)?,
+                _ => return Err($lhs.invalid($operator, $rhs)),
+            }))),
+            _ => return Err($lhs.invalid($operator, $rhs)),
+        }
+    };
+}
+
+macro_rules! compare {
+    ($lhs:expr, $rhs:expr; $($variant:ident),*) => {
+        match ($lhs, $rhs) {
+            $((Number::$variant(a), Number::$variant(b)) => a.partial_cmp(b),)*
+            (Number::Decimal(a), Number::Decimal(b)) => Some(a.compare(b)),
+            _ => return Err($lhs.invalid(Operator::EqualTo, $rhs)),
+        }
+    };
+}
+
+// `number as suffix` for every pair of fixed width number types, with the
+// semantics of Rust's `as`
+macro_rules! cast {
+    ($number:expr, $suffix:expr; $($variant:ident),*) => {
+        match $number {
+            $(Number::$variant(n) => cast!(@into n, $suffix),)*
+            Number::BigInt(_) | Number::Decimal(_) => None,
+        }
+    };
+    (@into $n:expr, $suffix:expr) => {
+        Some(match $suffix {
+            "i8" => Number::I8($n as i8),
+            "i16" => Number::I16($n as i16),
+            "i32" => Number::I32($n as i32),
+            "i64" => Number::I64($n as i64),
+            "i128" => Number::I128($n as i128),
+            "isize" => Number::ISize($n as isize),
+            "u8" => Number::U8($n as u8),
+            "u16" => Number::U16($n as u16),
+            "u32" => Number::U32($n as u32),
+            "u64" => Number::U64($n as u64),
+            "u128" => Number::U128($n as u128),
+            "usize" => Number::USize($n as usize),
+            "f32" => Number::F32($n as f32),
+            "f64" => Number::F64($n as f64),
+            _ => return None,
+        })
+    };
+}
+
+impl Number {
+    // The digits of a literal as a number of the type `suffix` names
+    pub fn parse(number: &str, suffix: &str) -> result::Result<Self, Error> {
+        let overflow = || Error::NumOverFlow(String::from(number), String::from(suffix));
+        let float_overflow = |_| overflow();
+        match suffix {
+            "n" | "bigint" => {
+                return BigInt::parse(number)
+                    .map(|n| Number::BigInt(Rc::new(n)))
+                    .ok_or_else(|| Error::InvalidSuffix(String::from(suffix)))
+            }
+            "d" | "decimal" => {
+                return Decimal::parse(number)
+                    .map(|n| Number::Decimal(Rc::new(n)))
+                    .ok_or_else(|| Error::InvalidSuffix(String::from(suffix)))
+            }
+            _ => {}
+        }
+        if number.contains('.') {
+            return Ok(match suffix {
+                "f32" => Number::F32(number.parse().map_err(float_overflow)?),
+                "f" | "f64" => Number::F64(number.parse().map_err(float_overflow)?),
+                _ => return Err(Error::InvalidSuffix(String::from(suffix))),
+            });
+        }
+        let int_overflow = |_| overflow();
+
+        Ok(match suffix {
+            "i8" => Number::I8(number.parse().map_err(int_overflow)?),
+            "i16" => Number::I16(number.parse().map_err(int_overflow)?),
+            "i32" => Number::I32(number.parse().map_err(int_overflow)?),
+            "i64" => Number::I64(number.parse().map_err(int_overflow)?),
+            "i128" => Number::I128(number.parse().map_err(int_overflow)?),
+            "isize" => Number::ISize(number.parse().map_err(int_overflow)?),
+            "u8" => Number::U8(number.parse().map_err(int_overflow)?),
+            "u16" => Number::U16(number.parse().map_err(int_overflow)?),
+            "u" | "u32" => Number::U32(number.parse().map_err(int_overflow)?),
+            "u64" => Number::U64(number.parse().map_err(int_overflow)?),
+            "u128" => Number::U128(number.parse().map_err(int_overflow)?),
+            "usize" => Number::USize(number.parse().map_err(int_overflow)?),
+            "f32" => Number::F32(number.parse().map_err(float_overflow)?),
+            "f" | "f64" => Number::F64(number.parse().map_err(float_overflow)?),
+            _ => return Err(Error::InvalidSuffix(String::from(suffix))),
+        })
+    }
+
+    pub const fn type_name(&self) -> &'static str {
+        match self {
+            Number::I8(_) => "i8",
+            Number::I16(_) => "i16",
+            Number::I32(_) => "i32",
+            Number::I64(_) => "i64",
+            Number::I128(_) => "i128",
+            Number::ISize(_) => "isize",
+            Number::U8(_) => "u8",
+            Number::U16(_) => "u16",
+            Number::U32(_) => "u32",
+            Number::U64(_) => "u64",
+            Number::U128(_) => "u128",
+            Number::USize(_) => "usize",
+            Number::F32(_) => "f32",
+            Number::F64(_) => "f64",
+            Number::BigInt(_) => "bigint",
+            Number::Decimal(_) => "decimal",
+        }
+    }
+
+    pub const fn is_integer(&self) -> bool {
+        !matches!(self, Number::F32(_) | Number::F64(_) | Number::Decimal(_))
+    }
+
+    // The value as an index into a collection, `None` for negative numbers and floats
+    pub fn as_index(&self) -> Option<usize> {
+        match *self {
+            Number::I8(n) => usize::try_from(n).ok(),
+            Number::I16(n) => usize::try_from(n).ok(),
+            Number::I32(n) => usize::try_from(n).ok(),
+            Number::I64(n) => usize::try_from(n).ok(),
+            Number::I128(n) => usize::try_from(n).ok(),
+            Number::ISize(n) => usize::try_from(n).ok(),
+            Number::U8(n) => Some(n.into()),
+            Number::U16(n) => Some(n.into()),
+            Number::U32(n) => usize::try_from(n).ok(),
+            Number::U64(n) => usize::try_from(n).ok(),
+            Number::U128(n) => usize::try_from(n).ok(),
+            Number::USize(n) => Some(n),
+            Number::BigInt(ref n) => n.to_string().parse().ok(),
+            Number::F32(_) | Number::F64(_) | Number::Decimal(_) => None,
+        }
+    }
+
+    fn invalid(&self, operator: Operator, rhs: &Self) -> Error {
+        Error::InvalidOperation(
+            operator.to_string(),
+            self.type_name().to_string(),
+            rhs.type_name().to_string(),
+        )
+    }
+
+    pub fn arithmetic(&self, operator: Operator, rhs: &Self) -> result::Result<Self, Error> {
+        let result = arithmetic!(
+            self, operator, rhs;
+            I8, I16, I32, I64, I128, ISize, U8, U16, U32, U64, U128, USize;
+            F32, F64
+        );
+        result.ok_or_else(|| {
+            Error::NumOverFlow(
+                format!("{self} {operator} {rhs}"),
+                self.type_name().to_string(),
+            )
+        })
+    }
+
+    pub fn compare(&self, rhs: &Self) -> result::Result<Option<Ordering>, Error> {
+        Ok(compare!(
+            self, rhs;
+            I8, I16, I32, I64, I128, ISize, U8, U16, U32, U64, U128, USize, F32, F64, BigInt
+        ))
+    }
+
+    pub fn negate(&self) -> result::Result<Self, Error> {
+        let overflow = || Error::NumOverFlow(format!("-{self}"), self.type_name().to_string());
+        Ok(match *self {
+            Number::I8(n) => Number::I8(n.checked_neg().ok_or_else(overflow)?),
+            Number::I16(n) => Number::I16(n.checked_neg().ok_or_else(overflow)?),
+            Number::I32(n) => Number::I32(n.checked_neg().ok_or_else(overflow)?),
+            Number::I64(n) => Number::I64(n.checked_neg().ok_or_else(overflow)?),
+            Number::I128(n) => Number::I128(n.checked_neg().ok_or_else(overflow)?),
+            Number::ISize(n) => Number::ISize(n.checked_neg().ok_or_else(overflow)?),
+            Number::F32(n) => Number::F32(-n),
+            Number::F64(n) => Number::F64(-n),
+            Number::BigInt(ref n) => Number::BigInt(Rc::new(-(**n).clone())),
+            Number::Decimal(ref n) => Number::Decimal(Rc::new(-(**n).clone())),
+            _ => {
+                return Err(Error::InvalidOperand(
+                    Operator::Minus.to_string(),
+                    self.type_name().to_string(),
+                ))
+            }
+        })
+    }
+
+    // `self as ty`, which fails only for a `ty` that is not a number type and
+    // for NaN and infinities cast to `bigint` or `decimal`:
+    // - integer to integer keeps the lowest bits of the two's complement, so
+    //   widening keeps the value, narrowing wraps around and a change of sign
+    //   reinterprets it, as in `-1i32 as u8 == 255`. A `bigint` narrows the
+    //   same way.
+    // - integer to float rounds to the nearest float, as in
+    //   `16777217 as f32 == 16777216.0`, or is infinite beyond its range
+    // - float to integer drops the fraction and saturates at the bounds of the
+    //   integer, `NaN` becomes `0` and infinities the minimum or maximum
+    // - `f64` to `f32` rounds to the nearest `f32`, the ones beyond its range
+    //   become infinities, and `f32` to `f64` keeps the value
+    // - any integer to `bigint` or `decimal` keeps the value, a float becomes
+    //   the shortest decimal it prints as, so `0.1 as decimal == 0.1d`
+    // - `decimal` to an integer drops the fraction, then narrows like `bigint`,
+    //   and to a float rounds like integers do
+    pub fn cast(&self, ty: &str) -> result::Result<Self, Error> {
+        let invalid = || Error::InvalidCast(self.type_name().to_string(), ty.to_string());
+        let float = matches!(ty, "f32" | "f64");
+        match self {
+            _ if ty == "bigint" => return Ok(Number::BigInt(Rc::new(self.to_bigint()?))),
+            _ if ty == "decimal" => return Ok(Number::Decimal(Rc::new(self.to_decimal()?))),
+            // The digits parse to the nearest float
+            Number::BigInt(_) | Number::Decimal(_) if float => {
+                return Number::parse(&self.to_string(), ty).map_err(|_| invalid())
+            }
+            Number::BigInt(n) => return Number::U128(n.wrapping_u128()).cast(ty),
+            Number::Decimal(n) => return Number::U128(n.trunc().wrapping_u128()).cast(ty),
+            _ => {}
+        }
+        self.fixed_cast(ty).ok_or_else(invalid)
+    }
+
+    #[allow(clippy::unnecessary_cast)]
+    fn fixed_cast(&self, ty: &str) -> Option<Self> {
+        cast!(
+            *self, ty;
+            I8, I16, I32, I64, I128, ISize, U8, U16, U32, U64, U128, USize, F32, F64
+        )
+    }
+
+    // `self as ty` only when the cast keeps the value, which converts back to
+    // `self` and keeps its sign. `NaN` stays `NaN` as a float.
+    pub fn convert(&self, ty: &str) -> Option<Self> {
+        let converted = self.cast(ty).ok()?;
+        let back = converted.cast(self.type_name()).ok()?;
+        let same = matches!(back.compare(self), Ok(Some(Ordering::Equal)))
+            && self.is_negative() == converted.is_negative();
+        (same || self.is_nan() && converted.is_nan()).then_some(converted)
+    }
+
+    fn to_bigint(&self) -> result::Result<BigInt, Error> {
+        Ok(match *self {
+            Number::I8(n) => BigInt::from_i128(n.into()),
+            Number::I16(n) => BigInt::from_i128(n.into()),
+            Number::I32(n) => BigInt::from_i128(n.into()),
+            Number::I64(n) => BigInt::from_i128(n.into()),
+            Number::I128(n) => BigInt::from_i128(n),
+            Number::ISize(n) => BigInt::from_i128(n as i128),
+            Number::U8(n) => BigInt::from_u128(n.into()),
+            Number::U16(n) => BigInt::from_u128(n.into()),
+            Number::U32(n) => BigInt::from_u128(n.into()),
+            Number::U64(n) => BigInt::from_u128(n.into()),
+            Number::U128(n) => BigInt::from_u128(n),
+            Number::USize(n) => BigInt::from_u128(n as u128),
+            Number::F32(n) => {
+                BigInt::from_f64(n.into()).ok_or_else(|| self.non_finite("bigint"))?
+            }
+            Number::F64(n) => BigInt::from_f64(n).ok_or_else(|| self.non_finite("bigint"))?,
+            Number::BigInt(ref n) => (**n).clone(),
+            Number::Decimal(ref n) => n.trunc(),
+        })
+    }
+
+    fn to_decimal(&self) -> result::Result<Decimal, Error> {
+        Ok(match *self {
+            Number::F32(n) => Decimal::from_float(n).ok_or_else(|| self.non_finite("decimal"))?,
+            Number::F64(n) => Decimal::from_float(n).ok_or_else(|| self.non_finite("decimal"))?,
+            Number::Decimal(ref n) => (**n).clone(),
+            _ => Decimal::from_bigint(self.to_bigint()?),
+        })
+    }
+
+    fn non_finite(&self, ty: &str) -> Error {
+        Error::NonFinite(self.to_string(), ty.to_string())
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match *self {
+            Number::I8(n) => n < 0,
+            Number::I16(n) => n < 0,
+            Number::I32(n) => n < 0,
+            Number::I64(n) => n < 0,
+            Number::I128(n) => n < 0,
+            Number::ISize(n) => n < 0,
+            Number::F32(n) => n < 0.0,
+            Number::F64(n) => n < 0.0,
+            Number::BigInt(ref n) => n.is_negative(),
+            Number::Decimal(ref n) => n.is_negative(),
+            _ => false,
+        }
+    }
+
+    fn is_nan(&self) -> bool {
+        match *self {
+            Number::F32(n) => n.is_nan(),
+            Number::F64(n) => n.is_nan(),
+            _ => false,
+        }
+    }
+
+    // The `1` of the same width, used by `++` and `--`
+    pub fn one(&self) -> Self {
+        match self {
+            Number::I8(_) => Number::I8(1),
+            Number::I16(_) => Number::I16(1),
+            Number::I32(_) => Number::I32(1),
+            Number::I64(_) => Number::I64(1),
+            Number::I128(_) => Number::I128(1),
+            Number::ISize(_) => Number::ISize(1),
+            Number::U8(_) => Number::U8(1),
+            Number::U16(_) => Number::U16(1),
+            Number::U32(_) => Number::U32(1),
+            Number::U64(_) => Number::U64(1),
+            Number::U128(_) => Number::U128(1),
+            Number::USize(_) => Number::USize(1),
+            Number::F32(_) => Number::F32(1.0),
+            Number::F64(_) => Number::F64(1.0),
+            Number::BigInt(_) => Number::BigInt(Rc::new(BigInt::from_u128(1))),
+            Number::Decimal(_) => {
+                Number::Decimal(Rc::new(Decimal::from_bigint(BigInt::from_u128(1))))
+            }
+        }
+    }
+}
+
+// Parsing what a number prints as, with the suffix of its type, gives back
+// the same number. Decimals keep the digits they are written with.
+impl Display for Number {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Number::I8(n) => write!(f, "{n}"),
+            Number::I16(n) => write!(f, "{n}"),
+            Number::I32(n) => write!(f, "{n}"),
+            Number::I64(n) => write!(f, "{n}"),
+            Number::I128(n) => write!(f, "{n}"),
+            Number::ISize(n) => write!(f, "{n}"),
+            Number::U8(n) => write!(f, "{n}"),
+            Number::U16(n) => write!(f, "{n}"),
+            Number::U32(n) => write!(f, "{n}"),
+            Number::U64(n) => write!(f, "{n}"),
+            Number::U128(n) => write!(f, "{n}"),
+            Number::USize(n) => write!(f, "{n}"),
+            Number::F32(n) => write!(f, "{n:?}"),
+            Number::F64(n) => write!(f, "{n:?}"),
+            Number::BigInt(n) => write!(f, "{n}"),
+            Number::Decimal(n) => write!(f, "{n}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Number;
+    use crate::{error::Error, io::run};
+
+    fn cast(n: Number, ty: &str) -> String {
+        let cast = n.cast(ty).unwrap();
+        format!("{cast} {}", cast.type_name())
+    }
+
+    #[test]
+    fn integers_keep_their_lowest_bits() {
+        assert_eq!(cast(Number::I32(300), "u8"), "44 u8");
+        assert_eq!(cast(Number::I32(-1), "u8"), "255 u8");
+        assert_eq!(cast(Number::U8(255), "i8"), "-1 i8");
+        assert_eq!(cast(Number::I8(-1), "u128"), format!("{} u128", u128::MAX));
+        assert_eq!(cast(Number::I8(-128), "i64"), "-128 i64");
+        let big = Number::parse("-340282366920938463463374607431768211457", "bigint").unwrap();
+        assert_eq!(cast(big, "i32"), "-1 i32");
+        let decimal = Number::parse("-1.9", "decimal").unwrap();
+        assert_eq!(cast(decimal, "u8"), "255 u8");
+    }
+
+    #[test]
+    fn floats_saturate_at_the_bounds_of_integers() {
+        assert_eq!(cast(Number::F64(3.9), "i32"), "3 i32");
+        assert_eq!(cast(Number::F64(-3.9), "i32"), "-3 i32");
+        assert_eq!(cast(Number::F64(1e10), "i32"), format!("{} i32", i32::MAX));
+        assert_eq!(cast(Number::F64(-1.0), "u8"), "0 u8");
+        assert_eq!(cast(Number::F64(f64::NAN), "i32"), "0 i32");
+        assert_eq!(cast(Number::F64(f64::NEG_INFINITY), "i8"), "-128 i8");
+    }
+
+    #[test]
+    fn integers_round_to_the_nearest_float() {
+        assert_eq!(
+            Number::I32(16_777_217).cast("f32").unwrap(),
+            Number::F32(16_777_216.0)
+        );
+        assert_eq!(Number::F64(0.1).cast("f32").unwrap(), Number::F32(0.1));
+        assert_eq!(
+            Number::F64(1e300).cast("f32").unwrap(),
+            Number::F32(f32::INFINITY)
+        );
+    }
+
+    #[test]
+    fn exact_types_keep_the_value() {
+        assert_eq!(
+            cast(Number::I64(i64::MIN), "bigint"),
+            format!("{} bigint", i64::MIN)
+        );
+        assert_eq!(cast(Number::F64(0.1), "decimal"), "0.1 decimal");
+        assert_eq!(cast(Number::F64(-2.5), "bigint"), "-2 bigint");
+        assert!(matches!(
+            Number::F64(f64::NAN).cast("bigint"),
+            Err(Error::NonFinite(..))
+        ));
+        assert!(matches!(
+            Number::F32(f32::INFINITY).cast("decimal"),
+            Err(Error::NonFinite(..))
+        ));
+    }
+
+    #[test]
+    fn only_numbers_are_cast_to() {
+        assert!(matches!(
+            Number::I32(1).cast("bool"),
+            Err(Error::InvalidCast(..))
+        ));
+        assert!(matches!(
+            Number::I32(1).cast("char"),
+            Err(Error::InvalidCast(..))
+        ));
+    }
+
+    #[test]
+    fn conversions_fail_when_the_value_changes() {
+        assert_eq!(Number::I32(200).convert("u8"), Some(Number::U8(200)));
+        assert_eq!(Number::I32(300).convert("u8"), None);
+        assert_eq!(Number::I32(-1).convert("u32"), None);
+        assert_eq!(Number::F64(2.5).convert("i32"), None);
+        assert_eq!(Number::F64(2.0).convert("i32"), Some(Number::I32(2)));
+        assert_eq!(Number::I64(16_777_217).convert("f32"), None);
+        assert!(Number::F64(f64::NAN).convert("f32").is_some());
+    }
+
+    #[test]
+    fn programs_cast_and_convert_numbers() {
+        let source =
+            "let x = 300; let nan = 0.0 / 0.0; (x as u8, -3.9 as i32, nan as i32, 255u8 as i8)";
+        assert_eq!(run(source).unwrap(), "(44, -3, 0, -1)");
+        let source = "(65u8 as char, 'a' as u32, true as u8, 300.to_u8(), 5.to_u8())";
+        assert_eq!(run(source).unwrap(), "('A', 97, 1, ?, 5)");
+        assert!(matches!(run("\"a\" as i32"), Err(Error::InvalidCast(..))));
+        // Only `u8` becomes a `char`
+        assert!(matches!(run("300 as char"), Err(Error::NumOverFlow(..))));
+    }
+}
@@ -0,0 +1,290 @@
+use super::{builtins::Native, environment::Env, map::Map};
+use crate::ast::{
+    stmt::{EnumDecl, FnDecl, StructDecl, TraitDecl},
+    token::{Literal, Number},
+};
+use ascii::AsciiString;
+use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc};
+
+#[derive(Clone)]
+pub enum Value {
+    Unit,
+    Null,
+    Char(char),
+    Boolean(bool),
+    Number(Number),
+    String(String),
+    Array(Rc<RefCell<Vec<Value>>>),
+    Tuple(Rc<Vec<Value>>),
+    Map(Rc<RefCell<Map>>),
+    Range(Range),
+    Function(Rc<Function>),
+    Native(Native),
+    Struct(Rc<Struct>),
+    Instance(Rc<RefCell<Instance>>),
+    Enum(Rc<Enum>),
+    Variant(Rc<Variant>),
+    Trait(Rc<Trait>),
+    // The function building a tuple variant, the variant is an index into the declaration
+    Constructor(Rc<Enum>, usize),
+}
+
+pub struct Function {
+    pub decl: Rc<FnDecl>,
+    pub closure: Env,
+}
+
+// Functions from the `impl` blocks of a type, methods and associated ones alike
+pub type Methods = RefCell<HashMap<AsciiString, Rc<Function>>>;
+
+// The traits of the `impl Trait for Type` blocks of a type
+pub type Traits = RefCell<Vec<Rc<TraitDecl>>>;
+
+pub struct Struct {
+    pub decl: Rc<StructDecl>,
+    pub methods: Methods,
+    pub traits: Traits,
+}
+
+pub struct Instance {
+    pub ty: Rc<Struct>,
+    // In the order the struct declares them
+    pub fields: Vec<(AsciiString, Value)>,
+}
+
+pub struct Enum {
+    pub decl: Rc<EnumDecl>,
+    pub methods: Methods,
+    pub traits: Traits,
+}
+
+// The methods a trait provides run where the trait is declared
+pub struct Trait {
+    pub decl: Rc<TraitDecl>,
+    pub closure: Env,
+}
+
+pub struct Variant {
+    pub ty: Rc<Enum>,
+    pub index: usize,
+    pub payload: Payload,
+}
+
+pub enum Payload {
+    Unit,
+    Tuple(Vec<Value>),
+    Struct(Vec<(AsciiString, Value)>),
+}
+
+impl Struct {
+    pub fn method(&self, name: &AsciiString) -> Option<Rc<Function>> {
+        self.methods.borrow().get(name).cloned()
+    }
+}
+
+impl Enum {
+    pub fn method(&self, name: &AsciiString) -> Option<Rc<Function>> {
+        self.methods.borrow().get(name).cloned()
+    }
+}
+
+impl Variant {
+    pub fn name(&self) -> &AsciiString {
+        &self.ty.decl.variants[self.index].name
+    }
+}
+
+impl Instance {
+    pub fn field(&mut self, name: &AsciiString) -> Option<&mut Value> {
+        self.fields
+            .iter_mut()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value)
+    }
+}
+
+#[derive(Clone)]
+pub struct Range {
+    pub start: Option<Number>,
+    pub end: Option<Number>,
+    pub inclusive: bool,
+}
+
+impl Value {
+    pub fn type_name(&self) -> String {
+        String::from(match self {
+            Value::Unit => "()",
+            Value::Null => "?",
+            Value::Char(_) => "char",
+            Value::Boolean(_) => "bool",
+            Value::Number(number) => number.type_name(),
+            Value::String(_) => "String",
+            Value::Array(array) => match array.borrow().first() {
+                Some(first) => return format!("[{}]", first.type_name()),
+                None => "[_]",
+            },
+            Value::Tuple(elements) => {
+                let types = elements.iter().map(Value::type_name).collect::<Vec<_>>();
+                return match types.len() {
+                    1 => format!("({},)", types[0]),
+                    _ => format!("({})", types.join(", ")),
+                };
+            }
+            Value::Map(map) => match map.borrow().entries().next() {
+                Some((key, value)) => {
+                    return format!("Map<{}, {}>", key.value().type_name(), value.type_name())
+                }
+                None => "Map<_, _>",
+            },
+            Value::Range(range) => match range.start.as_ref().or(range.end.as_ref()) {
+                Some(bound) => return format!("Range<{}>", bound.type_name()),
+                None => "Range",
+            },
+            Value::Function(_) | Value::Native(_) | Value::Constructor(..) => "fn",
+            Value::Struct(_) => "struct",
+            Value::Instance(instance) => return instance.borrow().ty.decl.name.to_string(),
+            Value::Enum(_) => "enum",
+            Value::Variant(variant) => return variant.ty.decl.name.to_string(),
+            Value::Trait(_) => "trait",
+        })
+    }
+}
+
+impl Value {
+    pub fn array(elements: Vec<Value>) -> Self {
+        Value::Array(Rc::new(RefCell::new(elements)))
+    }
+
+    // How the value is shown inside of a collection, where strings keep their quotes
+    pub fn repr(&self) -> String {
+        match self {
+            Value::Char(c) => format!("{c:?}"),
+            Value::String(s) => format!("{s:?}"),
+            value => value.to_string(),
+        }
+    }
+}
+
+impl From<Literal> for Value {
+    fn from(literal: Literal) -> Self {
+        match literal {
+            Literal::Null => Value::Null,
+            Literal::Char(c) => Value::Char(c),
+            Literal::Boolean(b) => Value::Boolean(b),
+            Literal::Number(n) => Value::Number(n),
+            Literal::Unsuffixed(n) => Value::Number(n.value()),
+            Literal::String(s) => Value::String(s),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Unit => write!(f, "()"),
+            Value::Null => write!(f, "?"),
+            Value::Char(c) => write!(f, "{c}"),
+            Value::Boolean(b) => write!(f, "{b}"),
+            Value::Number(number) => write!(f, "{number}"),
+            Value::String(s) => write!(f, "{s}"),
+            Value::Array(array) => {
+                let elements = array.borrow().iter().map(Value::repr).collect::<Vec<_>>();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            Value::Tuple(elements) => {
+                let elements = elements.iter().map(Value::repr).collect::<Vec<_>>();
+                match elements.len() {
+                    1 => write!(f, "({},)", elements[0]),
+                    _ => write!(f, "({})", elements.join(", ")),
+                }
+            }
+            Value::Map(map) => {
+                let entries = map
+                    .borrow()
+                    .entries()
+                    .map(|(key, value)| format!("{}: {}", key.value().repr(), value.repr()))
+                    .collect::<Vec<_>>();
+                write!(f, "#{{{}}}", entries.join(", "))
+            }
+            Value::Range(range) => {
+                if let Some(start) = &range.start {
+                    write!(f, "{start}")?;
+                }
+                write!(f, "{}", if range.inclusive { "..=" } else { ".." })?;
+                if let Some(end) = &range.end {
+                    write!(f, "{end}")?;
+                }
+                Ok(())
+            }
+            Value::Function(function) => write!(f, "<fn {}>", function.decl.name),
+            Value::Native(native) => write!(f, "<native fn {}>", native.name),
+            Value::Struct(ty) => write!(f, "<struct {}>", ty.decl.name),
+            Value::Instance(instance) => {
+                let instance = instance.borrow();
+                write_fields(f, instance.ty.decl.name.as_str(), &instance.fields)
+            }
+            Value::Enum(ty) => write!(f, "<enum {}>", ty.decl.name),
+            // Variants are shown with the path their constructors and patterns take
+            Value::Variant(variant) => {
+                let path = variant.ty.decl.path(variant.index);
+                match &variant.payload {
+                    Payload::Unit => write!(f, "{path}"),
+                    Payload::Tuple(values) => {
+                        let values = values.iter().map(Value::repr).collect::<Vec<_>>();
+                        write!(f, "{path}({})", values.join(", "))
+                    }
+                    Payload::Struct(fields) => write_fields(f, &path, fields),
+                }
+            }
+            Value::Constructor(ty, index) => write!(f, "<fn {}>", ty.decl.path(*index)),
+            Value::Trait(trait_) => write!(f, "<trait {}>", trait_.decl.name),
+        }
+    }
+}
+
+// `Name { field: value, ... }`
+fn write_fields(
+    f: &mut std::fmt::Formatter<'_>,
+    name: &str,
+    fields: &[(AsciiString, Value)],
+) -> std::fmt::Result {
+    let fields = fields
+        .iter()
+        .map(|(name, value)| format!("{name}: {}", value.repr()))
+        .collect::<Vec<_>>();
+    if fields.is_empty() {
+        write!(f, "{name} {{}}")
+    } else {
+        write!(f, "{name} {{ {} }}", fields.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::Error, io::run};
+
+    #[test]
+    fn null_is_written_as_in_source() {
+        assert_eq!(run("?").unwrap(), "?");
+        assert_eq!(run("let x: i32? = ?; [x]").unwrap(), "[?]");
+        assert_eq!(run("let x: i32? = ?; #{x: 1}").unwrap(), "#{?: 1}");
+        let found = match run("\"a\" + ?") {
+            Err(Error::InvalidOperation(_, _, found)) => found,
+            _ => panic!("`?` is added to a string"),
+        };
+        assert_eq!(found, "?");
+    }
+
+    #[test]
+    fn variants_are_written_with_their_enum() {
+        let shown = |source: &str| run(source).unwrap();
+        let decl = "enum E { A, B(i32, String), C { x: char } }";
+        assert_eq!(shown(&format!("{decl} E::A")), "E::A");
+        assert_eq!(shown(&format!("{decl} E::B(1, \"b\")")), "E::B(1, \"b\")");
+        assert_eq!(
+            shown(&format!("{decl} [E::C {{ x: 'c' }}]")),
+            "[E::C { x: 'c' }]"
+        );
+        assert_eq!(shown("1.cmp(2)"), "Ordering::Less");
+    }
+}
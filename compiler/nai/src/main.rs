@@ -1,18 +1,61 @@
 use color_print::cformat;
-use compiler::{error::Result, io};
-use std::env;
+use compiler::{
+    error::{Error, Level, Levels, Lint, Result},
+    io,
+};
+use std::{env, thread};
 
+// Calls nest the interpreter deeply, so programs run on a thread with room
+// for the deepest recursion the interpreter allows
+const STACK_SIZE: usize = 1 << 30;
+
+// `-W lint` makes a lint warn and `-D lint` fails the program on its
+// warnings, `warnings` sets every lint
 fn main() -> Result<()> {
     let args: Vec<String> = env::args().collect();
-    match args.len().cmp(&2) {
-        std::cmp::Ordering::Less => io::repl(),
-        std::cmp::Ordering::Equal => io::script(&args[1]),
-        std::cmp::Ordering::Greater => {
-            eprintln!(
-                "{}",
-                cformat!("<y>Usage</>: <u>{}</> <dim><<script>></>", args[0])
-            );
-            Ok(())
+    let mut levels = Levels::default();
+    let mut scripts = Vec::new();
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        let level = match arg.as_str() {
+            "-W" => Level::Warn,
+            "-D" => Level::Deny,
+            _ => {
+                scripts.push(arg);
+                continue;
+            }
+        };
+        let Some(name) = rest.next() else {
+            return usage(&args[0]);
+        };
+        match (name.as_str(), Lint::named(name)) {
+            ("warnings", _) => Lint::ALL
+                .into_iter()
+                .for_each(|lint| levels.set(lint, level)),
+            (_, Some(lint)) => levels.set(lint, level),
+            (_, None) => return Err(Error::UnknownLint(name.clone())),
         }
     }
+    let script = match scripts[..] {
+        [] => None,
+        [script] => Some(script.clone()),
+        _ => return usage(&args[0]),
+    };
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || match script {
+            Some(script) => io::script(&script, levels),
+            None => io::repl(levels),
+        })
+        .map_err(|e| Error::ThreadSpawn(e.to_string()))?
+        .join()
+        .unwrap()
+}
+
+fn usage(program: &str) -> Result<()> {
+    eprintln!(
+        "{}",
+        cformat!("<y>Usage</>: <u>{program}</> <dim>[-W <<lint>>] [-D <<lint>>] <<script>></>")
+    );
+    Ok(())
 }